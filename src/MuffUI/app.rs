@@ -1,11 +1,11 @@
 #![allow(non_snake_case)]
 
 use std::sync::Arc;
-use std::sync::Once;
-use std::mem::MaybeUninit;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::sync::Mutex;
-use crate::Win;
+use std::time::{Duration, Instant};
 use crate::muffui::*;
 
 pub trait MainApp {
@@ -13,70 +13,253 @@ pub trait MainApp {
     fn view(&self) -> Option<Self::View>;
 }
 
+struct Timer {
+    id: usize,
+    due: Instant,
+    /// repeats with this period, runs once without it
+    interval: Option<Duration>,
+    callback: Box<dyn FnMut()>,
+}
+
 pub struct App {
-    
+    backend: Rc<dyn Backend>,
+    context: Rc<RefCell<Option<Box<UIContext>>>>,
+    timers: RefCell<Vec<Timer>>,
+    lastTimer: Cell<usize>,
+    /// next time the view is polled for `Renderable::reload`, with the period
+    reload: Cell<Option<(Instant, Duration)>>,
+    /// errors the view has reported, see `takeErrors`
+    errors: Rc<RefCell<Vec<String>>>,
 }
 
 impl App where Self: MainApp {
     pub fn new() -> Self {
+        Self::withBackend(defaultBackend())
+    }
+
+    pub fn withBackend(backend: Rc<dyn Backend>) -> Self {
         Self {
-            
+            backend,
+            context: Rc::new(RefCell::new(None)),
+            timers: RefCell::new(vec![]),
+            lastTimer: Cell::new(0),
+            reload: Cell::new(None),
+            errors: Rc::new(RefCell::new(vec![])),
         }
     }
 
     pub fn shared() -> &'static Mutex<App> {
-        static mut CONF: MaybeUninit<Mutex<App>> = MaybeUninit::uninit();
-        static ONCE: Once = Once::new();
-    
-        ONCE.call_once(|| unsafe {
-            CONF.as_mut_ptr().write(Mutex::new(App::new()));
-        });
-
-        unsafe { &*CONF.as_ptr() }
-    }
-
-    pub fn run(&mut self) -> windows::core::Result<()> {
-        Win::CoInitializeEx()?;
-
-        let mut context = UIContext::create();
-        if let Some(view) = self.view() {
-            context = view.render(context, "/", "0", None);
-            Arc::get_mut(Notifier::shared()).map(|e|e.register(move|msg|{
-                EventHub::shared().try_lock().ok().as_mut().map(|e|{
-                    if let Some(msg) = msg {
-                        let msg = msg.clone();
-                        e.enqueueEvent(msg);
-                        let mut prev = context.clone();
-                        prev.prevItems = HashMap::new();
-                        context = view.render(prev, "/", "0", Some(msg));
-                        context = context.clone().clean();
-                        for (_, ci) in context.items.iter() {
-                            e.putListener(ci.hwnd, ci.listeners.clone());
-                        }
-                    }
-                    if e.dispatchEvents() {
-                        context = view.render(context.clone(), "/", "0", None);
-                    }
-                });
-            }));
+        thread_local! {
+            static CONF: &'static Mutex<App> = Box::leak(Box::new(Mutex::new(App::new())));
+        }
 
-            let mut msg = Win::MSG { ..Default::default() };
-            while Win::WM_QUIT != msg.message {
-                if let Some(msg2) = Win::PeekMessage() {
-                    msg = msg2;
+        CONF.with(|conf|*conf)
+    }
 
-                    Win::TranslateMessage(&mut msg);
-                    Win::DispatchMessage(&mut msg);
+    pub fn run(&mut self) -> Result<(), String> {
+        if !self.mount()? {
+            return Ok(());
+        }
 
-                    Arc::get_mut(Notifier::shared()).map(|e|e.notify(Some(msg)));
-                } else {
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                    Arc::get_mut(Notifier::shared()).map(|e|e.notify(None));
-                }
+        // sleeps until a message comes, from the window system or a `WakeHandle`, or a timer is due
+        let backend = self.backend.clone();
+        loop {
+            match backend.waitMessage(self.nextTimeout()) {
+                Some(msg) if WM_QUIT == msg.message => break,
+                Some(msg) => self.handleMessage(msg),
+                _ => (),
             }
+            self.runTimers();
+            for e in self.takeErrors() {
+                eprintln!("{}", e);
+            }
+        }
+
+        if let Ok(path) = std::env::var("MUFFUI_LAYOUT_DUMP") {
+            LayoutDebug::save(&path).map_err(|e|format!("can't save layout dump {}: {}", path, e))?;
+        }
+        if let Ok(path) = std::env::var("MUFFUI_EVENT_LOG") {
+            EventRecorder::save(&path).map_err(|e|format!("can't save event log {}: {}", path, e))?;
         }
 
         Ok(())
     }
-}
 
+    /// a wake only renders the view again, anything else goes through `routeMessage`
+    fn handleMessage(&self, msg: Message) {
+        if WM_WAKE == msg.message {
+            if let Some(e) = Arc::get_mut(Notifier::shared()) {
+                e.notify(Some(msg));
+            }
+        } else {
+            routeMessage(&*self.backend, msg);
+        }
+    }
+
+    /// renders the view and subscribes it to messages, false when there is no view
+    pub fn mount(&mut self) -> Result<bool, String> {
+        match self.view() {
+            Some(view) => self.mountView(view).map(|_|true),
+            _ => Ok(false),
+        }
+    }
+
+    /// same as `mount`, but with `view` instead of the view of the app
+    pub fn mountView<V: Renderable + 'static>(&mut self, view: V) -> Result<(), String> {
+        let backend = self.backend.clone();
+        backend.initialize()?;
+        setCurrentBackend(backend.clone());
+
+        // MUFFUI_LAYOUT_DUMP=<file> records the layout of every render pass, MUFFUI_LAYOUT_OUTLINES=1 also outlines it
+        let outlines = std::env::var("MUFFUI_LAYOUT_OUTLINES").map(|v|v == "1").unwrap_or(false);
+        if outlines || std::env::var("MUFFUI_LAYOUT_DUMP").is_ok() {
+            LayoutDebug::enable(outlines);
+        }
+        // MUFFUI_EVENT_LOG=<file> records the events of the session, TestDriver::replay plays them back
+        if std::env::var("MUFFUI_EVENT_LOG").is_ok() {
+            EventRecorder::enable();
+        }
+        let context = view.render(UIContext::create(backend), "/", "0", None);
+        LayoutDebug::record(&context);
+        *self.context.borrow_mut() = Some(context);
+        self.reload.set(view.reloadInterval().map(|interval|(Instant::now() + interval, interval)));
+        let current = self.context.clone();
+        let errors = self.errors.clone();
+        if let Some(e) = Arc::get_mut(Notifier::shared()) {
+            e.register(move|msg|{
+                let mut e = match EventHub::shared().try_lock() {
+                    Ok(e) => e,
+                    _ => return,
+                };
+                let mut current = current.borrow_mut();
+                let mut context = match current.take() {
+                    Some(context) => context,
+                    _ => return,
+                };
+                let backend = context.backend.clone();
+                let woken = msg.map(|msg|WM_WAKE == msg.message).unwrap_or(false);
+                if let Some(msg) = msg.filter(|_|!woken) {
+                    e.enqueueEvent(&*backend, msg);
+                    let mut prev = context.clone();
+                    prev.prevItems = HashMap::new();
+                    context = view.render(prev, "/", "0", Some(msg));
+                    context = context.clone().clean();
+                    e.putListeners(&context);
+                }
+                let reloaded = msg.is_none() && view.reload().unwrap_or_else(|e|{
+                    errors.borrow_mut().push(e);
+                    false
+                });
+                if e.dispatchEvents(&*backend) || woken || reloaded {
+                    context = context.update(&view);
+                }
+                *current = Some(context);
+            });
+        }
+
+        Ok(())
+    }
+
+    /// handles the queued messages and the due timers without waiting and ends with an idle tick, false once WM_QUIT is seen
+    #[allow(dead_code)]
+    pub fn pumpMessages(&mut self) -> bool {
+        while let Some(msg) = self.backend.peekMessage() {
+            if WM_QUIT == msg.message {
+                return false;
+            }
+            self.handleMessage(msg);
+        }
+        self.runTimers();
+        if let Some(e) = Arc::get_mut(Notifier::shared()) {
+            e.notify(None);
+        }
+
+        true
+    }
+
+    /// errors the view has reported since the last call, e.g. a layout file that fails to load, `run` prints them
+    #[allow(dead_code)]
+    pub fn takeErrors(&self) -> Vec<String> {
+        self.errors.take()
+    }
+
+    /// calls `callback` once after `delay` and renders the view again, the id is for `clearTimer`
+    #[allow(dead_code)]
+    pub fn setTimeout<F: FnMut() + 'static>(&self, delay: Duration, callback: F) -> usize {
+        self.addTimer(delay, None, Box::new(callback))
+    }
+
+    /// calls `callback` every `interval` and renders the view again, the id is for `clearTimer`
+    #[allow(dead_code)]
+    pub fn setInterval<F: FnMut() + 'static>(&self, interval: Duration, callback: F) -> usize {
+        self.addTimer(interval, Some(interval), Box::new(callback))
+    }
+
+    #[allow(dead_code)]
+    pub fn clearTimer(&self, id: usize) {
+        self.timers.borrow_mut().retain(|t|t.id != id);
+    }
+
+    /// handle other threads post messages to the app and wake it up with, see `WakeHandle::wake`
+    #[allow(dead_code)]
+    pub fn wakeHandle(&self) -> WakeHandle {
+        self.backend.wakeHandle()
+    }
+
+    fn addTimer(&self, delay: Duration, interval: Option<Duration>, callback: Box<dyn FnMut()>) -> usize {
+        let id = self.lastTimer.get() + 1;
+        self.lastTimer.set(id);
+        self.timers.borrow_mut().push(Timer { id, due: Instant::now() + delay, interval, callback });
+
+        id
+    }
+
+    /// time left until the next timer or reload poll, None when there is nothing to wait for
+    fn nextTimeout(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.timers.borrow().iter().map(|t|t.due)
+            .chain(self.reload.get().map(|(due, _)|due))
+            .min()
+            .map(|due|due.saturating_duration_since(now))
+    }
+
+    /// calls the due timers and polls the view for reload when it is time to
+    fn runTimers(&self) {
+        let now = Instant::now();
+        let (mut due, rest) = self.timers.take().into_iter().partition::<Vec<_>, _>(|t|t.due <= now);
+        *self.timers.borrow_mut() = rest;
+        for timer in due.iter_mut() {
+            (timer.callback)();
+        }
+        let fired = !due.is_empty();
+        // appended, the callbacks could have set other timers meanwhile
+        self.timers.borrow_mut().extend(due.into_iter().filter_map(|t|t.interval.map(|interval|Timer { due: now + interval, ..t })));
+        if fired {
+            self.handleMessage(Message { message: WM_WAKE, ..Default::default() });
+        }
+
+        if let Some((due, interval)) = self.reload.get().filter(|(due, _)|*due <= now) {
+            self.reload.set(Some((now.max(due) + interval, interval)));
+            if let Some(e) = Arc::get_mut(Notifier::shared()) {
+                e.notify(None);
+            }
+        }
+    }
+
+    /// rendered controls by their path in the view tree, e.g. "0:1_2[3]"
+    #[allow(dead_code)]
+    pub fn controls(&self) -> Vec<(String, Handle)> {
+        let mut controls = self.context.borrow().as_ref()
+            .map(|context|context.items.iter().map(|(path, ci)|(path.clone(), ci.hwnd)).collect::<Vec<_>>())
+            .unwrap_or_default();
+        controls.sort_by(|(a, _), (b, _)|a.cmp(b));
+
+        controls
+    }
+
+    /// text dump of the rendered tree, see `UIContext::snapshot`
+    #[allow(dead_code)]
+    pub fn snapshot(&self) -> String {
+        self.context.borrow().as_ref().map(|context|context.snapshot()).unwrap_or_default()
+    }
+}
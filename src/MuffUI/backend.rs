@@ -0,0 +1,250 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::muffui::Modifiers;
+
+/// opaque handle of a native control (HWND on win32)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Handle(pub isize);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Size {
+    pub cx: i32,
+    pub cy: i32,
+}
+
+/// platform neutral message, numbering follows win32 so native messages pass through untouched
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Message {
+    pub hwnd: Handle,
+    pub message: u32,
+    pub wParam: usize,
+    pub lParam: isize,
+}
+
+pub const CW_USEDEFAULT: i32 = -2147483648i32;
+
+pub const WM_CREATE: u32 = 0x0001;
+pub const WM_DESTROY: u32 = 0x0002;
+pub const WM_SIZE: u32 = 0x0005;
+pub const WM_SETFOCUS: u32 = 0x0007;
+pub const WM_KILLFOCUS: u32 = 0x0008;
+pub const WM_QUIT: u32 = 0x0012;
+pub const WM_SETCURSOR: u32 = 0x0020;
+pub const WM_DISPLAYCHANGE: u32 = 0x007E;
+pub const WM_NCHITTEST: u32 = 0x0084;
+pub const WM_NCACTIVATE: u32 = 0x0086;
+pub const BM_CLICK: u32 = 0x00F5;
+pub const WM_KEYDOWN: u32 = 0x0100;
+pub const WM_KEYUP: u32 = 0x0101;
+pub const WM_CHAR: u32 = 0x0102;
+pub const WM_SYSKEYDOWN: u32 = 0x0104;
+pub const WM_SYSKEYUP: u32 = 0x0105;
+pub const WM_COMMAND: u32 = 0x0111;
+pub const WM_HSCROLL: u32 = 0x0114;
+pub const WM_VSCROLL: u32 = 0x0115;
+pub const WM_CTLCOLOREDIT: u32 = 0x0133;
+pub const WM_CTLCOLORLISTBOX: u32 = 0x0134;
+pub const WM_MOUSEMOVE: u32 = 0x0200;
+pub const WM_LBUTTONDOWN: u32 = 0x0201;
+pub const WM_LBUTTONUP: u32 = 0x0202;
+pub const WM_RBUTTONDOWN: u32 = 0x0204;
+pub const WM_RBUTTONUP: u32 = 0x0205;
+pub const WM_MBUTTONDOWN: u32 = 0x0207;
+pub const WM_MBUTTONUP: u32 = 0x0208;
+pub const WM_MOUSEWHEEL: u32 = 0x020A;
+pub const WM_SIZING: u32 = 0x0214;
+pub const WM_MOUSELEAVE: u32 = 0x02A3;
+pub const WM_DPICHANGED: u32 = 0x02E0;
+pub const WM_APP: u32 = 0x8000;
+/// posted to a split every time its divider has been dragged
+pub const WM_SPLITMOVE: u32 = WM_APP + 1;
+/// the mouse has moved over a control, win32 has no such message so the event hub makes it of the moves
+pub const WM_MOUSEENTER: u32 = WM_APP + 2;
+/// posted by `WakeHandle::wake`, the app renders the view again without handing it to any control
+pub const WM_WAKE: u32 = WM_APP + 3;
+
+pub const CBN_SELCHANGE: u32 = 1;
+pub const CBN_SETFOCUS: u32 = 3;
+pub const CBN_KILLFOCUS: u32 = 4;
+pub const BN_SETFOCUS: u32 = 6;
+pub const BN_KILLFOCUS: u32 = 7;
+pub const EN_SETFOCUS: u32 = 0x0100;
+pub const EN_KILLFOCUS: u32 = 0x0200;
+
+/// mouse buttons and keys held down, in the wParam of mouse messages
+pub const MK_LBUTTON: usize = 0x0001;
+pub const MK_RBUTTON: usize = 0x0002;
+pub const MK_SHIFT: usize = 0x0004;
+pub const MK_CONTROL: usize = 0x0008;
+pub const MK_MBUTTON: usize = 0x0010;
+
+pub const VK_RETURN: u32 = 0x0D;
+pub const VK_SPACE: u32 = 0x20;
+
+pub const SB_LINEUP: u32 = 0;
+pub const SB_LINEDOWN: u32 = 1;
+pub const SB_PAGEUP: u32 = 2;
+pub const SB_PAGEDOWN: u32 = 3;
+pub const SB_THUMBPOSITION: u32 = 4;
+pub const SB_THUMBTRACK: u32 = 5;
+pub const SB_TOP: u32 = 6;
+pub const SB_BOTTOM: u32 = 7;
+/// mouse wheel rotation of one notch
+pub const WHEEL_DELTA: i32 = 120;
+
+#[allow(dead_code)]
+#[inline]
+pub fn LOWORD(l: usize) -> usize {
+    l & 0xffff
+}
+#[inline]
+pub fn HIWORD(l: usize) -> usize {
+    (l >> 16) & 0xffff
+}
+
+/// everything the backend needs to create a native control
+#[derive(Debug)]
+pub struct ControlDesc<'a> {
+    pub renderer: &'a str,
+    pub className: &'a str,
+    pub title: &'a str,
+    pub controlId: i32,
+    pub anchor: usize,
+    pub posX: i32,
+    pub posY: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// native window system used by UIContext, EventHub and AnchorMap
+pub trait Backend {
+    fn initialize(&self) -> Result<(), String>;
+    fn systemFontFace(&self) -> String;
+    /// height of the system font in pixels at the system dpi
+    fn systemFontSize(&self) -> i32;
+    /// dpi of the monitor new windows open on
+    fn systemDpi(&self) -> u32;
+    /// dpi of the monitor the window of `hwnd` is on
+    fn getDpi(&self, hwnd: Handle) -> u32;
+
+    fn createControl(&self, parent: Option<Handle>, desc: &ControlDesc) -> Handle;
+    fn destroyControl(&self, hwnd: Handle) -> bool;
+
+    fn getParent(&self, hwnd: Handle) -> Handle;
+    fn getChildren(&self, hwnd: Handle) -> Vec<Handle>;
+    fn getControlId(&self, hwnd: Handle) -> i32;
+    fn getClassName(&self, hwnd: Handle) -> Option<String>;
+    fn getTitle(&self, hwnd: Handle) -> String;
+    fn setTitle(&self, hwnd: Handle, title: &str);
+    /// keeps `hFont` when it already has the face and the size in pixels, otherwise replaces it
+    fn updateFont(&self, hwnd: Handle, hFont: Option<Handle>, fontFace: &str, fontSize: i32) -> Option<Handle>;
+    /// size of a single line of `text` drawn in `fontFace` of `fontSize` pixels, the height of a line for empty text
+    fn measureText(&self, text: &str, fontFace: &str, fontSize: i32) -> Size;
+    fn isSelected(&self, hwnd: Handle) -> bool;
+    fn setSelected(&self, hwnd: Handle, selected: bool);
+    fn getSelectItems(&self, hwnd: Handle) -> Vec<String>;
+    fn setSelectItems(&self, hwnd: Handle, items: &[String]);
+    fn getSelectedIndex(&self, hwnd: Handle) -> Option<usize>;
+    fn setSelectedIndex(&self, hwnd: Handle, index: usize);
+    fn getSelectItemText(&self, hwnd: Handle, index: usize) -> Option<String>;
+
+    /// outer rect in screen coordinates
+    fn getWindowRect(&self, hwnd: Handle) -> Option<Rect>;
+    fn getClientRect(&self, hwnd: Handle) -> Option<Rect>;
+    /// outer rect in the client coordinates of the parent
+    fn getControlRect(&self, hwnd: Handle) -> Option<Rect>;
+    /// grows a client rect with the non-client area of the window
+    fn adjustWindowRect(&self, hwnd: Handle, rect: Rect) -> Rect;
+    fn moveControl(&self, hwnd: Handle, rect: &Rect) -> bool;
+    fn isMaximized(&self, hwnd: Handle) -> bool;
+    fn isVisible(&self, hwnd: Handle) -> bool;
+    fn setVisible(&self, hwnd: Handle, visible: bool);
+    fn sizeGripSize(&self) -> Size;
+    /// shows the scroll bars of a scroll view with `content` of which `viewport` is visible from `offset`
+    fn setScrollInfo(&self, hwnd: Handle, content: Size, viewport: Size, offset: Point);
+    /// outlines `rects` in the client area of `hwnd` for layout debugging, they last until it is painted again
+    fn drawOutlines(&self, hwnd: Handle, rects: &[Rect]);
+    /// Shift, Ctrl and Alt held down while the message being handled was sent
+    fn modifiers(&self) -> Modifiers;
+
+    fn postMessage(&self, msg: Message);
+    fn peekMessage(&self) -> Option<Message>;
+    /// blocks until a message comes or `timeout` has passed, waits for a message as long as it takes without one
+    fn waitMessage(&self, timeout: Option<Duration>) -> Option<Message>;
+    fn dispatchMessage(&self, msg: &Message);
+    /// lets other threads post messages to the thread this backend runs on, ask for it on that thread
+    fn wakeHandle(&self) -> WakeHandle;
+}
+
+/// posts messages to the UI thread from any thread and wakes its message loop up
+#[derive(Clone)]
+pub struct WakeHandle {
+    post: Arc<dyn Fn(Message) + Send + Sync>,
+}
+
+impl WakeHandle {
+    pub fn new<F: Fn(Message) + Send + Sync + 'static>(post: F) -> Self {
+        Self { post: Arc::new(post) }
+    }
+
+    pub fn postMessage(&self, msg: Message) {
+        (self.post)(msg)
+    }
+
+    /// renders the view again, e.g. after a worker thread has changed the state it shows
+    #[allow(dead_code)]
+    pub fn wake(&self) {
+        self.postMessage(Message { message: WM_WAKE, ..Default::default() });
+    }
+
+    #[allow(dead_code)]
+    pub fn quit(&self, code: i32) {
+        self.postMessage(Message { message: WM_QUIT, wParam: code as usize, ..Default::default() });
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Rc<dyn Backend>>> = RefCell::new(None);
+}
+
+pub fn setCurrentBackend(backend: Rc<dyn Backend>) {
+    CURRENT.with(|current|*current.borrow_mut() = Some(backend));
+}
+
+pub fn currentBackend() -> Option<Rc<dyn Backend>> {
+    CURRENT.with(|current|current.borrow().clone())
+}
+
+#[cfg(windows)]
+pub fn defaultBackend() -> Rc<dyn Backend> {
+    Rc::new(crate::muffui::Win32Backend)
+}
+
+#[cfg(not(windows))]
+pub fn defaultBackend() -> Rc<dyn Backend> {
+    Rc::new(crate::muffui::HeadlessBackend::new())
+}
+
+pub fn PostQuitMessage(code: i32) {
+    if let Some(backend) = currentBackend() {
+        backend.postMessage(Message { message: WM_QUIT, wParam: code as usize, ..Default::default() });
+    }
+}
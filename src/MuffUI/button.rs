@@ -1,37 +1,26 @@
 #![allow(non_snake_case)]
 
-use std::sync::Arc;
 use std::rc::Rc;
-use std::sync::Mutex;
-use crate::Win;
 use crate::muffui::*;
 use crate::muffui::SharedProps::*;
 
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Button {
     pub props: Vec<SharedProps>,
 }
 
-impl Default for Button {
-    fn default() -> Self {
-        Self {
-            props: vec![],
-        }
-    }
-}
-
 impl Renderable for Button {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         None
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         context.render(parent, index, self, msg)
     }
 
     fn toViewState(&self) -> Vec<SharedProps> {
-        self.props.iter().map(|item|item.clone()).collect()
+        self.props.to_vec()
     }
 }
 
@@ -49,42 +38,48 @@ impl Button {
     pub fn title(self, title: &str) -> Self {
         Self {
             props: self.props.merge(SP::Title(title)),
-            ..self
         }
     }
 
+    #[allow(dead_code)]
     pub fn posX(self, posX: i32) -> Self {
         Self {
             props: self.props.merge(PosX(posX)),
-            ..self
         }
     }
 
+    #[allow(dead_code)]
     pub fn posY(self, posY: i32) -> Self {
         Self {
             props: self.props.merge(PosY(posY)),
-            ..self
         }
     }
 
+    #[allow(dead_code)]
     pub fn width(self, width: i32) -> Self {
         Self {
             props: self.props.merge(Width(width)),
-            ..self
         }
     }
 
+    #[allow(dead_code)]
     pub fn height(self, height: i32) -> Self {
         Self {
             props: self.props.merge(Height(height)),
-            ..self
         }
     }
 
+    #[allow(dead_code)]
     pub fn content<C: Into<Command<Vec<SharedProps>>>>(self, handler: C) -> Self {
         Self {
-            props: self.props.merge(SharedProps::DidClick(Arc::new(Mutex::new(handler.into())))),
-            ..self
+            props: self.props.merge(SP::DidClick(handler)),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn onClick<C: Into<Command<ClickEvent>>>(self, handler: C) -> Self {
+        Self {
+            props: self.props.merge(SP::OnClick(handler)),
         }
     }
 }
@@ -1,34 +1,22 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
-use std::sync::Mutex;
-use std::sync::Arc;
-use crate::Win;
 use crate::muffui::*;
 use crate::muffui::SharedProps::*;
 
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CheckBox {
     pub children: Option<Rc<dyn Renderable>>,
     pub props: Vec<SharedProps>,
 }
 
-impl Default for CheckBox {
-    fn default() -> Self {
-        Self {
-            children: Default::default(),
-            props: vec![],
-        }
-    }
-}
-
 impl Renderable for CheckBox {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         Some(self.children.as_ref()?.clone())
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let context = context.render(parent, index, self, msg);
         match &self.children {
             Some(children) => children.render(context, index, &format!("{}:1", index), msg),
@@ -37,7 +25,7 @@ impl Renderable for CheckBox {
     }
 
     fn toViewState(&self) -> Vec<SharedProps> {
-        self.props.iter().map(|item|item.clone()).collect()
+        self.props.to_vec()
     }
 }
 
@@ -60,6 +48,7 @@ impl CheckBox {
         }
     }
 
+    #[allow(dead_code)]
     pub fn posX(self, posX: i32) -> Self {
         Self {
             props: self.props.merge(PosX(posX)),
@@ -67,6 +56,7 @@ impl CheckBox {
         }
     }
 
+    #[allow(dead_code)]
     pub fn posY(self, posY: i32) -> Self {
         Self {
             props: self.props.merge(PosY(posY)),
@@ -74,6 +64,7 @@ impl CheckBox {
         }
     }
 
+    #[allow(dead_code)]
     pub fn width(self, width: i32) -> Self {
         Self {
             props: self.props.merge(Width(width)),
@@ -81,6 +72,7 @@ impl CheckBox {
         }
     }
 
+    #[allow(dead_code)]
     pub fn height(self, height: i32) -> Self {
         Self {
             props: self.props.merge(Height(height)),
@@ -90,14 +82,23 @@ impl CheckBox {
     #[allow(dead_code)]
     pub fn didChange<C: Into<Command<Vec<SharedProps>>>>(self, handler: C) -> Self {
         Self {
-            props: self.props.merge(SharedProps::DidChange(Arc::new(Mutex::new(handler.into())))),
+            props: self.props.merge(SP::DidChange(handler)),
             ..self
         }
     }
 
+    #[allow(dead_code)]
     pub fn content<C: Into<Command<Vec<SharedProps>>>>(self, handler: C) -> Self {
         Self {
-            props: self.props.merge(SharedProps::DidClick(Arc::new(Mutex::new(handler.into())))),
+            props: self.props.merge(SP::DidClick(handler)),
+            ..self
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn onClick<C: Into<Command<ClickEvent>>>(self, handler: C) -> Self {
+        Self {
+            props: self.props.merge(SP::OnClick(handler)),
             ..self
         }
     }
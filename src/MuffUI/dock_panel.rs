@@ -0,0 +1,214 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use crate::muffui::*;
+
+/// side of the room left by the earlier children a DockPanel child takes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dock {
+    #[default]
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DockStyle {
+    pub spacing: i32,
+    pub padding: i32,
+}
+
+impl From<&Vec<SharedProps>> for DockStyle {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        props.iter().fold(Self { ..Default::default() }, |style, prop|match prop {
+            SharedProps::Spacing(spacing) => Self { spacing: *spacing, ..style },
+            SharedProps::Padding(padding) => Self { padding: *padding, ..style },
+            _ => style,
+        })
+    }
+}
+
+/// preferred size of a child and the side it docks to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DockItem {
+    pub width: i32,
+    pub height: i32,
+    pub dock: Dock,
+    pub limits: SizeLimits,
+}
+
+impl From<&Vec<SharedProps>> for DockItem {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        let size = |v: i32| if v == CW_USEDEFAULT { 0 } else { v.max(0) };
+        let item = Self { limits: SizeLimits::from(props), ..Default::default() };
+        props.iter().fold(item, |item, prop|match prop {
+            SharedProps::Width(width) => Self { width: size(*width), ..item },
+            SharedProps::Height(height) => Self { height: size(*height), ..item },
+            SharedProps::Dock(dock) => Self { dock: *dock, ..item },
+            // a single ANF_DOCK_* side means the same as Dock
+            SharedProps::Anchor(anchor) => match anchor & ANF_DOCK_ALL {
+                ANF_DOCK_LEFT => Self { dock: Dock::Left, ..item },
+                ANF_DOCK_TOP => Self { dock: Dock::Top, ..item },
+                ANF_DOCK_RIGHT => Self { dock: Dock::Right, ..item },
+                ANF_DOCK_BOTTOM => Self { dock: Dock::Bottom, ..item },
+                _ => item,
+            },
+            _ => item,
+        })
+    }
+}
+
+/// rects of the children of a dock panel with `size` client area, every child takes its side
+/// of the room the children before it have left and the last one fills what remains
+pub fn layoutDock(style: &DockStyle, size: Size, items: &[DockItem]) -> Vec<Rect> {
+    let mut room = Rect {
+        left: style.padding, top: style.padding,
+        right: (size.cx - style.padding).max(style.padding),
+        bottom: (size.cy - style.padding).max(style.padding),
+    };
+
+    items.iter().enumerate().map(|(idx, item)|{
+        if idx + 1 == items.len() {
+            return room;
+        }
+        let width = item.width.min(item.limits.maxWidth).max(item.limits.minWidth).min(room.right - room.left);
+        let height = item.height.min(item.limits.maxHeight).max(item.limits.minHeight).min(room.bottom - room.top);
+        let (rect, left) = match item.dock {
+            Dock::Left => (Rect { right: room.left + width, ..room }, Rect { left: room.left + width + style.spacing, ..room }),
+            Dock::Top => (Rect { bottom: room.top + height, ..room }, Rect { top: room.top + height + style.spacing, ..room }),
+            Dock::Right => (Rect { left: room.right - width, ..room }, Rect { right: room.right - width - style.spacing, ..room }),
+            Dock::Bottom => (Rect { top: room.bottom - height, ..room }, Rect { bottom: room.bottom - height - style.spacing, ..room }),
+        };
+        // the spacing never turns the room inside out
+        room = Rect {
+            left: left.left.min(room.right), top: left.top.min(room.bottom),
+            right: left.right.max(room.left), bottom: left.bottom.max(room.top),
+        };
+
+        rect
+    }).collect()
+}
+
+/// container that docks its children to its sides in the order they are declared,
+/// Dock (or a single ANF_DOCK_* anchor) picks the side and the last child fills the rest
+#[derive(Clone)]
+pub struct DockPanel {
+    pub children: Option<Rc<dyn Renderable>>,
+    pub props: Vec<SharedProps>,
+}
+
+impl Renderable for DockPanel {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        Some(self.children.as_ref()?.clone())
+    }
+
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut context = context.render(parent, index, self, msg);
+        let children = match &self.children {
+            Some(children) => children,
+            _ => return context,
+        };
+
+        let childIndex = format!("{}:1", index);
+        if msg.is_none() {
+            let items = children.layoutItems(&childIndex);
+            let dpi = context.dpi(index);
+            let rects = layoutDock(&DockStyle::from(&scaleProps(&self.props, dpi)), context.clientSize(index), &items.iter()
+                .map(|(_, props)|DockItem::from(&measure(&*context.backend, props, dpi)))
+                .collect::<Vec<_>>());
+
+            context.place(&childIndex, items.into_iter().map(|(path, _)|path).zip(rects));
+        }
+
+        children.render(context, index, &childIndex, msg)
+    }
+
+    fn toViewState(&self) -> Vec<SharedProps> {
+        self.props.clone()
+    }
+}
+
+#[allow(dead_code)]
+impl DockPanel {
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
+        let defaultProps = vec![
+            SP::ClassName("panel#0x8002"),
+            SP::Renderer("dock-panel"),
+        ];
+        Self {
+            children: None,
+            props: defaultProps.merge(props.into()),
+        }
+    }
+
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
+        Self {
+            children: Some(Rc::new(children().intoChildren())),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docked(dock: Dock, width: i32, height: i32) -> DockItem {
+        DockItem { dock, width, height, ..Default::default() }
+    }
+
+    fn rects(style: DockStyle, items: &[DockItem]) -> Vec<[i32; 4]> {
+        layoutDock(&style, Size { cx: 300, cy: 200 }, items).iter()
+            .map(|r|[r.left, r.top, r.right - r.left, r.bottom - r.top])
+            .collect()
+    }
+
+    #[test]
+    fn it_docks_children_in_order() {
+        let items = [
+            docked(Dock::Top, 0, 20), docked(Dock::Top, 0, 30), docked(Dock::Bottom, 0, 25),
+            docked(Dock::Left, 50, 0), docked(Dock::Right, 40, 0), docked(Dock::Top, 10, 10),
+        ];
+
+        // the second top child goes below the first one instead of over it
+        assert_eq!(rects(DockStyle::default(), &items), vec![
+            [0, 0, 300, 20], [0, 20, 300, 30], [0, 175, 300, 25], [0, 50, 50, 125], [260, 50, 40, 125], [50, 50, 210, 125],
+        ]);
+        assert_eq!(rects(DockStyle { spacing: 5, padding: 10 }, &items[..3]), vec![
+            [10, 10, 280, 20], [10, 35, 280, 30], [10, 70, 280, 120],
+        ]);
+    }
+
+    #[test]
+    fn it_keeps_docked_children_in_room() {
+        let min = DockItem { limits: SizeLimits { minWidth: 80, ..Default::default() }, ..docked(Dock::Left, 20, 0) };
+        let items = [min, docked(Dock::Right, 500, 0), docked(Dock::Left, 10, 0), docked(Dock::Top, 0, 0)];
+
+        assert_eq!(rects(DockStyle { spacing: 4, ..Default::default() }, &items), vec![
+            [0, 0, 80, 200], [84, 0, 216, 200], [84, 0, 0, 200], [84, 0, 0, 200],
+        ]);
+        assert_eq!(DockItem::from(&vec![SP::Anchor(ANF_DOCK_BOTTOM)]).dock, Dock::Bottom);
+    }
+
+    #[test]
+    fn it_renders_docked_children() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = DockPanel::new([SP::Width(300), SP::Height(200)]).content(||(
+            Panel::new([SP::Dock(Dock::Top), SP::Height(30)]),
+            Panel::new([SP::Dock(Dock::Top), SP::Height(30)]),
+            Panel::new([SP::Anchor(ANF_DOCK_LEFT), SP::Width(60)]),
+            Panel::new([]),
+        ));
+
+        let context = view.render(UIContext::create(backend.clone()), "/", "0", None);
+        let rect = |path: &str| {
+            let r = backend.getControlRect(context.items[path].hwnd).unwrap();
+            [r.left, r.top, r.right - r.left, r.bottom - r.top]
+        };
+        assert_eq!(rect("0:1_1"), [0, 0, 300, 30]);
+        assert_eq!(rect("0:1_2"), [0, 30, 300, 30]);
+        assert_eq!(rect("0:1_3"), [0, 60, 60, 140]);
+        assert_eq!(rect("0:1_4"), [60, 60, 240, 140]);
+    }
+}
@@ -0,0 +1,154 @@
+#![allow(non_snake_case)]
+
+use crate::muffui::*;
+
+/// dpi the logical units of the views are given in, one logical unit is one pixel there
+pub const BASE_DPI: u32 = 96;
+
+/// logical units to pixels of a window with `dpi`, rounded to the nearest pixel
+pub fn scale(value: i32, dpi: u32) -> i32 {
+    convert(value, dpi, BASE_DPI)
+}
+
+/// pixels of a window with `dpi` back to logical units
+pub fn unscale(value: i32, dpi: u32) -> i32 {
+    convert(value, BASE_DPI, dpi)
+}
+
+/// pixels of a window moved from the `from` to the `to` dpi
+pub fn rescale(value: i32, from: u32, to: u32) -> i32 {
+    convert(value, to, from)
+}
+
+/// `value * to / from` rounded half away from zero, CW_USEDEFAULT and the unlimited size stay as they are
+fn convert(value: i32, to: u32, from: u32) -> i32 {
+    if value == CW_USEDEFAULT || value == i32::MAX || to == from || from == 0 {
+        return value;
+    }
+
+    let (value, to, from) = (value as i64, to as i64, from as i64);
+    let res = (2 * value.abs() * to + from) / (2 * from) * value.signum();
+
+    res.clamp(i32::MIN as i64 + 1, i32::MAX as i64 - 1) as i32
+}
+
+/// rect in the client area of a window moved from the `from` to the `to` dpi
+pub fn rescaleRect(rect: &Rect, from: u32, to: u32) -> Rect {
+    Rect {
+        left: rescale(rect.left, from, to),
+        top: rescale(rect.top, from, to),
+        right: rescale(rect.right, from, to),
+        bottom: rescale(rect.bottom, from, to),
+    }
+}
+
+pub fn rescaleSize(size: Size, from: u32, to: u32) -> Size {
+    Size { cx: rescale(size.cx, from, to), cy: rescale(size.cy, from, to) }
+}
+
+/// geometry and font size of `props` in pixels of a window with `dpi`, everything else as it is
+pub fn scaleProps(props: &[SharedProps], dpi: u32) -> Vec<SharedProps> {
+    let s = |v: &i32| scale(*v, dpi);
+    props.iter().map(|prop|match prop {
+        SharedProps::PosX(v) => SharedProps::PosX(s(v)),
+        SharedProps::PosY(v) => SharedProps::PosY(s(v)),
+        SharedProps::Width(v) => SharedProps::Width(s(v)),
+        SharedProps::Height(v) => SharedProps::Height(s(v)),
+        SharedProps::MinWidth(v) => SharedProps::MinWidth(s(v)),
+        SharedProps::MaxWidth(v) => SharedProps::MaxWidth(s(v)),
+        SharedProps::MinHeight(v) => SharedProps::MinHeight(s(v)),
+        SharedProps::MaxHeight(v) => SharedProps::MaxHeight(s(v)),
+        SharedProps::FontSize(v) => SharedProps::FontSize(s(v)),
+        SharedProps::Spacing(v) => SharedProps::Spacing(s(v)),
+        SharedProps::Padding(v) => SharedProps::Padding(s(v)),
+        SharedProps::ScrollX(v) => SharedProps::ScrollX(s(v)),
+        SharedProps::ScrollY(v) => SharedProps::ScrollY(s(v)),
+        SharedProps::RowHeight(v) => SharedProps::RowHeight(s(v)),
+        SharedProps::SplitPosition(v) => SharedProps::SplitPosition(s(v)),
+        SharedProps::Rows(lengths) => SharedProps::Rows(scaleLengths(lengths, dpi)),
+        SharedProps::Columns(lengths) => SharedProps::Columns(scaleLengths(lengths, dpi)),
+        _ => prop.clone(),
+    }).collect()
+}
+
+fn scaleLengths(lengths: &[GridLength], dpi: u32) -> Vec<GridLength> {
+    lengths.iter().map(|l|match l {
+        GridLength::Fixed(size) => GridLength::Fixed(scale(*size, dpi)),
+        _ => *l,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use super::*;
+
+    #[test]
+    fn it_scales_logical_units_by_dpi() {
+        assert_eq!(scale(100, 96), 100);
+        assert_eq!(scale(100, 144), 150);
+        assert_eq!(scale(25, 120), 31);
+        assert_eq!(scale(-5, 144), -8);
+        assert_eq!(unscale(150, 144), 100);
+        assert_eq!(scale(CW_USEDEFAULT, 192), CW_USEDEFAULT);
+        assert_eq!(scale(i32::MAX, 192), i32::MAX);
+        // fractional scales come back to the same logical value
+        for dpi in [96, 120, 144, 168, 192, 240] {
+            assert!((0..500).all(|v|unscale(scale(v, dpi), dpi) == v), "{}", dpi);
+        }
+    }
+
+    #[test]
+    fn it_rescales_between_monitors() {
+        assert_eq!(rescale(150, 144, 96), 100);
+        assert_eq!(rescaleRect(&Rect { left: 10, top: 20, right: 110, bottom: 70 }, 96, 192), Rect { left: 20, top: 40, right: 220, bottom: 140 });
+        assert_eq!(rescaleSize(Size { cx: 300, cy: 200 }, 192, 144), Size { cx: 225, cy: 150 });
+    }
+
+    #[test]
+    fn it_keeps_logical_layout_when_dpi_changes() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = Window::new([SP::Width(300), SP::Height(100)]).content(||(
+            Button::new([SP::ControlId(1), SP::Anchor(ANF_TOP | ANF_RIGHT), SP::Title("Save"), SP::PosX(250), SP::PosY(10)]),
+            Label::new([SP::ControlId(2), SP::Anchor(ANF_TOPLEFT), SP::Title("Name:"), SP::FontSize(12),
+                SP::PosX(10), SP::PosY(10), SP::Width(40), SP::Height(20)]),
+        ));
+        let mut context = UIContext::create(backend.clone());
+        for _ in 0..2 {
+            context = context.update(&view);
+        }
+        let window = context.items["0"].hwnd;
+        let settle = || while let Some(msg) = backend.peekMessage() {
+            backend.dispatchMessage(&msg);
+        };
+
+        backend.resize(window, 400, 100);
+        settle();
+        backend.setDpi(window, 192);
+        settle();
+        context = context.update(&view);
+
+        let node = |path: &str| backend.node(context.items[path].hwnd).unwrap();
+        let rect = |path: &str| {
+            let r = node(path).rect;
+            [r.left, r.top, r.right - r.left, r.bottom - r.top]
+        };
+        assert_eq!(rect("0"), [0, 0, 800, 200]);
+        assert_eq!(rect("0:1_1"), [700, 20, 88, 48]);
+        assert_eq!(rect("0:1_2"), [20, 20, 80, 40]);
+        assert_eq!((node("0:1_1").fontSize, node("0:1_2").fontSize), (32, 24));
+    }
+
+    #[test]
+    fn it_scales_geometry_and_font_props() {
+        let props = scaleProps(&[
+            SP::Title("Save"), SP::PosX(10), SP::Width(40), SP::FontSize(12), SP::Padding(3),
+            SP::Columns([GridLength::Auto, GridLength::Fixed(100), GridLength::Star(2)]),
+        ], 144);
+
+        assert_eq!(format!("{:?}", props), format!("{:?}", vec![
+            SP::Title("Save"), SP::PosX(15), SP::Width(60), SP::FontSize(18), SP::Padding(5),
+            SP::Columns([GridLength::Auto, GridLength::Fixed(150), GridLength::Star(2)]),
+        ]));
+    }
+}
@@ -1,19 +1,20 @@
 #![allow(non_snake_case)]
 use std::fmt;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::Once;
-use std::mem::MaybeUninit;
 use std::sync::Mutex;
 use crate::muffui::*;
-use crate::Win;
 
 #[derive(Clone)]
 pub struct EventInfo {
-    pub hwnd: Win::HWND,
-    pub parent: Win::HWND,
+    pub hwnd: Handle,
+    pub parent: Handle,
     pub listeners: Vec<SharedProps>,
     pub props: Vec<SharedProps>,
-    pub target: Win::HWND,
+    pub target: Handle,
+    /// message the event has been made of, the payloads of key and mouse events come from it
+    pub message: Message,
 }
 
 impl fmt::Debug for EventInfo {
@@ -22,57 +23,93 @@ impl fmt::Debug for EventInfo {
     }
 }
 
-impl From<Win::MSG> for EventInfo {
-    fn from(msg: Win::MSG) -> Self {
+impl From<Message> for EventInfo {
+    fn from(msg: Message) -> Self {
         //let className = Win::GetClassName(msg.hwnd);
         let empty = Self {
-            hwnd: Win::HWND(0),
-            parent: Win::HWND(0),
+            hwnd: Handle(0),
+            parent: Handle(0),
             listeners: vec![],
             props: vec![],
-            target: Win::HWND(0),
+            target: Handle(0),
+            message: msg,
         };
         let defaultEvent = Self {
             hwnd: msg.hwnd,
-            parent: Win::HWND(0),
+            parent: Handle(0),
             listeners: vec![],
             props: vec![],
             target: msg.hwnd,
+            message: msg,
         };
+        let listen = |listeners: Vec<SharedProps>| Self { listeners, ..defaultEvent.clone() };
         match msg.message {
-            Win::WM_CREATE => {
+            WM_CREATE => {
                 let sp = SharedProps::DidCreate(Arc::from(Mutex::from(Command::new(|_|{}))));
                 Self {
                     listeners: vec![sp],
                     ..defaultEvent
                 }
             },
-            Win::WM_SIZE | Win::WM_SIZING => {
+            WM_SIZE | WM_SIZING | WM_DPICHANGED => {
                 let sp = SharedProps::DidResize(Arc::from(Mutex::from(Command::new(|_|{}))));
                 Self {
                     listeners: vec![sp],
                     ..defaultEvent
                 }
             },
-            Win::WM_CTLCOLORLISTBOX => {
+            WM_HSCROLL | WM_VSCROLL | WM_MOUSEWHEEL => {
+                let sp = SharedProps::DidScroll(Arc::from(Mutex::from(Command::new(|_|{}))));
+                Self {
+                    listeners: vec![sp],
+                    ..defaultEvent
+                }
+            },
+            WM_SPLITMOVE => {
+                let sp = SharedProps::DidSplit(Arc::from(Mutex::from(Command::new(|_|{}))));
+                Self {
+                    listeners: vec![sp],
+                    ..defaultEvent
+                }
+            },
+            WM_CTLCOLORLISTBOX => {
                 //println!("WM_CTLCOLORLISTBOX");
                 empty
             },
-            Win::WM_CTLCOLOREDIT => {
+            WM_CTLCOLOREDIT => {
                 //println!("WM_CTLCOLOREDIT");
                 empty
             },
-            Win::WM_MOUSEMOVE => {
-                empty
+            WM_MOUSEMOVE => listen(vec![SharedProps::DidMouseMove(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_MOUSEENTER => listen(vec![SharedProps::DidMouseEnter(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_MOUSELEAVE => listen(vec![SharedProps::DidMouseLeave(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN => {
+                listen(vec![SharedProps::DidMouseDown(Arc::from(Mutex::from(Command::new(|_|{}))))])
             },
-            Win::WM_COMMAND => {
+            WM_SETFOCUS => listen(vec![SharedProps::DidFocus(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_KILLFOCUS => listen(vec![SharedProps::DidBlur(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_KEYDOWN | WM_SYSKEYDOWN => listen(vec![SharedProps::DidKeyDown(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_COMMAND => {
                 //println!("WM_COMMAND");
-                let Win::WPARAM(wParam) = msg.wParam;
-                let Win::LPARAM(control) = msg.lParam;
-                if Win::HIWORD(wParam) == Win::CBN_SELCHANGE as _ {
+                let wParam = msg.wParam;
+                let control = msg.lParam;
+                // native controls tell their parent about the focus
+                let focus = match HIWORD(wParam) as u32 {
+                    EN_SETFOCUS | CBN_SETFOCUS | BN_SETFOCUS => Some(SharedProps::DidFocus(Arc::from(Mutex::from(Command::new(|_|{}))))),
+                    EN_KILLFOCUS | CBN_KILLFOCUS | BN_KILLFOCUS => Some(SharedProps::DidBlur(Arc::from(Mutex::from(Command::new(|_|{}))))),
+                    _ => None,
+                };
+                if HIWORD(wParam) == CBN_SELCHANGE as _ {
                     let sp = SharedProps::DidChange(Arc::from(Mutex::from(Command::new(|_|{}))));
                     Self {
-                        hwnd: Win::HWND(control),
+                        hwnd: Handle(control),
+                        listeners: vec![sp],
+                        ..defaultEvent
+                    }
+                } else if let Some(sp) = focus {
+                    Self {
+                        hwnd: Handle(control),
+                        target: Handle(control),
                         listeners: vec![sp],
                         ..defaultEvent
                     }
@@ -80,42 +117,32 @@ impl From<Win::MSG> for EventInfo {
                     empty
                 }
             },
-            Win::WM_DISPLAYCHANGE => {
+            WM_DISPLAYCHANGE => {
                 //println!("WM_DISPLAYCHANGE");
                 empty
             },
-            Win::WM_CHAR => {
+            WM_CHAR => {
                 let sp = SharedProps::DidChange(Arc::from(Mutex::from(Command::new(|_|{}))));
                 Self {
                     listeners: vec![sp],
                     ..defaultEvent
                 }
             },
-            Win::WM_SYSKEYUP | Win::WM_KEYUP => {
-                let Win::WPARAM(actionId) = msg.wParam;
-                let sp = SharedProps::DidClick(Arc::from(Mutex::from(Command::new(|_|{}))));
-                match actionId {
-                    13 | 32 => Self {
-                        listeners: vec![sp],
-                        ..defaultEvent
-                    },
-                    _ => empty,
-                }
-            },
-            Win::WM_NCACTIVATE => {
+            WM_SYSKEYUP | WM_KEYUP => listen(vec![SharedProps::DidKeyUp(Arc::from(Mutex::from(Command::new(|_|{}))))]),
+            WM_NCACTIVATE => {
                 empty
             },
-            Win::WM_NCHITTEST => {
+            WM_NCHITTEST => {
                 empty
             },
-            Win::WM_SETCURSOR => {
+            WM_SETCURSOR => {
                 let listeners = vec![];
                 Self {
                     listeners,
                     ..defaultEvent
                 }
             },
-            Win::BM_CLICK => {
+            BM_CLICK => {
                 let sp = SharedProps::DidClick(Arc::from(Mutex::from(Command::new(|_|{}))));
                 let listeners = vec![sp];
                 Self {
@@ -123,15 +150,15 @@ impl From<Win::MSG> for EventInfo {
                     ..defaultEvent
                 }
             },
-            Win::WM_LBUTTONUP | Win::WM_RBUTTONUP | Win::WM_MBUTTONUP => {
+            WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP => {
                 let sp = SharedProps::DidClick(Arc::from(Mutex::from(Command::new(|_|{}))));
-                let listeners = vec![sp];
+                let listeners = vec![sp, SharedProps::DidMouseUp(Arc::from(Mutex::from(Command::new(|_|{}))))];
                 Self {
                     listeners,
                     ..defaultEvent
                 }  
             },
-            Win::WM_DESTROY => {
+            WM_DESTROY => {
                 let sp = SharedProps::DidDestroy(Arc::from(Mutex::from(Command::new(|_|{}))));
                 let listeners = vec![sp];
                 Self {
@@ -147,8 +174,77 @@ impl From<Win::MSG> for EventInfo {
     }
 }
 
+/// what the handlers of the event being dispatched have asked for
+#[derive(Clone, Copy, Default)]
+struct Propagation {
+    stopped: bool,
+    prevented: bool,
+}
+
+thread_local! {
+    static PROPAGATION: Cell<Propagation> = Cell::new(Propagation::default());
+    /// message the app loop is handing to the backend, the view has got it from the loop
+    static ROUTING: Cell<Option<Message>> = const { Cell::new(None) };
+}
+
+/// called from a handler, the controls after the current one on the way of the event don't get it
+#[allow(dead_code)]
+pub fn stopPropagation() {
+    PROPAGATION.with(|p|p.set(Propagation { stopped: true, ..p.get() }));
+}
+
+/// called from a handler, the backend skips its default processing of the message, see `isCancelable`
+#[allow(dead_code)]
+pub fn preventDefault() {
+    PROPAGATION.with(|p|p.set(Propagation { prevented: true, ..p.get() }));
+}
+
+/// messages the handlers get before the backend processes them, so that they can prevent it
+pub fn isCancelable(message: u32) -> bool {
+    matches!(message, WM_KEYDOWN | WM_SYSKEYDOWN | WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN)
+}
+
+/// hands `msg` to the backend and to the view once, the view gets cancelable messages first
+/// and the backend skips the ones a handler has called `preventDefault` for
+pub fn routeMessage(backend: &dyn Backend, msg: Message) {
+    let notify = |msg|Arc::get_mut(Notifier::shared()).map(|e|e.notify(Some(msg)));
+    let dispatch = |msg: &Message|{
+        let prev = ROUTING.with(|routing|routing.replace(Some(*msg)));
+        backend.dispatchMessage(msg);
+        ROUTING.with(|routing|routing.set(prev));
+    };
+    if isCancelable(msg.message) {
+        notify(msg);
+        if !EventHub::isDefaultPrevented(&msg) {
+            dispatch(&msg);
+        }
+    } else {
+        dispatch(&msg);
+        notify(msg);
+    }
+}
+
+/// true for the message `routeMessage` is dispatching and the copies the window system passes on to the parents
+/// of its target (the wheel), window procedures don't notify the view of them again
+pub fn isRoutedByLoop(msg: &Message) -> bool {
+    ROUTING.with(|routing|routing.get()
+        .map(|r|r.message == msg.message && r.wParam == msg.wParam && r.lParam == msg.lParam)
+        .unwrap_or(false))
+}
+
+/// `path` is a control the control at `of` is rendered in
+fn isAncestor(path: &str, of: &str) -> bool {
+    of.len() > path.len() && of.starts_with(path) && of[path.len()..].starts_with(':')
+}
+
 pub struct EventHub {
     pub events: Vec<EventInfo>,
+    /// control the mouse is over
+    pub hovered: Handle,
+    /// rendered controls by their path, the events go down and up this tree
+    pub controls: HashMap<String, ControlInfo>,
+    /// cancelable messages a handler has prevented the default processing of
+    prevented: Vec<Message>,
 }
 
 impl From<EventInfo> for Vec<EventInfo> {
@@ -161,6 +257,9 @@ impl Default for EventHub {
     fn default() -> Self {
         Self {
             events: vec![],
+            hovered: Handle(0),
+            controls: HashMap::new(),
+            prevented: vec![],
         }
     }
 }
@@ -170,138 +269,209 @@ impl EventHub {
         Self { ..Default::default() }
     }
 
+    /// one hub per UI thread
     pub fn shared() -> &'static Mutex<EventHub> {
-        static mut CONF: MaybeUninit<Mutex<EventHub>> = MaybeUninit::uninit();
-        static ONCE: Once = Once::new();
-    
-        ONCE.call_once(|| unsafe {
-            CONF.as_mut_ptr().write(Mutex::new(EventHub::new()));
-        });
+        thread_local! {
+            static CONF: &'static Mutex<EventHub> = Box::leak(Box::new(Mutex::new(EventHub::new())));
+        }
 
-        unsafe { &*CONF.as_ptr() }
+        CONF.with(|conf|*conf)
     }
 
-    pub fn enqueueEvent(&mut self, msg: Win::MSG) {
-        let event = EventInfo::from(msg);
-        if event.listeners.len() == 0 {
+    /// true when a handler of the shared hub has prevented the default processing of `msg`, asks once
+    pub fn isDefaultPrevented(msg: &Message) -> bool {
+        Self::shared().try_lock().ok().map(|mut hub|hub.takePrevented(msg)).unwrap_or(false)
+    }
+
+    pub fn takePrevented(&mut self, msg: &Message) -> bool {
+        let len = self.prevented.len();
+        self.prevented.retain(|m|m != msg);
+
+        self.prevented.len() != len
+    }
+
+    pub fn enqueueEvent(&mut self, backend: &dyn Backend, msg: Message) {
+        // the mouse enters and leaves controls by moving, win32 only tells when it leaves a window
+        match msg.message {
+            WM_MOUSEMOVE if msg.hwnd != self.hovered => {
+                let prev = std::mem::replace(&mut self.hovered, msg.hwnd);
+                if prev != Handle(0) {
+                    self.enqueue(backend, Message { hwnd: prev, message: WM_MOUSELEAVE, wParam: msg.wParam, lParam: 0 });
+                }
+                self.enqueue(backend, Message { message: WM_MOUSEENTER, ..msg });
+            },
+            WM_MOUSELEAVE if msg.hwnd != self.hovered => return,
+            WM_MOUSELEAVE => self.hovered = Handle(0),
+            _ => {},
+        }
+
+        self.enqueue(backend, msg);
+    }
+
+    fn enqueue(&mut self, backend: &dyn Backend, msg: Message) {
+        let mut event = EventInfo::from(msg);
+        // Enter and Space press buttons, check and radio boxes but no other control
+        let isPress = (msg.message == WM_KEYUP || msg.message == WM_SYSKEYUP) && [VK_RETURN, VK_SPACE].contains(&(msg.wParam as u32));
+        if isPress && backend.getClassName(msg.hwnd).as_deref() == Some("Button") {
+            event.listeners.push(SharedProps::DidClick(Arc::from(Mutex::from(Command::new(|_|{})))));
+        }
+        if event.listeners.is_empty() {
             return;
         }
-        let mut currentHwnd = event.hwnd;
-        while currentHwnd != Win::HWND(0) {
-            let parent = Win::GetParent(currentHwnd);
-            // Create events with bubble events (For Select/CompoBox edit control)
-            if let Some(e) = self.events.iter_mut().find(|e|e.hwnd == currentHwnd) {
-                let props = e.props.clone();
-                let listeners = e.listeners.clone();
-                e.listeners = listeners.merge(event.listeners.clone());
-                e.props = props.merge(event.props.clone());
-                e.parent = parent;
-                e.hwnd = currentHwnd;
-            } else {
-                let copyEvent = EventInfo {
-                    hwnd: currentHwnd,
-                    parent,
-                    ..event.clone()
-                };
-                self.events.push(copyEvent);
+        event.parent = backend.getParent(event.hwnd);
+
+        self.events.push(event);
+    }
+
+    /// takes the listeners of the rendered controls, the next dispatch calls them
+    pub fn putListeners(&mut self, context: &UIContext) {
+        self.controls = context.items.clone();
+    }
+
+    /// path of the control `hwnd` is or is a native part of, e.g. the edit of a combo box
+    fn pathOf(&self, backend: &dyn Backend, hwnd: Handle) -> Option<String> {
+        let mut hwnd = hwnd;
+        while hwnd != Handle(0) {
+            if let Some((path, _)) = self.controls.iter().find(|(_, ci)|ci.hwnd == hwnd) {
+                return Some(path.clone());
             }
-            currentHwnd = parent;
+            hwnd = backend.getParent(hwnd);
         }
+
+        None
     }
 
-    pub fn putListener<V: Into<Vec<SharedProps>>>(&mut self, hwnd: Win::HWND, listeners: V) {
-        for listener in listeners.into() {
-            let isListener = match listener {
-                SharedProps::DidResize(_)
-                | SharedProps::DidClick(_)
-                | SharedProps::DidChange(_)
-                | SharedProps::DidDestroy(_)
-                | SharedProps::DidCreate(_) => true,
-                _ => false,
-            };
-            if !isListener {
-                return;
-            }
-            if let Some(e) = self.events.iter_mut().find(|e|e.hwnd == hwnd) {
-                let clonned = e.listeners.clone();
-                e.listeners = clonned.update(listener.clone());
-            }
+    /// listeners of `kind` in the order the event meets them: down from the window to the target
+    /// for the capture phase, then up from the target when the event bubbles
+    fn route(&self, path: &str, kind: &SharedProps, bubbles: bool) -> Vec<SharedProps> {
+        let mut chain = self.controls.iter()
+            .filter(|(p, _)|isAncestor(p, path))
+            .collect::<Vec<_>>();
+        chain.sort_by_key(|(p, _)|p.len());
+        if let Some(target) = self.controls.get_key_value(path) {
+            chain.push(target);
         }
+        let find = |listeners: &Vec<SharedProps>| listeners.iter()
+            .find(|l|std::mem::discriminant(*l) == std::mem::discriminant(kind))
+            .cloned();
+
+        let capture = chain.iter().filter_map(|(_, ci)|match ci.listeners.prop(&SP::Capture([])) {
+            Some(SharedProps::Capture(listeners)) => find(listeners),
+            _ => None,
+        });
+        let bubble = chain.iter().rev()
+            .take(if bubbles { chain.len() } else { 1 })
+            .filter_map(|(_, ci)|find(&ci.listeners));
+
+        capture.chain(bubble).collect()
     }
 
-    pub fn dispatchEvents(&mut self) -> bool {
+    pub fn dispatchEvents(&mut self, backend: &dyn Backend) -> bool {
         let mut res = false;
-        for e in self.events.iter_mut() {
-            let clonned = e.listeners.clone();
-            e.listeners = vec![];
-            let mut props = vec![];
-            let className = Win::GetClassName(e.hwnd);
-            if let Some(cn) = className {
-                props.push(SP::ClassName(&cn));
-            }
-            let newTitle = Win::GetWindowText(e.hwnd);
-            props.push(SP::Title(&newTitle));
-
-            for l in clonned.iter() {
-                let mut props = props.clone();
-                res = match l {
-                    SharedProps::DidCreate(h) => {
-                        let rect = Win::GetWindowRect(e.hwnd)
-                            .and_then(|r|AnchorMap::ScreenToClient(e.parent, &r))
-                            .unwrap_or(Win::RECT { ..Default::default() });
-                        props.push(SharedProps::PosX(rect.left));
-                        props.push(SharedProps::PosY(rect.top));
-                        props.push(SharedProps::Width(rect.right - rect.left));
-                        props.push(SharedProps::Height(rect.bottom - rect.top));
-
-                        let mut h = h.lock().unwrap();
-                        h.exec(props);
-                        true
-                    },
-                    SharedProps::DidResize(h) => {
-                        let rect = Win::GetWindowRect(e.hwnd)
-                            .and_then(|r|AnchorMap::ScreenToClient(e.parent, &r))
-                            .unwrap_or(Win::RECT { ..Default::default() });
-                        props.push(SharedProps::PosX(rect.left));
-                        props.push(SharedProps::PosY(rect.top));
-                        props.push(SharedProps::Width(rect.right - rect.left));
-                        props.push(SharedProps::Height(rect.bottom - rect.top));
-
-                        let mut h = h.lock().unwrap();
-                        h.exec(props);
-                        true
-                    },
-                    SharedProps::DidChange(h) => {
-                        if let Some(selectedItem) = Win::SelectGetCurrentIndex(e.hwnd) {
-                            // toDO: Get selected title
-                            props.push(SharedProps::SelectedIndex(selectedItem));
-                        }
-                        let mut h = h.lock().unwrap();
-                        h.exec(props);
-                        true
-                    },
-                    SharedProps::DidClick(h) => {
-                        let mut h = h.lock().unwrap();
-                        let selected = Win::IsSelected(e.hwnd);
-                        props.push(SharedProps::Selected(selected));
-                        h.exec(props);
-                        true
-                    },
-                    SharedProps::DidDestroy(h) => {
-                        let mut h = h.lock().unwrap();
-                        h.exec(props);
-                        true
-                    },
-                    _ => false
+        for e in std::mem::take(&mut self.events) {
+            let path = match self.pathOf(backend, e.hwnd) {
+                Some(path) => path,
+                _ => continue,
+            };
+            EventRecorder::record(backend, &path, &e);
+            // the mouse enters and leaves only the control it is over, its parents keep it
+            let bubbles = e.message.message != WM_MOUSEENTER && e.message.message != WM_MOUSELEAVE;
+            for kind in e.listeners.iter() {
+                PROPAGATION.with(|p|p.set(Propagation::default()));
+                let listeners = self.route(&path, kind, bubbles);
+                for l in listeners.iter() {
+                    Self::exec(backend, &e, l);
+                    if PROPAGATION.with(|p|p.get().stopped) {
+                        break;
+                    }
                 }
+                if PROPAGATION.with(|p|p.get().prevented) && isCancelable(e.message.message) {
+                    self.prevented.push(e.message);
+                }
+                // focus, key and mouse events come all the time, only handling them renders the view again
+                res |= match kind {
+                    SharedProps::DidFocus(_) | SharedProps::DidBlur(_)
+                    | SharedProps::DidKeyDown(_) | SharedProps::DidKeyUp(_)
+                    | SharedProps::DidMouseDown(_) | SharedProps::DidMouseUp(_) | SharedProps::DidMouseMove(_)
+                    | SharedProps::DidMouseEnter(_) | SharedProps::DidMouseLeave(_) => !listeners.is_empty(),
+                    _ => true,
+                };
             }
         }
         res
     }
+
+    /// calls `l` with the payload of the event `e`
+    fn exec(backend: &dyn Backend, e: &EventInfo, l: &SharedProps) {
+        match l {
+            SharedProps::DidCreate(h) => {
+                let rect = backend.getControlRect(e.hwnd)
+                    .unwrap_or(Rect { ..Default::default() });
+
+                let mut h = h.lock().unwrap();
+                h.exec(CreateEvent { target: e.hwnd, rect });
+            },
+            SharedProps::DidResize(h) => {
+                let rect = backend.getControlRect(e.hwnd)
+                    .unwrap_or(Rect { ..Default::default() });
+
+                let mut h = h.lock().unwrap();
+                h.exec(ResizeEvent { target: e.hwnd, rect });
+            },
+            SharedProps::DidChange(h) => {
+                // toDO: Get selected title
+                let selectedIndex = backend.getSelectedIndex(e.hwnd);
+                let mut h = h.lock().unwrap();
+                h.exec(ChangeEvent { target: e.hwnd, text: backend.getTitle(e.hwnd), selectedIndex });
+            },
+            SharedProps::DidClick(h) => {
+                let mut h = h.lock().unwrap();
+                let selected = backend.isSelected(e.hwnd);
+                h.exec(ClickEvent { target: e.hwnd, selected });
+            },
+            SharedProps::DidDestroy(h) => {
+                let mut h = h.lock().unwrap();
+                h.exec(DestroyEvent { target: e.hwnd });
+            },
+            SharedProps::DidScroll(h) => {
+                // the scroll view the message came from, the wheel arrives at the control under the mouse
+                let event = ScrollState::enclosing(backend, e.target).map(|(hwnd, state)|{
+                    let dpi = backend.getDpi(hwnd);
+                    ScrollEvent { target: hwnd, x: unscale(state.offset.x, dpi), y: unscale(state.offset.y, dpi) }
+                });
+                let mut h = h.lock().unwrap();
+                h.exec(event.unwrap_or(ScrollEvent { target: e.target, ..Default::default() }));
+            },
+            SharedProps::DidFocus(h) | SharedProps::DidBlur(h) => {
+                h.lock().unwrap().exec(FocusEvent { target: e.target });
+            },
+            SharedProps::DidKeyDown(h) | SharedProps::DidKeyUp(h) => {
+                h.lock().unwrap().exec(KeyEvent::new(backend, &e.message));
+            },
+            SharedProps::DidMouseDown(h)
+            | SharedProps::DidMouseUp(h)
+            | SharedProps::DidMouseMove(h)
+            | SharedProps::DidMouseEnter(h)
+            | SharedProps::DidMouseLeave(h) => {
+                h.lock().unwrap().exec(MouseEvent::new(backend, &e.message));
+            },
+            SharedProps::DidSplit(h) => {
+                let event = SplitState::find(e.target).map(|state|{
+                    let (ratio, position) = state.position();
+                    SplitEvent { target: e.target, ratio, position: unscale(position, backend.getDpi(e.target)) }
+                });
+                let mut h = h.lock().unwrap();
+                h.exec(event.unwrap_or(SplitEvent { target: e.target, ..Default::default() }));
+            },
+            _ => {},
+        }
+    }
 }
 
+type Subscriber<E> = Box<dyn FnMut(&E)>;
+
 pub struct Notifier<E = Option<EventInfo>> {
-    subscribers: Vec<Box<dyn FnMut(&E)>>,
+    subscribers: Vec<Subscriber<E>>,
 }
 
 impl<E> Notifier<E> {
@@ -324,18 +494,152 @@ impl<E> Notifier<E> {
 }
 
 pub trait NotifierExt {
-    fn shared() -> &'static mut Arc<Notifier<Option<Win::MSG>>>;
+    fn shared() -> &'static mut Arc<Notifier<Option<Message>>>;
 }
 
-impl NotifierExt for Notifier<Option<Win::MSG>> {
-    fn shared() -> &'static mut Arc<Notifier<Option<Win::MSG>>> {
-        static mut CONF: MaybeUninit<Arc<Notifier<Option<Win::MSG>>>> = MaybeUninit::uninit();
-        static ONCE: Once = Once::new();
-    
-        ONCE.call_once(|| unsafe {
-            CONF.as_mut_ptr().write(Arc::new(Notifier::new()));
-        });
+impl NotifierExt for Notifier<Option<Message>> {
+    // the notifier never leaves its thread, the Arc is only there for the callers' Arc::get_mut
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn shared() -> &'static mut Arc<Notifier<Option<Message>>> {
+        thread_local! {
+            static CONF: *mut Arc<Notifier<Option<Message>>> = Box::into_raw(Box::new(Arc::new(Notifier::new())));
+        }
+
+        CONF.with(|conf|unsafe { &mut **conf })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
 
-        unsafe { &mut *CONF.as_mut_ptr() }
+    #[test]
+    fn it_captures_and_bubbles_events_along_the_view_tree() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let log = Rc::new(RefCell::new(vec![]));
+        let stop = Rc::new(Cell::new(""));
+        let logged = |name: &'static str| {
+            let (log, stop) = (log.clone(), stop.clone());
+            move|_: ClickEvent|{
+                log.borrow_mut().push(name);
+                if stop.get() == name {
+                    stopPropagation();
+                }
+            }
+        };
+        let view = {
+            let (capture, outer, inner, button) = (logged("capture"), logged("outer"), logged("inner"), logged("button"));
+            Window::new([SP::Width(300), SP::Height(200)]).content(move||
+                Panel::new([SP::Width(200), SP::Height(100), SP::Capture([SP::OnClick(capture.clone())]), SP::OnClick(outer.clone())]).content({
+                    let (inner, button) = (inner.clone(), button.clone());
+                    move||Panel::new([SP::Width(100), SP::Height(50), SP::OnClick(inner.clone())]).content({
+                        let button = button.clone();
+                        move||Button::new([SP::Width(50), SP::Height(20), SP::OnClick(button.clone())])
+                    })
+                })
+            )
+        };
+        let mut context = UIContext::create(backend.clone()).update(&view);
+        let button = context.items["0:1_1:1_1:1_1"].hwnd;
+        let mut click = |stopAt: &'static str| {
+            log.borrow_mut().clear();
+            stop.set(stopAt);
+            let msg = Message { hwnd: button, message: BM_CLICK, ..Default::default() };
+            let mut hub = EventHub::new();
+            hub.enqueueEvent(&*backend, msg);
+            context = view.render(context.clone(), "/", "0", Some(msg));
+            hub.putListeners(&context);
+            hub.dispatchEvents(&*backend);
+            log.borrow().clone()
+        };
+
+        assert_eq!(click(""), vec!["capture", "button", "inner", "outer"]);
+        assert_eq!(click("inner"), vec!["capture", "button", "inner"]);
+        assert_eq!(click("capture"), vec!["capture"]);
+    }
+
+    #[test]
+    fn it_prevents_default_of_cancelable_messages() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = Window::new([SP::Width(300), SP::Height(200)]).content(||
+            TextBox::new([SP::Width(100), SP::Height(20), SP::DidKeyDown(|_|preventDefault()), SP::DidKeyUp(|_|preventDefault())])
+        );
+        let context = UIContext::create(backend.clone()).update(&view);
+        let textBox = context.items["0:1_1"].hwnd;
+        let mut hub = EventHub::new();
+        let keyDown = Message { hwnd: textBox, message: WM_KEYDOWN, wParam: 0x41, ..Default::default() };
+        let keyUp = Message { message: WM_KEYUP, ..keyDown };
+        for msg in [keyDown, keyUp] {
+            hub.enqueueEvent(&*backend, msg);
+        }
+        let context = view.render(context, "/", "0", Some(keyDown));
+        hub.putListeners(&context);
+        hub.dispatchEvents(&*backend);
+
+        // the backend has already handled a key up when the handlers see it
+        assert!(hub.takePrevented(&keyDown));
+        assert!(!hub.takePrevented(&keyDown));
+        assert!(!hub.takePrevented(&keyUp));
+    }
+
+    #[test]
+    fn it_notifies_handlers_once_per_dispatched_message() {
+        fn logged<E>(log: &Rc<RefCell<Vec<&'static str>>>, name: &'static str) -> impl Fn(E) + Clone {
+            let log = log.clone();
+            move|_|log.borrow_mut().push(name)
+        }
+        let log = Rc::new(RefCell::new(vec![]));
+        let (down, up, press, moved) = (logged(&log, "down"), logged(&log, "up"), logged(&log, "press"), logged(&log, "move"));
+        let view = Window::new([SP::Width(300), SP::Height(200)]).content(move||
+            Panel::new([SP::Width(200), SP::Height(100),
+                SP::DidKeyDown(down.clone()), SP::DidKeyUp(up.clone()), SP::DidMouseDown(press.clone()), SP::DidMouseMove(moved.clone())])
+        );
+        let mut driver = TestDriver::mountView(view).unwrap();
+        driver.backend.setNotifyOnDispatch(true);
+        let panel = driver.get(By::Path("0:1_1")).hwnd;
+        for message in [WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_MOUSEMOVE] {
+            driver.backend.postMessage(Message { hwnd: panel, message, wParam: 0x41, ..Default::default() });
+        }
+        assert!(driver.settle());
+
+        assert_eq!(*log.borrow(), vec!["down", "up", "press", "move"]);
+    }
+
+    #[test]
+    fn it_keeps_propagation_and_prevented_default_when_backend_notifies_on_dispatch() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let logged = |name: &'static str| {
+            let log = log.clone();
+            move|event: KeyEvent|{
+                log.borrow_mut().push(name);
+                if name == "text" {
+                    stopPropagation();
+                    if event.keyCode == VK_SPACE {
+                        preventDefault();
+                    }
+                }
+            }
+        };
+        let (capture, panel, text) = (logged("capture"), logged("panel"), logged("text"));
+        let view = Window::new([SP::Width(300), SP::Height(200)]).content(move||
+            Panel::new([SP::Width(200), SP::Height(100), SP::Capture([SP::DidKeyDown(capture.clone())]), SP::DidKeyDown(panel.clone())]).content({
+                let text = text.clone();
+                move||TextBox::new([SP::Width(100), SP::Height(20), SP::DidKeyDown(text.clone())])
+            })
+        );
+        let mut driver = TestDriver::mountView(view).unwrap();
+        driver.backend.setNotifyOnDispatch(true);
+        let textBox = driver.get(By::Path("0:1_1:1_1")).hwnd;
+        for (key, processed) in [(0x41, true), (VK_SPACE, false)] {
+            let msg = Message { hwnd: textBox, message: WM_KEYDOWN, wParam: key as usize, ..Default::default() };
+            driver.backend.takeDispatched();
+            driver.backend.postMessage(msg);
+            assert!(driver.settle());
+
+            assert_eq!(log.take(), vec!["capture", "text"]);
+            assert_eq!(driver.backend.takeDispatched().contains(&msg), processed, "key {:#x}", key);
+        }
     }
 }
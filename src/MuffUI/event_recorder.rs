@@ -0,0 +1,300 @@
+#![allow(non_snake_case)]
+
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::time::Instant;
+use crate::muffui::*;
+
+/// state of the control an event has been recorded for, a replay puts the control back in it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum EventPayload {
+    #[default]
+    None,
+    Click { selected: bool },
+    Change { text: String, selectedIndex: Option<usize> },
+    /// left, top, right and bottom of a window, `toJson` writes it as [left, top, width, height] and `parse` reads it back
+    Resize { rect: Rect },
+    Scroll { x: i32, y: i32 },
+    Key { keyCode: u32, modifiers: Modifiers },
+    Mouse { x: i32, y: i32, modifiers: Modifiers },
+}
+
+/// event that has reached the event hub
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordedEvent {
+    /// milliseconds since the recording has started
+    pub time: u64,
+    /// path of the control in the view tree, handles change from run to run but paths don't
+    pub path: String,
+    /// listener the event is for, e.g. "DidClick"
+    pub kind: String,
+    pub message: u32,
+    pub wParam: usize,
+    pub lParam: isize,
+    pub payload: EventPayload,
+}
+
+#[derive(Default)]
+struct EventRecorderState {
+    enabled: bool,
+    started: Option<Instant>,
+    events: Vec<RecordedEvent>,
+}
+
+/// records the events the hub dispatches while it is enabled, `TestDriver::replay` plays them back
+pub struct EventRecorder;
+
+impl EventRecorder {
+    fn state() -> &'static RefCell<EventRecorderState> {
+        thread_local! {
+            static CONF: &'static RefCell<EventRecorderState> = Box::leak(Box::new(RefCell::new(Default::default())));
+        }
+
+        CONF.with(|conf|*conf)
+    }
+
+    pub fn enable() {
+        let mut state = Self::state().borrow_mut();
+        state.enabled = true;
+        state.started = Some(Instant::now());
+    }
+
+    #[allow(dead_code)]
+    pub fn disable() {
+        *Self::state().borrow_mut() = Default::default();
+    }
+
+    pub fn isEnabled() -> bool {
+        Self::state().borrow().enabled
+    }
+
+    #[allow(dead_code)]
+    pub fn events() -> Vec<RecordedEvent> {
+        Self::state().borrow().events.clone()
+    }
+
+    /// takes an event the hub is about to dispatch to the control at `path`, skips the ones
+    /// the replay brings about by itself: rendering creates and destroys controls and lays
+    /// the children out, the mouse enters controls by moving and the split reports its drag
+    pub fn record(backend: &dyn Backend, path: &str, e: &EventInfo) {
+        if !Self::isEnabled() {
+            return;
+        }
+        let kind = match e.listeners.first() {
+            Some(listener) => listener,
+            _ => return,
+        };
+        let isWindow = !path.contains(':');
+        match e.message.message {
+            WM_CREATE | WM_MOUSEENTER | WM_SPLITMOVE => return,
+            WM_DESTROY | WM_SIZE | WM_SIZING if !isWindow => return,
+            _ => {},
+        }
+
+        let payload = match kind {
+            SharedProps::DidClick(_) => EventPayload::Click { selected: backend.isSelected(e.hwnd) },
+            SharedProps::DidChange(_) => EventPayload::Change {
+                text: backend.getTitle(e.hwnd),
+                selectedIndex: backend.getSelectedIndex(e.hwnd),
+            },
+            SharedProps::DidResize(_) => EventPayload::Resize { rect: backend.getControlRect(e.hwnd).unwrap_or_default() },
+            SharedProps::DidScroll(_) => match ScrollState::enclosing(backend, e.target) {
+                Some((hwnd, state)) => {
+                    let dpi = backend.getDpi(hwnd);
+                    EventPayload::Scroll { x: unscale(state.offset.x, dpi), y: unscale(state.offset.y, dpi) }
+                },
+                _ => EventPayload::None,
+            },
+            SharedProps::DidKeyDown(_) | SharedProps::DidKeyUp(_) => {
+                let event = KeyEvent::new(backend, &e.message);
+                EventPayload::Key { keyCode: event.keyCode, modifiers: event.modifiers }
+            },
+            SharedProps::DidMouseDown(_) | SharedProps::DidMouseUp(_) | SharedProps::DidMouseMove(_) | SharedProps::DidMouseLeave(_) => {
+                let event = MouseEvent::new(backend, &e.message);
+                EventPayload::Mouse { x: event.x, y: event.y, modifiers: event.modifiers }
+            },
+            _ => EventPayload::None,
+        };
+
+        let mut state = Self::state().borrow_mut();
+        let time = state.started.map(|started|started.elapsed().as_millis() as u64).unwrap_or_default();
+        state.events.push(RecordedEvent {
+            time,
+            path: String::from(path),
+            kind: kindName(kind).to_string(),
+            message: e.message.message,
+            wParam: e.message.wParam,
+            lParam: e.message.lParam,
+            payload,
+        });
+    }
+
+    /// recorded events as a JSON array, one event per line
+    pub fn toJson() -> String {
+        let state = Self::state().borrow();
+        let modifiers = |m: &Modifiers| format!("\"shift\": {}, \"control\": {}, \"alt\": {}", m.shift, m.control, m.alt);
+
+        let mut res = String::from("[");
+        for (idx, e) in state.events.iter().enumerate() {
+            let payload = match &e.payload {
+                EventPayload::None => String::new(),
+                EventPayload::Click { selected } => format!("\"selected\": {}", selected),
+                EventPayload::Change { text, selectedIndex } => format!("\"text\": {}, \"selectedIndex\": {}",
+                    jsonString(text), selectedIndex.map(|i|i.to_string()).unwrap_or_else(||String::from("null"))),
+                EventPayload::Resize { rect } => format!("\"rect\": [{}, {}, {}, {}]",
+                    rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top),
+                EventPayload::Scroll { x, y } => format!("\"x\": {}, \"y\": {}", x, y),
+                EventPayload::Key { keyCode, modifiers: m } => format!("\"keyCode\": {}, {}", keyCode, modifiers(m)),
+                EventPayload::Mouse { x, y, modifiers: m } => format!("\"x\": {}, \"y\": {}, {}", x, y, modifiers(m)),
+            };
+            let _ = write!(res, "{}\n  {{\"time\": {}, \"path\": {}, \"kind\": {}, \"message\": {}, \"wParam\": {}, \"lParam\": {}, \"payload\": {{{}}}}}",
+                if idx > 0 { "," } else { "" },
+                e.time, jsonString(&e.path), jsonString(&e.kind), e.message, e.wParam, e.lParam, payload,
+            );
+        }
+        res.push_str("\n]\n");
+
+        res
+    }
+
+    pub fn save(path: &str) -> std::io::Result<()> {
+        std::fs::write(path, Self::toJson())
+    }
+
+    /// events of a recording made by `toJson`
+    pub fn parse(text: &str) -> Result<Vec<RecordedEvent>, MarkupError> {
+        let items = match parseJsonValue(text)? {
+            JsonValue::Array(items) => items,
+            _ => return Err(MarkupError { line: 1, column: 1, message: String::from("expected an array of events") }),
+        };
+
+        items.into_iter().map(|(line, column, item)|{
+            let error = |message: String| MarkupError { line, column, message };
+            let fields = match item {
+                JsonValue::Object(fields) => fields,
+                _ => return Err(error(String::from("expected an event object"))),
+            };
+            let field = |fields: &Vec<(String, usize, usize, JsonValue)>, name: &str| fields.iter()
+                .find_map(|(key, _, _, value)|match value {
+                    JsonValue::Scalar(value) if key == name => Some(value.clone()),
+                    _ => None,
+                });
+            let number = |fields: &Vec<(String, usize, usize, JsonValue)>, name: &str| field(fields, name)
+                .and_then(|value|value.parse::<i64>().ok())
+                .ok_or_else(||error(format!("expected a number in {:?}", name)));
+            let flag = |fields: &Vec<(String, usize, usize, JsonValue)>, name: &str| field(fields, name).as_deref() == Some("true");
+
+            let payload = fields.iter().find_map(|(key, _, _, value)|match value {
+                JsonValue::Object(payload) if key == "payload" => Some(payload),
+                _ => None,
+            }).ok_or_else(||error(String::from("expected a payload object")))?;
+            let modifiers = Modifiers { shift: flag(payload, "shift"), control: flag(payload, "control"), alt: flag(payload, "alt") };
+            let kind = field(&fields, "kind").unwrap_or_default();
+            let payload = match kind.as_str() {
+                "DidClick" => EventPayload::Click { selected: flag(payload, "selected") },
+                "DidChange" => EventPayload::Change {
+                    text: field(payload, "text").unwrap_or_default(),
+                    selectedIndex: number(payload, "selectedIndex").ok().map(|i|i as usize),
+                },
+                "DidResize" => {
+                    let rect = payload.iter().find_map(|(key, _, _, value)|match value {
+                        JsonValue::Array(items) if key == "rect" => Some(items.iter().filter_map(|(_, _, v)|match v {
+                            JsonValue::Scalar(v) => v.parse::<i32>().ok(),
+                            _ => None,
+                        }).collect::<Vec<_>>()),
+                        _ => None,
+                    });
+                    match rect.as_deref() {
+                        Some([left, top, width, height]) => EventPayload::Resize {
+                            rect: Rect { left: *left, top: *top, right: left + width, bottom: top + height },
+                        },
+                        _ => return Err(error(String::from("expected a rect [left, top, width, height]"))),
+                    }
+                },
+                "DidScroll" => EventPayload::Scroll { x: number(payload, "x")? as i32, y: number(payload, "y")? as i32 },
+                "DidKeyDown" | "DidKeyUp" => EventPayload::Key { keyCode: number(payload, "keyCode")? as u32, modifiers },
+                "DidMouseDown" | "DidMouseUp" | "DidMouseMove" | "DidMouseLeave" => EventPayload::Mouse {
+                    x: number(payload, "x")? as i32, y: number(payload, "y")? as i32, modifiers,
+                },
+                _ => EventPayload::None,
+            };
+
+            Ok(RecordedEvent {
+                time: number(&fields, "time")? as u64,
+                path: field(&fields, "path").ok_or_else(||error(String::from("expected a path")))?,
+                kind,
+                message: number(&fields, "message")? as u32,
+                wParam: number(&fields, "wParam")? as usize,
+                lParam: number(&fields, "lParam")? as isize,
+                payload,
+            })
+        }).collect()
+    }
+
+    #[allow(dead_code)]
+    pub fn load(path: &str) -> Result<Vec<RecordedEvent>, LoadError> {
+        let text = std::fs::read_to_string(path).map_err(|e|LoadError::io(path, e))?;
+
+        Self::parse(&text).map_err(|e|LoadError::markup(path, e))
+    }
+}
+
+fn kindName(listener: &SharedProps) -> &'static str {
+    match listener {
+        SharedProps::DidCreate(_) => "DidCreate",
+        SharedProps::DidClick(_) => "DidClick",
+        SharedProps::DidChange(_) => "DidChange",
+        SharedProps::DidResize(_) => "DidResize",
+        SharedProps::DidDestroy(_) => "DidDestroy",
+        SharedProps::DidScroll(_) => "DidScroll",
+        SharedProps::DidSplit(_) => "DidSplit",
+        SharedProps::DidFocus(_) => "DidFocus",
+        SharedProps::DidBlur(_) => "DidBlur",
+        SharedProps::DidKeyDown(_) => "DidKeyDown",
+        SharedProps::DidKeyUp(_) => "DidKeyUp",
+        SharedProps::DidMouseDown(_) => "DidMouseDown",
+        SharedProps::DidMouseUp(_) => "DidMouseUp",
+        SharedProps::DidMouseMove(_) => "DidMouseMove",
+        SharedProps::DidMouseEnter(_) => "DidMouseEnter",
+        SharedProps::DidMouseLeave(_) => "DidMouseLeave",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_recorded_payloads() {
+        let json = r#"[
+          {"time": 0, "path": "0", "kind": "DidResize", "message": 5, "wParam": 0, "lParam": 0, "payload": {"rect": [10, 20, 300, 200]}},
+          {"time": 15, "path": "0:1_1", "kind": "DidKeyDown", "message": 256, "wParam": 65, "lParam": 1, "payload": {"keyCode": 65, "shift": true, "control": false, "alt": false}},
+          {"time": 30, "path": "0:1_2", "kind": "DidMouseMove", "message": 512, "wParam": 0, "lParam": 393221, "payload": {"x": 5, "y": 6, "shift": false, "control": true, "alt": false}},
+          {"time": 45, "path": "0:1_2", "kind": "DidFocus", "message": 7, "wParam": 0, "lParam": 0, "payload": {}}
+        ]"#;
+
+        let events = EventRecorder::parse(json).unwrap();
+        assert_eq!(events.iter().map(|e|e.payload.clone()).collect::<Vec<_>>(), vec![
+            EventPayload::Resize { rect: Rect { left: 10, top: 20, right: 310, bottom: 220 } },
+            EventPayload::Key { keyCode: 65, modifiers: Modifiers { shift: true, ..Default::default() } },
+            EventPayload::Mouse { x: 5, y: 6, modifiers: Modifiers { control: true, ..Default::default() } },
+            EventPayload::None,
+        ]);
+        assert_eq!((events[2].time, events[2].path.as_str(), events[2].lParam), (30, "0:1_2", 393221));
+
+        let error = EventRecorder::parse("[\n  {\"time\": 0, \"payload\": {}}\n]").unwrap_err();
+        assert_eq!(error.to_string(), "2:3: expected a path");
+    }
+
+    #[test]
+    fn it_reports_unreadable_event_log_without_position() {
+        let path = std::env::temp_dir().join(format!("muffui-missing-events-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let error = EventRecorder::load(path).unwrap_err();
+        assert!(matches!(error, LoadError::Io { .. }));
+        assert!(error.to_string().starts_with(&format!("{}: ", path)));
+    }
+}
@@ -0,0 +1,360 @@
+#![allow(non_snake_case)]
+
+use crate::muffui::*;
+
+/// keys held down while a key or mouse event happened
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// mouse buttons held down while a mouse event happened
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MouseButtons {
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+}
+
+/// payload of DidFocus and DidBlur
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FocusEvent {
+    /// control that has got or lost the focus
+    pub target: Handle,
+}
+
+/// payload of DidKeyDown and DidKeyUp
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub target: Handle,
+    /// virtual key code, e.g. VK_RETURN
+    pub keyCode: u32,
+    pub modifiers: Modifiers,
+    /// the key has been held down and repeats
+    pub repeat: bool,
+}
+
+/// payload of DidMouseDown, DidMouseUp, DidMouseMove, DidMouseEnter and DidMouseLeave
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub target: Handle,
+    /// position in logical units in the client area of `target`
+    pub x: i32,
+    pub y: i32,
+    /// button pressed or released by DidMouseDown and DidMouseUp
+    pub button: Option<MouseButton>,
+    pub buttons: MouseButtons,
+    pub modifiers: Modifiers,
+}
+
+/// payload of DidScroll
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScrollEvent {
+    /// scroll view that has been scrolled
+    pub target: Handle,
+    /// offset in logical units
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<ScrollEvent> for Vec<SharedProps> {
+    fn from(event: ScrollEvent) -> Self {
+        vec![SharedProps::ScrollX(event.x), SharedProps::ScrollY(event.y)]
+    }
+}
+
+/// payload of DidClick
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClickEvent {
+    pub target: Handle,
+    /// check state of check and radio boxes after the click
+    pub selected: bool,
+}
+
+impl From<ClickEvent> for Vec<SharedProps> {
+    fn from(event: ClickEvent) -> Self {
+        vec![SharedProps::Selected(event.selected)]
+    }
+}
+
+/// payload of DidChange
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChangeEvent {
+    pub target: Handle,
+    /// text of a text box, or of the item picked in a select
+    pub text: String,
+    /// item picked in a select
+    pub selectedIndex: Option<usize>,
+}
+
+impl From<ChangeEvent> for Vec<SharedProps> {
+    fn from(event: ChangeEvent) -> Self {
+        let mut props = vec![SP::Title(&event.text)];
+        if let Some(index) = event.selectedIndex {
+            props.push(SharedProps::SelectedIndex(index));
+        }
+
+        props
+    }
+}
+
+/// payload of DidResize
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResizeEvent {
+    pub target: Handle,
+    /// rect of `target` in the client area of its parent
+    pub rect: Rect,
+}
+
+impl From<ResizeEvent> for Vec<SharedProps> {
+    fn from(event: ResizeEvent) -> Self {
+        let rect = event.rect;
+        vec![
+            SharedProps::PosX(rect.left),
+            SharedProps::PosY(rect.top),
+            SharedProps::Width(rect.right - rect.left),
+            SharedProps::Height(rect.bottom - rect.top),
+        ]
+    }
+}
+
+/// payload of DidCreate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CreateEvent {
+    pub target: Handle,
+    /// rect of `target` in the client area of its parent
+    pub rect: Rect,
+}
+
+impl From<CreateEvent> for Vec<SharedProps> {
+    fn from(event: CreateEvent) -> Self {
+        ResizeEvent { target: event.target, rect: event.rect }.into()
+    }
+}
+
+/// payload of DidDestroy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DestroyEvent {
+    pub target: Handle,
+}
+
+impl From<DestroyEvent> for Vec<SharedProps> {
+    fn from(_: DestroyEvent) -> Self {
+        vec![]
+    }
+}
+
+/// payload of DidSplit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SplitEvent {
+    /// split whose divider has been dragged
+    pub target: Handle,
+    /// share of the split the first pane takes
+    pub ratio: f32,
+    /// size of the first pane in logical units
+    pub position: i32,
+}
+
+impl From<SplitEvent> for Vec<SharedProps> {
+    fn from(event: SplitEvent) -> Self {
+        vec![SharedProps::SplitRatio(event.ratio), SharedProps::SplitPosition(event.position)]
+    }
+}
+
+impl KeyEvent {
+    pub fn new(backend: &dyn Backend, msg: &Message) -> Self {
+        Self {
+            target: msg.hwnd,
+            keyCode: msg.wParam as u32,
+            modifiers: backend.modifiers(),
+            // bit 30 is the state of the key before the message
+            repeat: msg.message != WM_KEYUP && msg.message != WM_SYSKEYUP && msg.lParam & (1 << 30) != 0,
+        }
+    }
+}
+
+impl MouseEvent {
+    pub fn new(backend: &dyn Backend, msg: &Message) -> Self {
+        let dpi = backend.getDpi(msg.hwnd);
+        let coordinate = |word: usize| unscale(word as u16 as i16 as i32, dpi);
+        let button = match msg.message {
+            WM_LBUTTONDOWN | WM_LBUTTONUP => Some(MouseButton::Left),
+            WM_RBUTTONDOWN | WM_RBUTTONUP => Some(MouseButton::Right),
+            WM_MBUTTONDOWN | WM_MBUTTONUP => Some(MouseButton::Middle),
+            _ => None,
+        };
+
+        Self {
+            target: msg.hwnd,
+            x: coordinate(LOWORD(msg.lParam as usize)),
+            y: coordinate(HIWORD(msg.lParam as usize)),
+            button,
+            buttons: MouseButtons {
+                left: msg.wParam & MK_LBUTTON != 0,
+                right: msg.wParam & MK_RBUTTON != 0,
+                middle: msg.wParam & MK_MBUTTON != 0,
+            },
+            modifiers: backend.modifiers(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    #[test]
+    fn it_reports_focus_key_and_mouse_events() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let log = Rc::new(RefCell::new(vec![]));
+        let logged = |name: &'static str| {
+            let log = log.clone();
+            move|event: String|log.borrow_mut().push(format!("{} {}", name, event))
+        };
+        let view = {
+            let (focus, blur, key, click) = (logged("focus"), logged("blur"), logged("key"), logged("click"));
+            let (enter, leave, down, moved) = (logged("enter"), logged("leave"), logged("down"), logged("move"));
+            Window::new([SP::Width(300), SP::Height(100)]).content(move||(
+                TextBox::new([SP::ControlId(1), SP::PosX(10), SP::PosY(10), SP::Width(100), SP::Height(20),
+                    SP::DidFocus({ let f = focus.clone(); move|e: FocusEvent|f(format!("{:?}", e.target)) }),
+                    SP::DidBlur({ let f = blur.clone(); move|e: FocusEvent|f(format!("{:?}", e.target)) }),
+                    SP::DidKeyDown({ let f = key.clone(); move|e: KeyEvent|f(format!("{} {:?} {}", e.keyCode, e.modifiers, e.repeat)) }),
+                    SP::DidClick({ let f = click.clone(); move|_|f(String::from("text box")) }),
+                ]),
+                Button::new([SP::ControlId(2), SP::PosX(150), SP::PosY(10), SP::Width(100), SP::Height(20),
+                    SP::DidClick({ let f = click.clone(); move|_|f(String::from("button")) }),
+                    SP::DidMouseEnter({ let f = enter.clone(); move|e: MouseEvent|f(format!("{} {}", e.x, e.y)) }),
+                    SP::DidMouseLeave({ let f = leave.clone(); move|_|f(String::new()) }),
+                    SP::DidMouseDown({ let f = down.clone(); move|e: MouseEvent|f(format!("{:?} {:?}", e.button, e.buttons)) }),
+                ]),
+                Panel::new([SP::ControlId(3), SP::PosY(50), SP::Width(300), SP::Height(50),
+                    SP::DidMouseMove({ let f = moved.clone(); move|e: MouseEvent|f(format!("{} {}", e.x, e.y)) }),
+                ]),
+            ))
+        };
+        let mut context = UIContext::create(backend.clone()).update(&view);
+        let mut hub = EventHub::new();
+        let mut pump = |context: Box<UIContext>| {
+            let mut context = context;
+            while let Some(msg) = backend.peekMessage() {
+                backend.dispatchMessage(&msg);
+                hub.enqueueEvent(&*backend, msg);
+                context = view.render(context, "/", "0", Some(msg));
+                hub.putListeners(&context);
+                hub.dispatchEvents(&*backend);
+            }
+            context
+        };
+        let (textBox, button) = (context.items["0:1_1"].hwnd, context.items["0:1_2"].hwnd);
+
+        backend.focus(textBox);
+        backend.setModifiers(Modifiers { shift: true, ..Default::default() });
+        backend.pressKey(0x41);
+        // the modifiers are read when the key message is dispatched
+        context = pump(context);
+        backend.setModifiers(Default::default());
+        backend.pressKey(VK_RETURN);
+        backend.focus(button);
+        backend.pressKey(VK_SPACE);
+        context = pump(context);
+        assert_eq!(*log.borrow(), vec![
+            format!("focus {:?}", textBox),
+            String::from("key 65 Modifiers { shift: true, control: false, alt: false } false"),
+            String::from("key 13 Modifiers { shift: false, control: false, alt: false } false"),
+            format!("blur {:?}", textBox),
+            String::from("click button"),
+        ]);
+
+        // the panel gets the moves of its own, the button only enters and leaves
+        log.borrow_mut().clear();
+        let panel = context.items["0:1_3"].hwnd;
+        backend.mouse(button, WM_MOUSEMOVE, Point { x: 5, y: 6 }, 0);
+        backend.mouse(button, WM_RBUTTONDOWN, Point { x: 5, y: 6 }, MK_RBUTTON);
+        backend.mouse(panel, WM_MOUSEMOVE, Point { x: 40, y: 30 }, 0);
+        backend.mouse(panel, WM_MOUSEMOVE, Point { x: 41, y: 30 }, 0);
+        let _ = pump(context);
+        assert_eq!(*log.borrow(), vec![
+            "enter 5 6", "down Some(Right) MouseButtons { left: false, right: true, middle: false }", "leave ", "move 40 30", "move 41 30",
+        ]);
+    }
+
+    #[test]
+    fn it_reports_click_change_and_resize_payloads() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let log = Rc::new(RefCell::new(vec![]));
+        let view = {
+            let log = log.clone();
+            Window::new([SP::Width(300), SP::Height(100)])
+                .onResize({ let log = log.clone(); move|e: ResizeEvent|log.borrow_mut().push(format!("resize {:?}", e.rect)) })
+                .content(move||(
+                    CheckBox::new([SP::ControlId(1)])
+                        .onClick({ let log = log.clone(); move|e: ClickEvent|log.borrow_mut().push(format!("click {}", e.selected)) }),
+                    TextBox::new([SP::ControlId(2)])
+                        .onChange({ let log = log.clone(); move|e: ChangeEvent|log.borrow_mut().push(format!("change {} {:?}", e.text, e.selectedIndex)) }),
+                    // handlers taking props still get them
+                    TextBox::new([SP::ControlId(3)])
+                        .content({ let log = log.clone(); move|props: Vec<SharedProps>|log.borrow_mut().push(format!("props {:?}", props)) }),
+                ))
+        };
+        let context = UIContext::create(backend.clone()).update(&view);
+        let (window, checkBox) = (context.items["0"].hwnd, context.items["0:1_1"].hwnd);
+        let (textBox, oldTextBox) = (context.items["0:1_2"].hwnd, context.items["0:1_3"].hwnd);
+
+        backend.setSelected(checkBox, true);
+        backend.postMessage(Message { hwnd: checkBox, message: BM_CLICK, ..Default::default() });
+        for (hwnd, text) in [(textBox, "milk"), (oldTextBox, "bread")] {
+            backend.setTitle(hwnd, text);
+            backend.postMessage(Message { hwnd, message: WM_CHAR, wParam: 'k' as usize, ..Default::default() });
+        }
+        backend.resize(window, 400, 200);
+        let mut context = context;
+        while let Some(msg) = backend.peekMessage() {
+            backend.dispatchMessage(&msg);
+            let mut hub = EventHub::new();
+            hub.enqueueEvent(&*backend, msg);
+            context = view.render(context, "/", "0", Some(msg));
+            hub.putListeners(&context);
+            hub.dispatchEvents(&*backend);
+        }
+
+        assert_eq!(*log.borrow(), vec![
+            "click true", "change milk None", "props [Title(bread)]",
+            "resize Rect { left: 0, top: 0, right: 400, bottom: 200 }",
+        ]);
+    }
+
+    #[test]
+    fn it_reports_destroy_payload() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let log = Rc::new(RefCell::new(vec![]));
+        let view = {
+            let log = log.clone();
+            Window::new([SP::Width(300), SP::Height(100), SP::OnDestroy(move|e: DestroyEvent|log.borrow_mut().push(e.target))])
+        };
+        let context = UIContext::create(backend.clone()).update(&view);
+        let window = context.items["0"].hwnd;
+
+        backend.close(window);
+        let mut context = context;
+        while let Some(msg) = backend.peekMessage() {
+            let mut hub = EventHub::new();
+            hub.enqueueEvent(&*backend, msg);
+            context = view.render(context, "/", "0", Some(msg));
+            hub.putListeners(&context);
+            hub.dispatchEvents(&*backend);
+        }
+
+        assert_eq!(*log.borrow(), vec![window]);
+    }
+}
@@ -0,0 +1,384 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use crate::muffui::*;
+
+/// direction a stack puts its children in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Row,
+    Column,
+}
+
+/// placement of the children across the stacking direction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// children take the whole line
+    Stretch,
+}
+
+/// placement of the children along the stacking direction, when they don't grow into the free space
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// free space goes between the children
+    SpaceBetween,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlexStyle {
+    pub direction: Direction,
+    pub spacing: i32,
+    pub padding: i32,
+    pub align: Align,
+    pub justify: Justify,
+    pub wrap: bool,
+}
+
+impl From<&Vec<SharedProps>> for FlexStyle {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        props.iter().fold(Self { ..Default::default() }, |style, prop|match prop {
+            SharedProps::Direction(direction) => Self { direction: *direction, ..style },
+            SharedProps::Spacing(spacing) => Self { spacing: *spacing, ..style },
+            SharedProps::Padding(padding) => Self { padding: *padding, ..style },
+            SharedProps::AlignItems(align) => Self { align: *align, ..style },
+            SharedProps::JustifyContent(justify) => Self { justify: *justify, ..style },
+            SharedProps::Wrap(wrap) => Self { wrap: *wrap, ..style },
+            _ => style,
+        })
+    }
+}
+
+/// preferred size of a child and how it takes part in the free or missing space
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlexItem {
+    pub width: i32,
+    pub height: i32,
+    pub grow: i32,
+    pub shrink: i32,
+    pub limits: SizeLimits,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self { width: 0, height: 0, grow: 0, shrink: 1, limits: Default::default() }
+    }
+}
+
+impl From<&Vec<SharedProps>> for FlexItem {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        let size = |v: i32| if v == CW_USEDEFAULT { 0 } else { v.max(0) };
+        let item = Self { limits: SizeLimits::from(props), ..Default::default() };
+        props.iter().fold(item, |item, prop|match prop {
+            SharedProps::Width(width) => Self { width: size(*width), ..item },
+            SharedProps::Height(height) => Self { height: size(*height), ..item },
+            SharedProps::Grow(grow) => Self { grow: (*grow).max(0), ..item },
+            SharedProps::Shrink(shrink) => Self { shrink: (*shrink).max(0), ..item },
+            _ => item,
+        })
+    }
+}
+
+/// rects of the children inside a stack with `size` client area, in the order of `items`
+pub fn layoutFlex(style: &FlexStyle, size: Size, items: &[FlexItem]) -> Vec<Rect> {
+    let isRow = style.direction == Direction::Row;
+    let (mainSize, crossSize) = match isRow {
+        true => (size.cx - 2 * style.padding, size.cy - 2 * style.padding),
+        _ => (size.cy - 2 * style.padding, size.cx - 2 * style.padding),
+    };
+    let main = |item: &FlexItem| if isRow { item.width } else { item.height };
+    let cross = |item: &FlexItem| if isRow { item.height } else { item.width };
+    let limitMain = |item: &FlexItem, v: i32| if isRow { item.limits.width(v) } else { item.limits.height(v) };
+    let limitCross = |item: &FlexItem, v: i32| if isRow { item.limits.height(v) } else { item.limits.width(v) };
+
+    // children of each line, a new line starts when the next child doesn't fit
+    let mut lines: Vec<Vec<usize>> = vec![];
+    let mut used = 0;
+    for (idx, item) in items.iter().enumerate() {
+        match lines.last_mut() {
+            Some(line) if !style.wrap || used + style.spacing + main(item) <= mainSize => {
+                used += style.spacing + main(item);
+                line.push(idx);
+            },
+            _ => {
+                used = main(item);
+                lines.push(vec![idx]);
+            },
+        }
+    }
+
+    let mut res = vec![Rect { ..Default::default() }; items.len()];
+    let mut crossPos = style.padding;
+    for line in lines.iter() {
+        let lineCross = match style.wrap {
+            true => line.iter().map(|idx|cross(&items[*idx])).max().unwrap_or_default(),
+            _ => crossSize,
+        };
+
+        let preferred = line.iter().map(|idx|limitMain(&items[*idx], main(&items[*idx]))).collect::<Vec<_>>();
+        let gaps = style.spacing * (line.len() as i32 - 1);
+        // children stopped by their limits keep that size and the others share the rest
+        let mut sizes = preferred.clone();
+        let mut frozen = vec![false; line.len()];
+        loop {
+            let fixed = sizes.iter().zip(preferred.iter()).zip(frozen.iter())
+                .map(|((s, p), f)|if *f { *s } else { *p })
+                .sum::<i32>();
+            let free = mainSize - fixed - gaps;
+            let weights = line.iter().zip(frozen.iter()).map(|(idx, f)|match (*f, free > 0) {
+                (true, _) => 0,
+                (_, true) => items[*idx].grow,
+                _ => items[*idx].shrink * main(&items[*idx]),
+            }).collect::<Vec<_>>();
+            if free == 0 || weights.iter().all(|w|*w == 0) {
+                break;
+            }
+
+            let mut limited = false;
+            for (pos, delta) in utils::share(free, &weights).into_iter().enumerate() {
+                if frozen[pos] {
+                    continue;
+                }
+                let size = (preferred[pos] + delta).max(0);
+                sizes[pos] = limitMain(&items[line[pos]], size);
+                if sizes[pos] != size {
+                    (frozen[pos], limited) = (true, true);
+                }
+            }
+            if !limited {
+                break;
+            }
+        }
+
+        let free = (mainSize - sizes.iter().sum::<i32>() - gaps).max(0);
+        let (mut mainPos, gap) = match style.justify {
+            Justify::Start => (style.padding, style.spacing),
+            Justify::Center => (style.padding + free / 2, style.spacing),
+            Justify::End => (style.padding + free, style.spacing),
+            Justify::SpaceBetween if line.len() > 1 => (style.padding, style.spacing + free / (line.len() as i32 - 1)),
+            Justify::SpaceBetween => (style.padding, style.spacing),
+        };
+
+        for (idx, itemMain) in line.iter().zip(sizes) {
+            let itemCross = limitCross(&items[*idx], match style.align {
+                Align::Stretch => lineCross,
+                _ => cross(&items[*idx]).min(lineCross.max(0)),
+            });
+            let offset = match style.align {
+                Align::Start | Align::Stretch => 0,
+                Align::Center => (lineCross - itemCross) / 2,
+                Align::End => lineCross - itemCross,
+            };
+            let (x, y, w, h) = match isRow {
+                true => (mainPos, crossPos + offset, itemMain, itemCross),
+                _ => (crossPos + offset, mainPos, itemCross, itemMain),
+            };
+            res[*idx] = Rect { left: x, top: y, right: x + w, bottom: y + h };
+            mainPos += itemMain + gap;
+        }
+
+        crossPos += lineCross + style.spacing;
+    }
+
+    res
+}
+
+/// container that places its children one after another from their Width/Height,
+/// with Spacing, Padding, AlignItems, JustifyContent, Wrap and the Grow/Shrink of the children
+#[derive(Clone)]
+pub struct Flex {
+    pub children: Option<Rc<dyn Renderable>>,
+    pub props: Vec<SharedProps>,
+}
+
+impl Renderable for Flex {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        Some(self.children.as_ref()?.clone())
+    }
+
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut context = context.render(parent, index, self, msg);
+        let children = match &self.children {
+            Some(children) => children,
+            _ => return context,
+        };
+
+        let childIndex = format!("{}:1", index);
+        if msg.is_none() {
+            let items = children.layoutItems(&childIndex);
+            let dpi = context.dpi(index);
+            let rects = layoutFlex(&FlexStyle::from(&scaleProps(&self.props, dpi)), context.clientSize(index), &items.iter()
+                .map(|(_, props)|FlexItem::from(&measure(&*context.backend, props, dpi)))
+                .collect::<Vec<_>>());
+
+            context.place(&childIndex, items.into_iter().map(|(path, _)|path).zip(rects));
+        }
+
+        children.render(context, index, &childIndex, msg)
+    }
+
+    fn toViewState(&self) -> Vec<SharedProps> {
+        self.props.clone()
+    }
+}
+
+impl Flex {
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
+        let defaultProps = vec![
+            SP::ClassName("panel#0x8002"),
+            SP::Renderer("flex"),
+        ];
+        Self {
+            children: None,
+            props: defaultProps.merge(props.into()),
+        }
+    }
+
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
+        Self {
+            children: Some(Rc::new(children().intoChildren())),
+            ..self
+        }
+    }
+}
+
+/// `Flex` that puts its children left to right
+pub struct HStack;
+
+// returns the `Flex` it configures, so `view!` can use HStack(...) like any other container
+#[allow(clippy::new_ret_no_self)]
+impl HStack {
+    #[allow(dead_code)]
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Flex {
+        Flex::new(vec![SP::Direction(Direction::Row)].merge(props.into()))
+    }
+}
+
+/// `Flex` that puts its children top to bottom
+pub struct VStack;
+
+#[allow(clippy::new_ret_no_self)]
+impl VStack {
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Flex {
+        Flex::new(vec![SP::Direction(Direction::Column)].merge(props.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(width: i32, height: i32) -> FlexItem {
+        FlexItem { width, height, ..Default::default() }
+    }
+
+    fn rects(style: FlexStyle, items: &[FlexItem]) -> Vec<[i32; 4]> {
+        layoutFlex(&style, Size { cx: 200, cy: 100 }, items).iter()
+            .map(|r|[r.left, r.top, r.right - r.left, r.bottom - r.top])
+            .collect()
+    }
+
+    #[test]
+    fn it_stacks_children_with_spacing_and_padding() {
+        let row = FlexStyle { spacing: 4, padding: 10, ..Default::default() };
+        assert_eq!(rects(row, &[item(50, 20), item(30, 40)]), vec![[10, 10, 50, 20], [64, 10, 30, 40]]);
+
+        let column = FlexStyle { direction: Direction::Column, spacing: 2, align: Align::Stretch, ..Default::default() };
+        assert_eq!(rects(column, &[item(50, 27), item(30, 27)]), vec![[0, 0, 200, 27], [0, 29, 200, 27]]);
+    }
+
+    #[test]
+    fn it_aligns_and_justifies_children() {
+        let items = [item(50, 20), item(30, 40)];
+        let style = |align, justify| FlexStyle { align, justify, ..Default::default() };
+
+        assert_eq!(rects(style(Align::Center, Justify::Center), &items), vec![[60, 40, 50, 20], [110, 30, 30, 40]]);
+        assert_eq!(rects(style(Align::End, Justify::End), &items), vec![[120, 80, 50, 20], [170, 60, 30, 40]]);
+        assert_eq!(rects(style(Align::Stretch, Justify::SpaceBetween), &items), vec![[0, 0, 50, 100], [170, 0, 30, 100]]);
+    }
+
+    #[test]
+    fn it_grows_and_shrinks_children() {
+        let grow = [item(20, 10), FlexItem { grow: 1, ..item(20, 10) }, FlexItem { grow: 3, ..item(0, 10) }];
+        assert_eq!(rects(FlexStyle::default(), &grow), vec![[0, 0, 20, 10], [20, 0, 60, 10], [80, 0, 120, 10]]);
+
+        let shrink = [item(150, 10), FlexItem { shrink: 0, ..item(50, 10) }, item(50, 10)];
+        assert_eq!(rects(FlexStyle::default(), &shrink), vec![[0, 0, 113, 10], [113, 0, 50, 10], [163, 0, 37, 10]]);
+    }
+
+    #[test]
+    fn it_keeps_children_in_their_limits() {
+        let limits = |minWidth, maxWidth, maxHeight| SizeLimits { minWidth, maxWidth, maxHeight, ..Default::default() };
+        let grow = [FlexItem { grow: 1, limits: limits(0, 50, 30), ..item(20, 10) }, FlexItem { grow: 1, ..item(20, 10) }];
+        let style = FlexStyle { align: Align::Stretch, ..Default::default() };
+        assert_eq!(rects(style, &grow), vec![[0, 0, 50, 30], [50, 0, 150, 100]]);
+
+        let shrink = [FlexItem { limits: limits(140, i32::MAX, i32::MAX), ..item(150, 10) }, item(150, 10)];
+        assert_eq!(rects(FlexStyle::default(), &shrink), vec![[0, 0, 140, 10], [140, 0, 60, 10]]);
+    }
+
+    #[test]
+    fn it_measures_children_without_size() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = HStack::new([SP::Width(300), SP::Height(40), SP::Spacing(4), SP::AlignItems(Align::Center)]).content(||(
+            Label::new(SP::Title("Title:")),
+            Button::new([SP::Title("Save"), SP::MinWidth(60)]),
+        ));
+
+        let context = view.render(UIContext::create(backend.clone()), "/", "0", None);
+        let rect = |path: &str| {
+            let r = backend.getControlRect(context.items[path].hwnd).unwrap();
+            [r.left, r.top, r.right - r.left, r.bottom - r.top]
+        };
+        assert_eq!(rect("0:1_1"), [0, 12, 36, 16]);
+        assert_eq!(rect("0:1_2"), [40, 8, 60, 24]);
+    }
+
+    #[test]
+    fn it_wraps_children_into_lines() {
+        let style = FlexStyle { wrap: true, spacing: 5, ..Default::default() };
+        let items = [item(90, 20), item(90, 30), item(90, 10), item(250, 10)];
+
+        assert_eq!(rects(style, &items), vec![
+            [0, 0, 90, 20], [95, 0, 90, 30],
+            [0, 35, 90, 10],
+            [0, 50, 200, 10],
+        ]);
+    }
+
+    #[test]
+    fn it_places_rendered_children_and_reflows_on_resize() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = Window::new([SP::Width(300), SP::Height(100)]).content(||
+            HStack::new([SP::Anchor(ANF_DOCK_ALL), SP::ControlId(1), SP::Spacing(10), SP::Padding(5)]).content(||(
+                Button::new([SP::ControlId(2), SP::Width(60), SP::Height(20), SP::PosX(999)]),
+                ForEach::keyed(vec![3, 4], |i|*i, |i, _|TextBox::new([SP::ControlId(i), SP::Width(40), SP::Height(20), SP::Grow(1)])),
+            ))
+        );
+        let render = |context| view.render(context, "/", "0", None);
+        let rect = |context: &UIContext, path: &str| {
+            let r = backend.getControlRect(context.items[path].hwnd).unwrap();
+            [r.left, r.top, r.right - r.left, r.bottom - r.top]
+        };
+
+        let context = render(render(UIContext::create(backend.clone())));
+        assert_eq!(rect(&context, "0:1_1:1_1"), [5, 5, 60, 20]);
+        assert_eq!(rect(&context, "0:1_1:1_2[3]"), [75, 5, 105, 20]);
+        assert_eq!(rect(&context, "0:1_1:1_2[4]"), [190, 5, 105, 20]);
+
+        backend.resize(context.items["0"].hwnd, 400, 100);
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+        let context = render(context);
+        assert_eq!(rect(&context, "0:1_1"), [0, 0, 400, 100]);
+        assert_eq!(rect(&context, "0:1_1:1_2[3]"), [75, 5, 155, 20]);
+        assert_eq!(rect(&context, "0:1_1:1_2[4]"), [240, 5, 155, 20]);
+    }
+}
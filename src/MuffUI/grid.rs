@@ -0,0 +1,295 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use crate::muffui::*;
+
+/// size of a grid row or column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridLength {
+    Fixed(i32),
+    /// as large as the largest child in it
+    Auto,
+    /// share of the space left by the fixed and auto tracks
+    Star(i32),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GridStyle {
+    pub rows: Vec<GridLength>,
+    pub columns: Vec<GridLength>,
+    pub spacing: i32,
+    pub padding: i32,
+}
+
+impl From<&Vec<SharedProps>> for GridStyle {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        props.iter().fold(Self { ..Default::default() }, |style, prop|match prop {
+            SharedProps::Rows(rows) => Self { rows: rows.clone(), ..style },
+            SharedProps::Columns(columns) => Self { columns: columns.clone(), ..style },
+            SharedProps::Spacing(spacing) => Self { spacing: *spacing, ..style },
+            SharedProps::Padding(padding) => Self { padding: *padding, ..style },
+            _ => style,
+        })
+    }
+}
+
+/// preferred size of a child, its cells and where it sits in them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridItem {
+    pub width: i32,
+    pub height: i32,
+    pub row: usize,
+    pub column: usize,
+    pub rowSpan: usize,
+    pub columnSpan: usize,
+    pub horizontal: Align,
+    pub vertical: Align,
+    pub limits: SizeLimits,
+}
+
+impl Default for GridItem {
+    fn default() -> Self {
+        Self {
+            width: 0, height: 0,
+            row: 0, column: 0, rowSpan: 1, columnSpan: 1,
+            horizontal: Align::Stretch, vertical: Align::Stretch,
+            limits: Default::default(),
+        }
+    }
+}
+
+impl From<&Vec<SharedProps>> for GridItem {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        let size = |v: i32| if v == CW_USEDEFAULT { 0 } else { v.max(0) };
+        let item = Self { limits: SizeLimits::from(props), ..Default::default() };
+        props.iter().fold(item, |item, prop|match prop {
+            SharedProps::Width(width) => Self { width: size(*width), ..item },
+            SharedProps::Height(height) => Self { height: size(*height), ..item },
+            SharedProps::Row(row) => Self { row: *row, ..item },
+            SharedProps::Column(column) => Self { column: *column, ..item },
+            SharedProps::RowSpan(span) => Self { rowSpan: *span, ..item },
+            SharedProps::ColumnSpan(span) => Self { columnSpan: *span, ..item },
+            SharedProps::HorizontalAlign(align) => Self { horizontal: *align, ..item },
+            SharedProps::VerticalAlign(align) => Self { vertical: *align, ..item },
+            _ => item,
+        })
+    }
+}
+
+/// sizes of the tracks of one axis, `items` are (first track, span, preferred size) of the children
+fn solveTracks(lengths: &[GridLength], available: i32, spacing: i32, items: &[(usize, usize, i32)]) -> Vec<i32> {
+    let count = lengths.len();
+    let mut sizes = lengths.iter().map(|l|match l {
+        GridLength::Fixed(size) => (*size).max(0),
+        _ => 0,
+    }).collect::<Vec<_>>();
+
+    // children of one auto track first, then the spanning ones add what is still missing to the last auto track
+    let mut items = items.to_vec();
+    items.sort_by_key(|(_, span, _)|*span);
+    for (start, span, size) in items {
+        let tracks = start..start + span;
+        if lengths[tracks.clone()].iter().any(|l|matches!(l, GridLength::Star(_))) {
+            continue;
+        }
+        let missing = size - sizes[tracks.clone()].iter().sum::<i32>() - spacing * (span as i32 - 1);
+        if let Some(last) = tracks.rev().find(|idx|lengths[*idx] == GridLength::Auto) {
+            sizes[last] += missing.max(0);
+        }
+    }
+
+    let used = sizes.iter().sum::<i32>() + spacing * (count as i32 - 1);
+    let weights = lengths.iter().map(|l|match l {
+        GridLength::Star(weight) => (*weight).max(0),
+        _ => 0,
+    }).collect::<Vec<_>>();
+    sizes.iter_mut().zip(utils::share((available - used).max(0), &weights)).for_each(|(s, d)|*s += d);
+
+    sizes
+}
+
+/// offset and size of the cells from `start` spanning `span` tracks, and the part a child of `size` takes there
+/// kept in `limit`
+fn place(sizes: &[i32], spacing: i32, padding: i32, (start, span): (usize, usize), size: i32, align: Align, limit: impl Fn(i32) -> i32) -> (i32, i32) {
+    let offset = padding + sizes[..start].iter().sum::<i32>() + spacing * start as i32;
+    let cell = sizes[start..start + span].iter().sum::<i32>() + spacing * (span as i32 - 1);
+    let size = limit(match align {
+        Align::Stretch => cell,
+        _ => size.min(cell),
+    });
+
+    match align {
+        Align::Start | Align::Stretch => (offset, size),
+        Align::Center => (offset + (cell - size) / 2, size),
+        Align::End => (offset + cell - size, size),
+    }
+}
+
+/// rects of the children inside a grid with `size` client area, in the order of `items`.
+/// a grid without rows or columns has a single star track there, cells out of the grid are moved into the last track
+pub fn layoutGrid(style: &GridStyle, size: Size, items: &[GridItem]) -> Vec<Rect> {
+    let single = [GridLength::Star(1)];
+    let rows = if style.rows.is_empty() { &single[..] } else { &style.rows[..] };
+    let columns = if style.columns.is_empty() { &single[..] } else { &style.columns[..] };
+    let cells = |start: usize, span: usize, count: usize| {
+        let start = start.min(count - 1);
+        (start, span.clamp(1, count - start))
+    };
+
+    let items = items.iter().map(|item|{
+        let (row, rowSpan) = cells(item.row, item.rowSpan, rows.len());
+        let (column, columnSpan) = cells(item.column, item.columnSpan, columns.len());
+        GridItem { row, rowSpan, column, columnSpan, ..*item }
+    }).collect::<Vec<_>>();
+
+    let heights = solveTracks(rows, size.cy - 2 * style.padding, style.spacing, &items.iter()
+        .map(|item|(item.row, item.rowSpan, item.height))
+        .collect::<Vec<_>>());
+    let widths = solveTracks(columns, size.cx - 2 * style.padding, style.spacing, &items.iter()
+        .map(|item|(item.column, item.columnSpan, item.width))
+        .collect::<Vec<_>>());
+
+    items.iter().map(|item|{
+        let (left, width) = place(&widths, style.spacing, style.padding, (item.column, item.columnSpan), item.width, item.horizontal, |v|item.limits.width(v));
+        let (top, height) = place(&heights, style.spacing, style.padding, (item.row, item.rowSpan), item.height, item.vertical, |v|item.limits.height(v));
+
+        Rect { left, top, right: left + width, bottom: top + height }
+    }).collect()
+}
+
+/// container that places its children into the cells of Rows and Columns,
+/// children pick their cells with Row, Column, RowSpan, ColumnSpan and align there with HorizontalAlign and VerticalAlign
+#[derive(Clone)]
+pub struct Grid {
+    pub children: Option<Rc<dyn Renderable>>,
+    pub props: Vec<SharedProps>,
+}
+
+impl Renderable for Grid {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        Some(self.children.as_ref()?.clone())
+    }
+
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut context = context.render(parent, index, self, msg);
+        let children = match &self.children {
+            Some(children) => children,
+            _ => return context,
+        };
+
+        let childIndex = format!("{}:1", index);
+        if msg.is_none() {
+            let items = children.layoutItems(&childIndex);
+            let dpi = context.dpi(index);
+            let rects = layoutGrid(&GridStyle::from(&scaleProps(&self.props, dpi)), context.clientSize(index), &items.iter()
+                .map(|(_, props)|GridItem::from(&measure(&*context.backend, props, dpi)))
+                .collect::<Vec<_>>());
+
+            context.place(&childIndex, items.into_iter().map(|(path, _)|path).zip(rects));
+        }
+
+        children.render(context, index, &childIndex, msg)
+    }
+
+    fn toViewState(&self) -> Vec<SharedProps> {
+        self.props.clone()
+    }
+}
+
+#[allow(dead_code)]
+impl Grid {
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
+        let defaultProps = vec![
+            SP::ClassName("panel#0x8002"),
+            SP::Renderer("grid"),
+        ];
+        Self {
+            children: None,
+            props: defaultProps.merge(props.into()),
+        }
+    }
+
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
+        Self {
+            children: Some(Rc::new(children().intoChildren())),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use GridLength::*;
+
+    fn cell(row: usize, column: usize, width: i32, height: i32) -> GridItem {
+        GridItem { row, column, width, height, ..Default::default() }
+    }
+
+    fn rects(style: GridStyle, items: &[GridItem]) -> Vec<[i32; 4]> {
+        layoutGrid(&style, Size { cx: 300, cy: 100 }, items).iter()
+            .map(|r|[r.left, r.top, r.right - r.left, r.bottom - r.top])
+            .collect()
+    }
+
+    #[test]
+    fn it_sizes_fixed_auto_and_star_tracks() {
+        let style = GridStyle {
+            columns: vec![Fixed(50), Auto, Star(1), Star(2)],
+            rows: vec![Auto, Star(1)],
+            spacing: 4, padding: 2,
+        };
+        let items = [cell(0, 0, 10, 20), cell(0, 1, 70, 10), cell(1, 2, 0, 0), cell(1, 3, 0, 0)];
+
+        // columns 50, 70, (296 - 132) / 3 = 54 and 110, rows 20 and 96 - 24
+        assert_eq!(rects(style, &items), vec![
+            [2, 2, 50, 20], [56, 2, 70, 20], [130, 26, 54, 72], [188, 26, 110, 72],
+        ]);
+    }
+
+    #[test]
+    fn it_spans_cells() {
+        let style = GridStyle { columns: vec![Auto, Auto, Star(1)], rows: vec![Auto, Auto], spacing: 10, ..Default::default() };
+        let items = [
+            cell(0, 0, 40, 20),
+            GridItem { columnSpan: 2, ..cell(1, 0, 150, 20) },
+            GridItem { rowSpan: 2, columnSpan: 5, ..cell(0, 1, 0, 0) },
+        ];
+
+        // the spanning child widens the last auto column by what the first one can't give
+        assert_eq!(rects(style, &items), vec![[0, 0, 40, 20], [0, 30, 150, 20], [50, 0, 250, 50]]);
+    }
+
+    #[test]
+    fn it_aligns_children_in_cells() {
+        let style = GridStyle { columns: vec![Fixed(100), Fixed(100), Fixed(100)], ..Default::default() };
+        let at = |column, horizontal, vertical| GridItem { horizontal, vertical, ..cell(0, column, 40, 20) };
+        let items = [at(0, Align::Start, Align::End), at(1, Align::Center, Align::Center), at(2, Align::End, Align::Stretch)];
+
+        assert_eq!(rects(style, &items), vec![[0, 80, 40, 20], [130, 40, 40, 20], [260, 0, 40, 100]]);
+    }
+
+    #[test]
+    fn it_renders_form_into_cells() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = Grid::new([
+            SP::Width(300), SP::Height(60), SP::Padding(5), SP::Spacing(5),
+            SP::Columns([Auto, Star(1)]), SP::Rows([Auto, Auto]),
+        ]).content(||(
+            Label::new([SP::Title("Name:"), SP::Width(50), SP::Height(20)]),
+            TextBox::new([SP::Column(1), SP::Height(20)]),
+            Label::new([SP::Title("Notes:"), SP::Row(1), SP::Width(60), SP::Height(20), SP::HorizontalAlign(Align::End)]),
+            TextBox::new([SP::Row(1), SP::Column(1), SP::Height(20)]),
+        ));
+
+        let context = view.render(UIContext::create(backend.clone()), "/", "0", None);
+        let rect = |path: &str| {
+            let r = backend.getControlRect(context.items[path].hwnd).unwrap();
+            [r.left, r.top, r.right - r.left, r.bottom - r.top]
+        };
+        assert_eq!(rect("0:1_1"), [5, 5, 60, 20]);
+        assert_eq!(rect("0:1_2"), [70, 5, 225, 20]);
+        assert_eq!(rect("0:1_3"), [5, 30, 60, 20]);
+        assert_eq!(rect("0:1_4"), [70, 30, 225, 20]);
+    }
+}
@@ -1,32 +1,22 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
-use crate::Win;
 use crate::muffui::*;
 use crate::muffui::SharedProps::*;
 
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct GroupBox {
     pub children: Option<Rc<dyn Renderable>>,
     pub props: Vec<SharedProps>,
 }
 
-impl Default for GroupBox {
-    fn default() -> Self {
-        Self {
-            children: Default::default(),
-            props: vec![],
-        }
-    }
-}
-
 impl Renderable for GroupBox {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         Some(self.children.as_ref()?.clone())
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let context = context.render(parent, index, self, msg);
         match &self.children {
             Some(children) => children.render(context, index, &format!("{}:1", index), msg),
@@ -35,10 +25,11 @@ impl Renderable for GroupBox {
     }
 
     fn toViewState(&self) -> Vec<SharedProps> {
-        self.props.iter().map(|item|item.clone()).collect()
+        self.props.to_vec()
     }
 }
 
+#[allow(dead_code)]
 impl GroupBox {
     pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
         let defaultProps = vec![
@@ -86,21 +77,9 @@ impl GroupBox {
         }
     }
 
-    pub fn content<A: 'static, B: 'static, C: 'static, D: 'static, E: 'static, F: 'static, G: 'static, T: 'static, FF>(self, mut children: FF) -> Self
-        where
-            A: Renderable,
-            B: Renderable,
-            C: Renderable,
-            D: Renderable,
-            E: Renderable,
-            F: Renderable,
-            G: Renderable,
-            T: Into<ContentArgs<A, B, C, D, E, F, G>>,
-            FF: FnMut() -> T
-    {
-        let args: ContentArgs<A, B, C, D, E, F, G> = children().into();
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
         Self {
-            children: Some(Rc::new(args)),
+            children: Some(Rc::new(children().intoChildren())),
             ..self
         }
     }
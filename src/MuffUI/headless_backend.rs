@@ -0,0 +1,506 @@
+#![allow(non_snake_case)]
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use crate::muffui::*;
+
+/// in-memory replacement of a native control
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default)]
+pub struct HeadlessNode {
+    pub hwnd: Handle,
+    pub parent: Handle,
+    pub children: Vec<Handle>,
+    pub renderer: String,
+    pub className: String,
+    pub title: String,
+    pub controlId: i32,
+    pub anchor: usize,
+    /// outer rect in the client coordinates of the parent
+    pub rect: Rect,
+    pub visible: bool,
+    pub selected: bool,
+    pub selectItems: Vec<String>,
+    pub selectedIndex: Option<usize>,
+    pub fontFace: String,
+    pub fontSize: i32,
+    /// dpi of the monitor of a window, 0 for the controls in it
+    pub dpi: u32,
+    /// content size and offset shown by the scroll bars of a scroll view
+    pub scroll: (Size, Point),
+    /// rects of the last layout debug outlines drawn over the control
+    pub outlines: Vec<Rect>,
+}
+
+/// backend without a window system, keeps the control tree in memory
+pub struct HeadlessBackend {
+    nodes: RefCell<HashMap<Handle, HeadlessNode>>,
+    roots: RefCell<Vec<Handle>>,
+    queue: RefCell<VecDeque<Message>>,
+    /// messages other threads have posted with a `WakeHandle`
+    posted: Arc<(Mutex<VecDeque<Message>>, Condvar)>,
+    lastHandle: Cell<isize>,
+    systemDpi: Cell<u32>,
+    modifiers: Cell<Modifiers>,
+    focused: Cell<Handle>,
+    notifyOnDispatch: Cell<bool>,
+    /// messages that have reached the default processing of `dispatchMessage`
+    dispatched: RefCell<Vec<Message>>,
+}
+
+impl Default for HeadlessBackend {
+    fn default() -> Self {
+        Self {
+            nodes: RefCell::new(HashMap::new()),
+            roots: RefCell::new(vec![]),
+            queue: RefCell::new(VecDeque::new()),
+            posted: Arc::new((Mutex::new(VecDeque::new()), Condvar::new())),
+            lastHandle: Cell::new(0),
+            systemDpi: Cell::new(BASE_DPI),
+            modifiers: Cell::new(Default::default()),
+            focused: Cell::new(Handle(0)),
+            notifyOnDispatch: Cell::new(false),
+            dispatched: RefCell::new(vec![]),
+        }
+    }
+}
+
+impl HeadlessBackend {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self { ..Default::default() }
+    }
+
+    fn nextHandle(&self) -> Handle {
+        self.lastHandle.set(self.lastHandle.get() + 1);
+        Handle(self.lastHandle.get())
+    }
+
+    pub fn node(&self, hwnd: Handle) -> Option<HeadlessNode> {
+        self.nodes.borrow().get(&hwnd).cloned()
+    }
+
+    #[allow(dead_code)]
+    pub fn roots(&self) -> Vec<Handle> {
+        self.roots.borrow().clone()
+    }
+
+    /// all live nodes, parents before children in creation order
+    #[allow(dead_code)]
+    pub fn nodes(&self) -> Vec<HeadlessNode> {
+        let mut res = vec![];
+        let mut stack = self.roots().into_iter().rev().collect::<Vec<_>>();
+        while let Some(hwnd) = stack.pop() {
+            if let Some(node) = self.node(hwnd) {
+                stack.extend(node.children.iter().rev());
+                res.push(node);
+            }
+        }
+
+        res
+    }
+
+    /// resizes a window the way a user would drag its border, no further than its size limits
+    #[allow(dead_code)]
+    pub fn resize(&self, hwnd: Handle, width: i32, height: i32) {
+        let size = AnchorMap::windowLimits(self, hwnd).clamp(Size { cx: width, cy: height });
+        if let Some(node) = self.nodes.borrow_mut().get_mut(&hwnd) {
+            node.rect.right = node.rect.left + size.cx;
+            node.rect.bottom = node.rect.top + size.cy;
+        }
+        self.postMessage(Message { hwnd, message: WM_SIZE, ..Default::default() });
+    }
+
+    /// dpi of the monitor new windows open on
+    #[allow(dead_code)]
+    pub fn setSystemDpi(&self, dpi: u32) {
+        self.systemDpi.set(dpi);
+    }
+
+    /// moves a window to a monitor with `dpi`, the way a user would drag it there
+    #[allow(dead_code)]
+    pub fn setDpi(&self, hwnd: Handle, dpi: u32) {
+        self.modify(hwnd, |n|n.dpi = dpi);
+        let dpi = dpi as usize & 0xffff;
+        self.postMessage(Message { hwnd, message: WM_DPICHANGED, wParam: dpi << 16 | dpi, ..Default::default() });
+    }
+
+    /// turns the mouse wheel over a control, `delta` is WHEEL_DELTA per notch and positive away from the user
+    #[allow(dead_code)]
+    pub fn wheel(&self, hwnd: Handle, delta: i32) {
+        let delta = delta as i16 as u16 as usize;
+        self.postMessage(Message { hwnd, message: WM_MOUSEWHEEL, wParam: delta << 16, ..Default::default() });
+    }
+
+    /// drags with the left button from `from` to `to`, both in the client area of `hwnd`
+    #[allow(dead_code)]
+    pub fn drag(&self, hwnd: Handle, from: Point, to: Point) {
+        self.mouse(hwnd, WM_LBUTTONDOWN, from, MK_LBUTTON);
+        self.mouse(hwnd, WM_MOUSEMOVE, to, MK_LBUTTON);
+        self.mouse(hwnd, WM_LBUTTONUP, to, 0);
+    }
+
+    /// notifies the view of the messages it dispatches like the win32 window procedure does, but the ones the loop routes
+    #[allow(dead_code)]
+    pub fn setNotifyOnDispatch(&self, notify: bool) {
+        self.notifyOnDispatch.set(notify);
+    }
+
+    /// messages the backend has processed since the last call, without the ones a handler has prevented the default of
+    #[allow(dead_code)]
+    pub fn takeDispatched(&self) -> Vec<Message> {
+        self.dispatched.take()
+    }
+
+    /// keys held down from now on, like a user would press Shift, Ctrl or Alt
+    #[allow(dead_code)]
+    pub fn setModifiers(&self, modifiers: Modifiers) {
+        self.modifiers.set(modifiers);
+    }
+
+    /// moves the focus to `hwnd` the way clicking or tabbing to it would
+    #[allow(dead_code)]
+    pub fn focus(&self, hwnd: Handle) {
+        let prev = self.focused.replace(hwnd);
+        if prev != Handle(0) && prev != hwnd {
+            self.postMessage(Message { hwnd: prev, message: WM_KILLFOCUS, wParam: hwnd.0 as usize, ..Default::default() });
+        }
+        self.postMessage(Message { hwnd, message: WM_SETFOCUS, wParam: prev.0 as usize, ..Default::default() });
+    }
+
+    /// presses and releases `key` over the control with the focus
+    #[allow(dead_code)]
+    pub fn pressKey(&self, key: u32) {
+        let hwnd = self.focused.get();
+        self.postMessage(Message { hwnd, message: WM_KEYDOWN, wParam: key as usize, lParam: 1 });
+        self.postMessage(Message { hwnd, message: WM_KEYUP, wParam: key as usize, lParam: 1 | 3 << 30 });
+    }
+
+    /// posts a mouse message at `point` in the client area of `hwnd`, `held` are the MK_* buttons held down
+    #[allow(dead_code)]
+    pub fn mouse(&self, hwnd: Handle, message: u32, point: Point, held: usize) {
+        let modifiers = self.modifiers.get();
+        let wParam = held | if modifiers.shift { MK_SHIFT } else { 0 } | if modifiers.control { MK_CONTROL } else { 0 };
+        let lParam = ((point.y as i16 as u16 as isize) << 16) | point.x as i16 as u16 as isize;
+        self.postMessage(Message { hwnd, message, wParam, lParam });
+    }
+
+    /// closes a window the way a user would with the close button
+    #[allow(dead_code)]
+    pub fn close(&self, hwnd: Handle) {
+        self.destroyControl(hwnd);
+        self.postMessage(Message { hwnd, message: WM_DESTROY, ..Default::default() });
+    }
+
+    fn modify<R: Default>(&self, hwnd: Handle, f: impl FnOnce(&mut HeadlessNode) -> R) -> R {
+        self.nodes.borrow_mut().get_mut(&hwnd).map(f).unwrap_or_default()
+    }
+
+    fn read<R: Default>(&self, hwnd: Handle, f: impl FnOnce(&HeadlessNode) -> R) -> R {
+        self.nodes.borrow().get(&hwnd).map(f).unwrap_or_default()
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn initialize(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn systemFontFace(&self) -> String {
+        String::from("Segoe UI")
+    }
+
+    fn systemFontSize(&self) -> i32 {
+        rescale(16, BASE_DPI, self.systemDpi.get())
+    }
+
+    fn systemDpi(&self) -> u32 {
+        self.systemDpi.get()
+    }
+
+    fn getDpi(&self, hwnd: Handle) -> u32 {
+        let mut node = self.node(hwnd);
+        while let Some(n) = node {
+            if n.dpi != 0 {
+                return n.dpi;
+            }
+            node = self.node(n.parent);
+        }
+
+        self.systemDpi.get()
+    }
+
+    fn createControl(&self, parent: Option<Handle>, desc: &ControlDesc) -> Handle {
+        let hwnd = self.nextHandle();
+        let parent = parent.filter(|p|self.nodes.borrow().contains_key(p));
+        let orDefault = |v: i32| if v == CW_USEDEFAULT { 0 } else { v };
+        let (posX, posY) = (orDefault(desc.posX), orDefault(desc.posY));
+        let (width, height) = (orDefault(desc.width).max(0), orDefault(desc.height).max(0));
+
+        let node = HeadlessNode {
+            hwnd,
+            parent: parent.unwrap_or(Handle(0)),
+            renderer: String::from(desc.renderer),
+            className: String::from(desc.className),
+            title: String::from(desc.title),
+            controlId: desc.controlId,
+            anchor: desc.anchor,
+            rect: Rect { left: posX, top: posY, right: posX + width, bottom: posY + height },
+            visible: true,
+            ..Default::default()
+        };
+        self.nodes.borrow_mut().insert(hwnd, node);
+
+        match parent {
+            Some(parent) => self.modify(parent, |p|p.children.push(hwnd)),
+            _ => self.roots.borrow_mut().push(hwnd),
+        }
+
+        hwnd
+    }
+
+    fn destroyControl(&self, hwnd: Handle) -> bool {
+        let node = match self.nodes.borrow_mut().remove(&hwnd) {
+            Some(node) => node,
+            _ => return false,
+        };
+        for child in node.children.iter() {
+            self.destroyControl(*child);
+        }
+        self.modify(node.parent, |p|p.children.retain(|c|*c != hwnd));
+        self.roots.borrow_mut().retain(|c|*c != hwnd);
+
+        true
+    }
+
+    fn getParent(&self, hwnd: Handle) -> Handle {
+        self.read(hwnd, |n|n.parent)
+    }
+
+    fn getChildren(&self, hwnd: Handle) -> Vec<Handle> {
+        self.read(hwnd, |n|n.children.clone())
+    }
+
+    fn getControlId(&self, hwnd: Handle) -> i32 {
+        self.read(hwnd, |n|n.controlId)
+    }
+
+    fn getClassName(&self, hwnd: Handle) -> Option<String> {
+        self.read(hwnd, |n|Some(n.className.clone()))
+    }
+
+    fn getTitle(&self, hwnd: Handle) -> String {
+        self.read(hwnd, |n|n.title.clone())
+    }
+
+    fn setTitle(&self, hwnd: Handle, title: &str) {
+        self.modify(hwnd, |n|n.title = String::from(title))
+    }
+
+    fn updateFont(&self, hwnd: Handle, hFont: Option<Handle>, fontFace: &str, fontSize: i32) -> Option<Handle> {
+        let isSame = self.read(hwnd, |n|n.fontFace == fontFace && n.fontSize == fontSize);
+        if isSame && hFont.is_some() {
+            return hFont;
+        }
+        self.modify(hwnd, |n|{
+            n.fontFace = String::from(fontFace);
+            n.fontSize = fontSize;
+        });
+
+        Some(self.nextHandle())
+    }
+
+    /// every char is 3/8 of the font size wide whatever the face, so layouts don't depend on the machine
+    fn measureText(&self, text: &str, _fontFace: &str, fontSize: i32) -> Size {
+        Size { cx: text.chars().count() as i32 * fontSize * 3 / 8, cy: fontSize }
+    }
+
+    fn isSelected(&self, hwnd: Handle) -> bool {
+        self.read(hwnd, |n|n.selected)
+    }
+
+    fn setSelected(&self, hwnd: Handle, selected: bool) {
+        self.modify(hwnd, |n|n.selected = selected)
+    }
+
+    fn getSelectItems(&self, hwnd: Handle) -> Vec<String> {
+        self.read(hwnd, |n|n.selectItems.clone())
+    }
+
+    fn setSelectItems(&self, hwnd: Handle, items: &[String]) {
+        self.modify(hwnd, |n|n.selectItems = items.to_vec())
+    }
+
+    fn getSelectedIndex(&self, hwnd: Handle) -> Option<usize> {
+        self.read(hwnd, |n|n.selectedIndex.filter(|_|n.renderer.starts_with("select")))
+    }
+
+    fn setSelectedIndex(&self, hwnd: Handle, index: usize) {
+        self.modify(hwnd, |n|n.selectedIndex = Some(index))
+    }
+
+    fn getSelectItemText(&self, hwnd: Handle, index: usize) -> Option<String> {
+        self.read(hwnd, |n|n.selectItems.get(index).cloned())
+    }
+
+    fn getWindowRect(&self, hwnd: Handle) -> Option<Rect> {
+        let mut rect = self.node(hwnd)?.rect;
+        let mut parent = self.getParent(hwnd);
+        while let Some(node) = self.node(parent) {
+            rect.left += node.rect.left;
+            rect.right += node.rect.left;
+            rect.top += node.rect.top;
+            rect.bottom += node.rect.top;
+            parent = node.parent;
+        }
+
+        Some(rect)
+    }
+
+    fn getClientRect(&self, hwnd: Handle) -> Option<Rect> {
+        let rect = self.node(hwnd)?.rect;
+
+        Some(Rect { left: 0, top: 0, right: rect.right - rect.left, bottom: rect.bottom - rect.top })
+    }
+
+    fn getControlRect(&self, hwnd: Handle) -> Option<Rect> {
+        Some(self.node(hwnd)?.rect)
+    }
+
+    fn adjustWindowRect(&self, _hwnd: Handle, rect: Rect) -> Rect {
+        rect
+    }
+
+    fn moveControl(&self, hwnd: Handle, rect: &Rect) -> bool {
+        let size = |r: &Rect|(r.right - r.left, r.bottom - r.top);
+        let prev = self.node(hwnd).map(|n|n.rect);
+        let res = self.modify(hwnd, |n|{
+            n.rect = *rect;
+            n.visible = true;
+            true
+        });
+        // a resized container lays its children out like it would on WM_SIZE
+        if prev.map(|r|size(&r) != size(rect)).unwrap_or(false) {
+            AnchorMap::layout(self, hwnd);
+        }
+
+        res
+    }
+
+    fn isMaximized(&self, _hwnd: Handle) -> bool {
+        false
+    }
+
+    fn isVisible(&self, hwnd: Handle) -> bool {
+        self.read(hwnd, |n|n.visible)
+    }
+
+    fn setVisible(&self, hwnd: Handle, visible: bool) {
+        self.modify(hwnd, |n|n.visible = visible)
+    }
+
+    fn sizeGripSize(&self) -> Size {
+        Size { cx: 16, cy: 16 }
+    }
+
+    fn setScrollInfo(&self, hwnd: Handle, content: Size, _viewport: Size, offset: Point) {
+        self.modify(hwnd, |n|n.scroll = (content, offset))
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        self.modifiers.get()
+    }
+
+    fn drawOutlines(&self, hwnd: Handle, rects: &[Rect]) {
+        self.modify(hwnd, |n|n.outlines = rects.to_vec())
+    }
+
+    fn postMessage(&self, msg: Message) {
+        self.queue.borrow_mut().push_back(msg);
+    }
+
+    fn peekMessage(&self) -> Option<Message> {
+        let mut queue = self.queue.borrow_mut();
+        queue.extend(self.posted.0.lock().unwrap().drain(..));
+
+        queue.pop_front()
+    }
+
+    fn waitMessage(&self, timeout: Option<Duration>) -> Option<Message> {
+        if let Some(msg) = self.peekMessage() {
+            return Some(msg);
+        }
+
+        let deadline = timeout.map(|timeout|Instant::now() + timeout);
+        let (posted, ready) = &*self.posted;
+        let mut posted = posted.lock().unwrap();
+        while posted.is_empty() {
+            posted = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return None;
+                    }
+                    ready.wait_timeout(posted, left).unwrap().0
+                },
+                _ => ready.wait(posted).unwrap(),
+            };
+        }
+
+        posted.pop_front()
+    }
+
+    fn dispatchMessage(&self, msg: &Message) {
+        self.dispatched.borrow_mut().push(*msg);
+        match msg.message {
+            WM_SIZE | WM_SIZING => {
+                AnchorMap::layout(self, msg.hwnd);
+            },
+            WM_HSCROLL | WM_VSCROLL => {
+                ScrollState::scroll(self, msg);
+            },
+            WM_LBUTTONDOWN | WM_MOUSEMOVE | WM_LBUTTONUP => {
+                SplitState::drag(self, msg);
+            },
+            WM_MOUSEWHEEL => {
+                // goes up to the first scroll view, like DefWindowProc passes it to the parent
+                let mut hwnd = msg.hwnd;
+                while hwnd != Handle(0) && ScrollState::scroll(self, &Message { hwnd, ..*msg }).is_none() {
+                    hwnd = self.getParent(hwnd);
+                }
+            },
+            WM_DPICHANGED => {
+                // the window keeps its size in logical units, like win32 suggests it
+                let dpi = HIWORD(msg.wParam) as u32;
+                let prev = AnchorMap::changeDpi(self, msg.hwnd, dpi);
+                if let Some((prev, rect)) = prev.zip(self.getControlRect(msg.hwnd)) {
+                    let size = rescaleSize(Size { cx: rect.right - rect.left, cy: rect.bottom - rect.top }, prev, dpi);
+                    self.moveControl(msg.hwnd, &Rect { right: rect.left + size.cx, bottom: rect.top + size.cy, ..rect });
+                }
+            },
+            _ => (),
+        }
+
+        if self.notifyOnDispatch.get() && !isRoutedByLoop(msg) {
+            if let Some(e) = Arc::get_mut(Notifier::shared()) {
+                e.notify(Some(*msg));
+            }
+        }
+    }
+
+    fn wakeHandle(&self) -> WakeHandle {
+        let posted = self.posted.clone();
+        WakeHandle::new(move|msg|{
+            let (posted, ready) = &*posted;
+            posted.lock().unwrap().push_back(msg);
+            ready.notify_all();
+        })
+    }
+}
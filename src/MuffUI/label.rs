@@ -1,32 +1,22 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
-use crate::Win;
 use crate::muffui::*;
 use crate::muffui::SharedProps::*;
 
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Label {
     pub children: Option<Rc<dyn Renderable>>,
     pub props: Vec<SharedProps>,
 }
 
-impl Default for Label {
-    fn default() -> Self {
-        Self {
-            children: Default::default(),
-            props: vec![],
-        }
-    }
-}
-
 impl Renderable for Label {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         Some(self.children.as_ref()?.clone())
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let context = context.render(parent, index, self, msg);
         match &self.children {
             Some(children) => children.render(context, index, &format!("{}:1", index), msg),
@@ -35,7 +25,7 @@ impl Renderable for Label {
     }
 
     fn toViewState(&self) -> Vec<SharedProps> {
-        self.props.iter().map(|item|item.clone()).collect()
+        self.props.to_vec()
     }
 }
 
@@ -58,6 +48,7 @@ impl Label {
         }
     }
 
+    #[allow(dead_code)]
     pub fn posX(self, posX: i32) -> Self {
         Self {
             props: self.props.merge(PosX(posX)),
@@ -65,6 +56,7 @@ impl Label {
         }
     }
 
+    #[allow(dead_code)]
     pub fn posY(self, posY: i32) -> Self {
         Self {
             props: self.props.merge(PosY(posY)),
@@ -72,6 +64,7 @@ impl Label {
         }
     }
 
+    #[allow(dead_code)]
     pub fn width(self, width: i32) -> Self {
         Self {
             props: self.props.merge(Width(width)),
@@ -79,6 +72,7 @@ impl Label {
         }
     }
 
+    #[allow(dead_code)]
     pub fn height(self, height: i32) -> Self {
         Self {
             props: self.props.merge(Height(height)),
@@ -0,0 +1,213 @@
+#![allow(non_snake_case)]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::muffui::*;
+
+/// most render passes kept, the oldest go first
+const KEEP_PASSES: usize = 100;
+
+/// layout of one control after a render pass
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayoutEntry {
+    pub path: String,
+    pub controlId: i32,
+    pub flags: usize,
+    /// rect the anchors of the control are solved from, None for controls a container places
+    pub initial: Option<Rect>,
+    pub current: Rect,
+    /// `delta` and `sizedBorders` of the anchor map of the parent
+    pub delta: Size,
+    pub sizedBorders: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayoutPass {
+    pub pass: usize,
+    pub entries: Vec<LayoutEntry>,
+}
+
+#[derive(Default)]
+struct LayoutDebugState {
+    enabled: bool,
+    outlines: bool,
+    count: usize,
+    passes: Vec<LayoutPass>,
+}
+
+/// records the layout of every render pass while it is enabled, to see why anchors misbehave
+pub struct LayoutDebug;
+
+impl LayoutDebug {
+    fn state() -> &'static RefCell<LayoutDebugState> {
+        thread_local! {
+            static CONF: &'static RefCell<LayoutDebugState> = Box::leak(Box::new(RefCell::new(Default::default())));
+        }
+
+        CONF.with(|conf|*conf)
+    }
+
+    /// starts recording, `outlines` also draws the rects of the controls over their parents
+    pub fn enable(outlines: bool) {
+        let mut state = Self::state().borrow_mut();
+        state.enabled = true;
+        state.outlines = outlines;
+    }
+
+    #[allow(dead_code)]
+    pub fn disable() {
+        *Self::state().borrow_mut() = Default::default();
+    }
+
+    pub fn isEnabled() -> bool {
+        Self::state().borrow().enabled
+    }
+
+    #[allow(dead_code)]
+    pub fn passes() -> Vec<LayoutPass> {
+        Self::state().borrow().passes.clone()
+    }
+
+    /// takes the layout of a rendered context when recording
+    pub fn record(context: &UIContext) {
+        if !Self::isEnabled() {
+            return;
+        }
+
+        let backend = &*context.backend;
+        let mut paths = context.items.keys().collect::<Vec<_>>();
+        paths.sort_by(|a, b|comparePaths(a, b));
+        let entries = paths.into_iter().map(|path|{
+            let ci = &context.items[path];
+            let map = AnchorMap::find(backend.getParent(ci.hwnd));
+            let map = map.as_ref().map(|map|map.borrow());
+            let entry = map.as_ref().and_then(|map|map.findWindow(ci.hwnd));
+            LayoutEntry {
+                path: path.clone(),
+                controlId: backend.getControlId(ci.hwnd),
+                flags: entry.map(|e|e.flags()).unwrap_or(ci.anchor),
+                initial: entry.map(|e|e.rect()),
+                current: backend.getControlRect(ci.hwnd).unwrap_or_default(),
+                delta: map.as_ref().map(|map|map.delta()).unwrap_or_default(),
+                sizedBorders: map.as_ref().map(|map|map.sizedBorders()).unwrap_or(ANF_NONE),
+            }
+        }).collect::<Vec<_>>();
+
+        let outlines = {
+            let mut state = Self::state().borrow_mut();
+            state.count += 1;
+            let pass = LayoutPass { pass: state.count, entries };
+            if state.passes.len() == KEEP_PASSES {
+                state.passes.remove(0);
+            }
+            state.passes.push(pass);
+            state.outlines
+        };
+        if outlines {
+            let mut byParent: HashMap<Handle, Vec<Rect>> = HashMap::new();
+            for ci in context.items.values() {
+                let parent = backend.getParent(ci.hwnd);
+                if parent != Handle(0) {
+                    byParent.entry(parent).or_default().push(backend.getControlRect(ci.hwnd).unwrap_or_default());
+                }
+            }
+            for (parent, rects) in byParent {
+                backend.drawOutlines(parent, &rects);
+            }
+        }
+    }
+
+    /// recorded passes as a JSON array, flags are lists of ANF_* names and rects [left, top, width, height]
+    pub fn toJson() -> String {
+        let state = Self::state().borrow();
+        let flags = |flags: usize| match flags {
+            ANF_NONE => String::from("[]"),
+            _ => format!("[{}]", anchorToString(flags).split('|').map(jsonString).collect::<Vec<_>>().join(", ")),
+        };
+        let rect = |r: &Rect| format!("[{}, {}, {}, {}]", r.left, r.top, r.right - r.left, r.bottom - r.top);
+
+        let mut res = String::from("[");
+        for (idx, pass) in state.passes.iter().enumerate() {
+            let _ = write!(res, "{}\n  {{\"pass\": {}, \"controls\": [", if idx > 0 { "," } else { "" }, pass.pass);
+            for (idx, e) in pass.entries.iter().enumerate() {
+                let _ = write!(res, "{}\n    {{\"path\": {}, \"controlId\": {}, \"flags\": {}, \"initial\": {}, \"current\": {}, \"delta\": [{}, {}], \"sizedBorders\": {}}}",
+                    if idx > 0 { "," } else { "" },
+                    jsonString(&e.path), e.controlId, flags(e.flags),
+                    e.initial.as_ref().map(rect).unwrap_or_else(||String::from("null")), rect(&e.current),
+                    e.delta.cx, e.delta.cy, flags(e.sizedBorders),
+                );
+            }
+            res.push_str("\n  ]}");
+        }
+        res.push_str("\n]\n");
+
+        res
+    }
+
+    pub fn save(path: &str) -> std::io::Result<()> {
+        std::fs::write(path, Self::toJson())
+    }
+}
+
+/// `value` quoted and escaped as a JSON string
+pub fn jsonString(value: &str) -> String {
+    let mut res = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c if (c as u32) < 0x20 => { let _ = write!(res, "\\u{:04x}", c as u32); },
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn it_records_layout_of_render_passes() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = Window::new([SP::Width(300), SP::Height(200)]).content(||(
+            Button::new([SP::ControlId(1), SP::Anchor(ANF_TOP | ANF_RIGHT), SP::PosX(200), SP::PosY(10), SP::Width(60), SP::Height(20)]),
+            VStack::new([SP::ControlId(2), SP::Anchor(ANF_DOCK_BOTTOM), SP::Height(40)]).content(||
+                Label::new([SP::Title("a \"quoted\" title"), SP::Height(20)])
+            ),
+        ));
+        LayoutDebug::enable(true);
+
+        // the anchors are taken on the second pass
+        let context = UIContext::create(backend.clone()).update(&view).update(&view);
+        let window = context.items["0"].hwnd;
+        backend.resize(window, 400, 250);
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+        let _ = context.update(&view);
+
+        let passes = LayoutDebug::passes();
+        assert_eq!(passes.iter().map(|p|p.pass).collect::<Vec<_>>(), vec![1, 2, 3]);
+        let button = passes[2].entries.iter().find(|e|e.path == "0:1_1").unwrap();
+        assert_eq!(button, &LayoutEntry {
+            path: String::from("0:1_1"), controlId: 1, flags: ANF_TOP | ANF_RIGHT,
+            initial: Some(Rect { left: 200, top: 10, right: 260, bottom: 30 }),
+            current: Rect { left: 300, top: 10, right: 360, bottom: 30 },
+            delta: Size { cx: 100, cy: 50 }, sizedBorders: ANF_RIGHT | ANF_BOTTOM,
+        });
+        // the stack places its label itself
+        let label = passes[2].entries.iter().find(|e|e.path == "0:1_2:1_1").unwrap();
+        assert_eq!((label.initial, label.delta), (None, Size::default()));
+        assert!(!backend.node(window).unwrap().outlines.is_empty());
+
+        let json = LayoutDebug::toJson();
+        assert!(json.contains("{\"path\": \"0:1_1\", \"controlId\": 1, \"flags\": [\"ANF_TOP\", \"ANF_RIGHT\"], \"initial\": [200, 10, 60, 20], \"current\": [300, 10, 60, 20], \"delta\": [100, 50], \"sizedBorders\": [\"ANF_BOTTOM\", \"ANF_RIGHT\"]}"), "{}", json);
+        assert_eq!(jsonString("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\n\"");
+        LayoutDebug::disable();
+        assert_eq!(LayoutDebug::toJson(), "[\n]\n");
+    }
+}
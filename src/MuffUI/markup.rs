@@ -0,0 +1,920 @@
+#![allow(non_snake_case)]
+
+use std::fmt;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::muffui::*;
+
+/// parse or build error, `line` and `column` count from 1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkupError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// error of loading a file, it can't be read or its text doesn't parse
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    Io { path: String, message: String },
+    Markup { path: String, error: MarkupError },
+}
+
+impl LoadError {
+    pub fn io<P: AsRef<Path>>(path: P, error: std::io::Error) -> Self {
+        LoadError::Io { path: path.as_ref().display().to_string(), message: error.to_string() }
+    }
+
+    pub fn markup<P: AsRef<Path>>(path: P, error: MarkupError) -> Self {
+        LoadError::Markup { path: path.as_ref().display().to_string(), error }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, message } => write!(f, "{}: {}", path, message),
+            LoadError::Markup { path, error } => write!(f, "{}:{}", path, error),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MarkupAttribute {
+    pub name: String,
+    pub value: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// element of a layout file before it is turned into widgets
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MarkupNode {
+    pub name: String,
+    pub attributes: Vec<MarkupAttribute>,
+    pub children: Vec<MarkupNode>,
+    pub line: usize,
+    pub column: usize,
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Reader {
+    fn new(text: &str) -> Self {
+        Self { chars: text.chars().collect(), pos: 0, line: 1, column: 1 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn startsWith(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)|self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn skip(&mut self, count: usize) {
+        for _ in 0..count {
+            self.next();
+        }
+    }
+
+    fn skipWhitespace(&mut self) {
+        while self.peek().map(|c|c.is_whitespace()).unwrap_or(false) {
+            self.next();
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, MarkupError> {
+        Err(MarkupError { line: self.line, column: self.column, message: message.into() })
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), MarkupError> {
+        if !self.startsWith(s) {
+            return match self.peek() {
+                Some(c) => self.error(format!("expected {:?}, found {:?}", s, c)),
+                _ => self.error(format!("expected {:?}, found end of file", s)),
+            };
+        }
+        self.skip(s.chars().count());
+
+        Ok(())
+    }
+
+    /// skips everything up to and including `end`
+    fn skipPast(&mut self, end: &str) -> Result<(), MarkupError> {
+        while !self.startsWith(end) {
+            if self.next().is_none() {
+                return self.error(format!("expected {:?}, found end of file", end));
+            }
+        }
+
+        self.expect(end)
+    }
+}
+
+fn isNameChar(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == ':' || c == '.'
+}
+
+fn readXmlName(reader: &mut Reader) -> Result<String, MarkupError> {
+    let mut name = String::new();
+    while let Some(c) = reader.peek().filter(|c|isNameChar(*c)) {
+        name.push(c);
+        reader.next();
+    }
+    if name.is_empty() {
+        return match reader.peek() {
+            Some(c) => reader.error(format!("expected a name, found {:?}", c)),
+            _ => reader.error("expected a name, found end of file"),
+        };
+    }
+
+    Ok(name)
+}
+
+/// skips whitespace, comments, processing instructions and doctype
+fn skipXmlMisc(reader: &mut Reader) -> Result<(), MarkupError> {
+    loop {
+        reader.skipWhitespace();
+        if reader.startsWith("<!--") {
+            reader.skipPast("-->")?;
+        } else if reader.startsWith("<?") {
+            reader.skipPast("?>")?;
+        } else if reader.startsWith("<!") {
+            reader.skipPast(">")?;
+        } else {
+            return Ok(());
+        }
+    }
+}
+
+fn readXmlValue(reader: &mut Reader) -> Result<String, MarkupError> {
+    let quote = match reader.peek() {
+        Some(c) if c == '"' || c == '\'' => c,
+        _ => return reader.error("expected a quoted attribute value"),
+    };
+    reader.next();
+
+    let mut value = String::new();
+    loop {
+        match reader.peek() {
+            Some(c) if c == quote => {
+                reader.next();
+                return Ok(value);
+            },
+            Some('&') => {
+                let (line, column) = (reader.line, reader.column);
+                let mut entity = String::new();
+                reader.next();
+                while let Some(c) = reader.peek().filter(|c|*c != ';' && *c != quote) {
+                    entity.push(c);
+                    reader.next();
+                }
+                reader.expect(";")?;
+                let c = match entity.as_str() {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+                    _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+                    _ => None,
+                };
+                match c {
+                    Some(c) => value.push(c),
+                    _ => return Err(MarkupError { line, column, message: format!("unknown entity &{};", entity) }),
+                }
+            },
+            Some('<') => return reader.error("'<' is not allowed in attribute values"),
+            Some(c) => {
+                value.push(c);
+                reader.next();
+            },
+            _ => return reader.error("unterminated attribute value"),
+        }
+    }
+}
+
+fn readXmlElement(reader: &mut Reader) -> Result<MarkupNode, MarkupError> {
+    let (line, column) = (reader.line, reader.column);
+    reader.expect("<")?;
+    let name = readXmlName(reader)?;
+    let mut node = MarkupNode { name, line, column, ..Default::default() };
+
+    loop {
+        reader.skipWhitespace();
+        if reader.startsWith("/>") {
+            reader.skip(2);
+            return Ok(node);
+        }
+        if reader.startsWith(">") {
+            reader.skip(1);
+            break;
+        }
+        let (line, column) = (reader.line, reader.column);
+        let name = readXmlName(reader)?;
+        reader.skipWhitespace();
+        reader.expect("=")?;
+        reader.skipWhitespace();
+        let value = readXmlValue(reader)?;
+        if node.attributes.iter().any(|a|a.name == name) {
+            return Err(MarkupError { line, column, message: format!("duplicate attribute {:?}", name) });
+        }
+        node.attributes.push(MarkupAttribute { name, value, line, column });
+    }
+
+    loop {
+        skipXmlMisc(reader)?;
+        if reader.startsWith("</") {
+            reader.skip(2);
+            let (line, column) = (reader.line, reader.column);
+            let name = readXmlName(reader)?;
+            if name != node.name {
+                return Err(MarkupError { line, column, message: format!("expected </{}>, found </{}>", node.name, name) });
+            }
+            reader.skipWhitespace();
+            reader.expect(">")?;
+            return Ok(node);
+        }
+        match reader.peek() {
+            Some('<') => node.children.push(readXmlElement(reader)?),
+            Some(_) => return reader.error("text content is not supported, use the Title attribute"),
+            _ => return reader.error(format!("expected </{}>, found end of file", node.name)),
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn parseXml(text: &str) -> Result<MarkupNode, MarkupError> {
+    let mut reader = Reader::new(text);
+    skipXmlMisc(&mut reader)?;
+    let root = readXmlElement(&mut reader)?;
+    skipXmlMisc(&mut reader)?;
+    if reader.peek().is_some() {
+        return reader.error("unexpected content after the root element");
+    }
+
+    Ok(root)
+}
+
+/// parsed JSON, objects and arrays keep the line and column of their items for error messages
+pub enum JsonValue {
+    Object(Vec<(String, usize, usize, JsonValue)>),
+    Array(Vec<(usize, usize, JsonValue)>),
+    Scalar(String),
+    Null,
+}
+
+fn readJsonString(reader: &mut Reader) -> Result<String, MarkupError> {
+    reader.expect("\"")?;
+    let mut value = String::new();
+    loop {
+        match reader.next() {
+            Some('"') => return Ok(value),
+            Some('\\') => {
+                let c = match reader.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('u') => {
+                        let code = (0..4).filter_map(|_|reader.next()).collect::<String>();
+                        match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                            Some(c) => c,
+                            _ => return reader.error(format!("invalid escape \\u{}", code)),
+                        }
+                    },
+                    Some(c @ ('"' | '\\' | '/')) => c,
+                    Some(c) => return reader.error(format!("invalid escape \\{}", c)),
+                    _ => return reader.error("unterminated string"),
+                };
+                value.push(c);
+            },
+            Some(c) => value.push(c),
+            _ => return reader.error("unterminated string"),
+        }
+    }
+}
+
+fn readJsonValue(reader: &mut Reader) -> Result<JsonValue, MarkupError> {
+    reader.skipWhitespace();
+    match reader.peek() {
+        Some('{') => {
+            reader.next();
+            let mut fields = vec![];
+            reader.skipWhitespace();
+            if reader.startsWith("}") {
+                reader.next();
+                return Ok(JsonValue::Object(fields));
+            }
+            loop {
+                reader.skipWhitespace();
+                let (line, column) = (reader.line, reader.column);
+                let key = readJsonString(reader)?;
+                reader.skipWhitespace();
+                reader.expect(":")?;
+                let value = readJsonValue(reader)?;
+                fields.push((key, line, column, value));
+                reader.skipWhitespace();
+                match reader.next() {
+                    Some(',') => continue,
+                    Some('}') => return Ok(JsonValue::Object(fields)),
+                    _ => return reader.error("expected ',' or '}'"),
+                }
+            }
+        },
+        Some('[') => {
+            reader.next();
+            let mut items = vec![];
+            reader.skipWhitespace();
+            if reader.startsWith("]") {
+                reader.next();
+                return Ok(JsonValue::Array(items));
+            }
+            loop {
+                reader.skipWhitespace();
+                let (line, column) = (reader.line, reader.column);
+                items.push((line, column, readJsonValue(reader)?));
+                reader.skipWhitespace();
+                match reader.next() {
+                    Some(',') => continue,
+                    Some(']') => return Ok(JsonValue::Array(items)),
+                    _ => return reader.error("expected ',' or ']'"),
+                }
+            }
+        },
+        Some('"') => Ok(JsonValue::Scalar(readJsonString(reader)?)),
+        Some(c) if c == '-' || c.is_ascii_digit() => {
+            let mut value = String::new();
+            while let Some(c) = reader.peek().filter(|c|c.is_ascii_digit() || "+-.eE".contains(*c)) {
+                value.push(c);
+                reader.next();
+            }
+            Ok(JsonValue::Scalar(value))
+        },
+        _ if reader.startsWith("true") => {
+            reader.skip(4);
+            Ok(JsonValue::Scalar(String::from("true")))
+        },
+        _ if reader.startsWith("false") => {
+            reader.skip(5);
+            Ok(JsonValue::Scalar(String::from("false")))
+        },
+        _ if reader.startsWith("null") => {
+            reader.skip(4);
+            Ok(JsonValue::Null)
+        },
+        Some(c) => reader.error(format!("unexpected {:?}", c)),
+        _ => reader.error("unexpected end of file"),
+    }
+}
+
+/// element is an object with the element name in "type", its children in "children" and attributes in other fields
+fn jsonToNode(value: JsonValue, line: usize, column: usize) -> Result<MarkupNode, MarkupError> {
+    let fields = match value {
+        JsonValue::Object(fields) => fields,
+        _ => return Err(MarkupError { line, column, message: String::from("expected an element object") }),
+    };
+
+    let mut node = MarkupNode { line, column, ..Default::default() };
+    for (key, line, column, value) in fields {
+        match (key.as_str(), value) {
+            ("type", JsonValue::Scalar(name)) => node.name = name,
+            ("children", JsonValue::Array(items)) => {
+                for (line, column, item) in items {
+                    node.children.push(jsonToNode(item, line, column)?);
+                }
+            },
+            ("type", _) | ("children", _) => {
+                return Err(MarkupError { line, column, message: format!("unexpected value of {:?}", key) });
+            },
+            (_, JsonValue::Scalar(value)) => node.attributes.push(MarkupAttribute { name: key, value, line, column }),
+            (_, JsonValue::Null) => (),
+            _ => return Err(MarkupError { line, column, message: format!("attribute {:?} must be a string, number or boolean", key) }),
+        }
+    }
+    if node.name.is_empty() {
+        return Err(MarkupError { line, column, message: String::from("element has no \"type\"") });
+    }
+
+    Ok(node)
+}
+
+/// a single JSON value, numbers and booleans come as scalars of their text
+pub fn parseJsonValue(text: &str) -> Result<JsonValue, MarkupError> {
+    let mut reader = Reader::new(text);
+    let value = readJsonValue(&mut reader)?;
+    reader.skipWhitespace();
+    if reader.peek().is_some() {
+        return reader.error("unexpected content after the root element");
+    }
+
+    Ok(value)
+}
+
+#[allow(dead_code)]
+pub fn parseJson(text: &str) -> Result<MarkupNode, MarkupError> {
+    let mut reader = Reader::new(text);
+    reader.skipWhitespace();
+    let (line, column) = (reader.line, reader.column);
+
+    jsonToNode(parseJsonValue(text)?, line, column)
+}
+
+type Handler = Rc<RefCell<dyn FnMut(Vec<SharedProps>)>>;
+
+/// named event handlers the layout binds with DidClick="name" and friends
+#[derive(Clone, Default)]
+pub struct Handlers {
+    handlers: HashMap<String, Handler>,
+}
+
+impl Handlers {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self { ..Default::default() }
+    }
+
+    #[allow(dead_code)]
+    pub fn handler(self, name: &str, handler: impl FnMut(Vec<SharedProps>) + 'static) -> Self {
+        let mut handlers = self.handlers;
+        handlers.insert(String::from(name), Rc::new(RefCell::new(handler)));
+
+        Self { handlers }
+    }
+
+    fn command(&self, name: &str) -> Option<Command<Vec<SharedProps>>> {
+        let handler = self.handlers.get(name)?.clone();
+
+        Some(Command::new(move|args|(handler.borrow_mut())(args)))
+    }
+}
+
+fn toProp(element: &str, attr: &MarkupAttribute, handlers: &Handlers) -> Result<SharedProps, MarkupError> {
+    let error = |message: String| MarkupError { line: attr.line, column: attr.column, message };
+    let invalid = || error(format!("invalid value of {}: {:?}", attr.name, attr.value));
+    let number = || attr.value.trim().parse::<i32>().map_err(|_|invalid());
+    let flag = || attr.value.trim().parse::<bool>().map_err(|_|invalid());
+    let index = || attr.value.trim().parse::<usize>().map_err(|_|invalid());
+    let ratio = || attr.value.trim().parse::<f32>().map_err(|_|invalid());
+    let align = || match attr.value.trim() {
+        "start" => Ok(Align::Start),
+        "center" => Ok(Align::Center),
+        "end" => Ok(Align::End),
+        "stretch" => Ok(Align::Stretch),
+        _ => Err(invalid()),
+    };
+    // "auto, 120, *, 2*"
+    let lengths = || attr.value.split(',').map(|l|match l.trim() {
+        "auto" => Ok(GridLength::Auto),
+        "*" => Ok(GridLength::Star(1)),
+        l if l.ends_with('*') => l[..l.len() - 1].parse().map(GridLength::Star).map_err(|_|invalid()),
+        l => l.parse().map(GridLength::Fixed).map_err(|_|invalid()),
+    }).collect::<Result<Vec<_>, _>>();
+    let handler = || handlers.command(&attr.value)
+        .ok_or_else(||error(format!("unknown handler {:?}", attr.value)));
+    let value = attr.value.as_str();
+
+    Ok(match attr.name.as_str() {
+        "ClassName" => SP::ClassName(value),
+        "Title" => SP::Title(value),
+        "FontFace" => SP::FontFace(value),
+        "FontSize" => SP::FontSize(number()?),
+        "ControlId" => SP::ControlId(number()?),
+        "Width" => SP::Width(number()?),
+        "Height" => SP::Height(number()?),
+        "PosX" => SP::PosX(number()?),
+        "PosY" => SP::PosY(number()?),
+        "MinWidth" => SP::MinWidth(number()?),
+        "MaxWidth" => SP::MaxWidth(number()?),
+        "MinHeight" => SP::MinHeight(number()?),
+        "MaxHeight" => SP::MaxHeight(number()?),
+        "Anchor" => SP::Anchor(anchorFromString(value).ok_or_else(invalid)?),
+        "Selected" => SP::Selected(flag()?),
+        "SelectItems" => SP::SelectItems(value.split(',').map(|i|i.trim()).filter(|i|!i.is_empty())),
+        "SelectedIndex" => SP::SelectedIndex(value.trim().parse().map_err(|_|invalid())?),
+        "Spacing" => SP::Spacing(number()?),
+        "Padding" => SP::Padding(number()?),
+        "AlignItems" => SP::AlignItems(align()?),
+        "JustifyContent" => SP::JustifyContent(match value.trim() {
+            "start" => Justify::Start,
+            "center" => Justify::Center,
+            "end" => Justify::End,
+            "space-between" => Justify::SpaceBetween,
+            _ => return Err(invalid()),
+        }),
+        "Wrap" => SP::Wrap(flag()?),
+        "Grow" => SP::Grow(number()?),
+        "Shrink" => SP::Shrink(number()?),
+        "Rows" => SP::Rows(lengths()?),
+        "Columns" => SP::Columns(lengths()?),
+        "Row" => SP::Row(index()?),
+        "Column" => SP::Column(index()?),
+        "RowSpan" => SP::RowSpan(index()?),
+        "ColumnSpan" => SP::ColumnSpan(index()?),
+        "HorizontalAlign" => SP::HorizontalAlign(align()?),
+        "VerticalAlign" => SP::VerticalAlign(align()?),
+        "ScrollX" => SP::ScrollX(number()?),
+        "ScrollY" => SP::ScrollY(number()?),
+        "RowHeight" => SP::RowHeight(number()?),
+        "SplitRatio" => SP::SplitRatio(ratio()?),
+        "SplitPosition" => SP::SplitPosition(number()?),
+        "Dock" => SP::Dock(match value.trim() {
+            "left" => Dock::Left,
+            "top" => Dock::Top,
+            "right" => Dock::Right,
+            "bottom" => Dock::Bottom,
+            _ => return Err(invalid()),
+        }),
+        "DidCreate" => SP::DidCreate(handler()?),
+        "DidClick" => SP::DidClick(handler()?),
+        "DidChange" => SP::DidChange(handler()?),
+        "DidResize" => SP::DidResize(handler()?),
+        "DidDestroy" => SP::DidDestroy(handler()?),
+        "DidScroll" => {
+            // the handlers of a layout take props, the offset comes as ScrollX and ScrollY
+            let mut handler = handler()?;
+            SP::DidScroll(move|event: ScrollEvent|handler.exec(event.into()))
+        },
+        "DidSplit" => {
+            // the ratio and position come as SplitRatio and SplitPosition
+            let mut handler = handler()?;
+            SP::DidSplit(move|event: SplitEvent|handler.exec(event.into()))
+        },
+        name => return Err(error(format!("unknown attribute {:?} of <{}>", name, element))),
+    })
+}
+
+fn build(node: &MarkupNode, handlers: &Handlers) -> Result<Rc<dyn Renderable>, MarkupError> {
+    let props = node.attributes.iter()
+        .map(|attr|toProp(&node.name, attr, handlers))
+        .collect::<Result<Vec<_>, _>>()?;
+    let children = node.children.iter()
+        .map(|child|build(child, handlers))
+        .collect::<Result<Vec<_>, _>>()?;
+    let noChildren = || match node.children.first() {
+        Some(child) => Err(MarkupError {
+            line: child.line, column: child.column,
+            message: format!("<{}> can't have children", node.name),
+        }),
+        _ => Ok(()),
+    };
+
+    Ok(match node.name.as_str() {
+        "window" => Rc::new(Window::new(props).content(move||children.clone())),
+        "panel" => Rc::new(Panel::new(props).content(move||children.clone())),
+        "group-box" => Rc::new(GroupBox::new(props).content(move||children.clone())),
+        "select" => Rc::new(Select::new(props).content(move||children.clone())),
+        "h-stack" => Rc::new(HStack::new(props).content(move||children.clone())),
+        "v-stack" => Rc::new(VStack::new(props).content(move||children.clone())),
+        "grid" => Rc::new(Grid::new(props).content(move||children.clone())),
+        "scroll-view" => Rc::new(ScrollView::new(props).content(move||children.clone())),
+        "dock-panel" => Rc::new(DockPanel::new(props).content(move||children.clone())),
+        "split" => Rc::new(Split::new(props).content(move||children.clone())),
+        "label" => noChildren().map(|_|Rc::new(Label::new(props)))?,
+        "text-box" => noChildren().map(|_|Rc::new(TextBox::new(props)))?,
+        "check-box" => noChildren().map(|_|Rc::new(CheckBox::new(props)))?,
+        "radio-box" => noChildren().map(|_|Rc::new(RadioBox::new(props)))?,
+        "button" => noChildren().map(|_|Rc::new(Button::new(props)))?,
+        name => return Err(MarkupError { line: node.line, column: node.column, message: format!("unknown element <{}>", name) }),
+    })
+}
+
+/// view described by an XML or JSON layout
+#[derive(Clone)]
+pub struct Layout {
+    pub root: Rc<dyn Renderable>,
+}
+
+impl Renderable for Layout {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        Some(self.root.clone())
+    }
+}
+
+impl Layout {
+    /// XML when the text starts with '<', JSON otherwise
+    #[allow(dead_code)]
+    pub fn parse(text: &str, handlers: &Handlers) -> Result<Self, MarkupError> {
+        let node = match text.trim_start().starts_with('<') {
+            true => parseXml(text)?,
+            _ => parseJson(text)?,
+        };
+
+        Ok(Self { root: build(&node, handlers)? })
+    }
+
+    #[allow(dead_code)]
+    pub fn load<P: AsRef<Path>>(path: P, handlers: &Handlers) -> Result<Self, LoadError> {
+        let text = std::fs::read_to_string(path.as_ref()).map_err(|e|LoadError::io(&path, e))?;
+
+        Self::parse(&text, handlers).map_err(|e|LoadError::markup(&path, e))
+    }
+}
+
+/// layout loaded from a file, parsed again when the file changes on disk.
+/// a file that fails to load is reported once by `reload` and the last good layout stays on screen
+pub struct LayoutFile {
+    path: PathBuf,
+    handlers: Handlers,
+    layout: RefCell<Option<Layout>>,
+    /// modification time and length of the file when it was last looked at, None while it can't be read
+    stamp: Cell<Option<(SystemTime, u64)>>,
+    /// the first load failed and `reload` hasn't reported it yet
+    error: RefCell<Option<String>>,
+    /// how often the app looks at the file
+    interval: Duration,
+}
+
+impl LayoutFile {
+    #[allow(dead_code)]
+    pub fn new<P: AsRef<Path>>(path: P, handlers: Handlers) -> Self {
+        let res = Self {
+            path: path.as_ref().to_path_buf(),
+            handlers,
+            layout: RefCell::new(None),
+            stamp: Cell::new(None),
+            error: RefCell::new(None),
+            interval: Duration::from_millis(500),
+        };
+        res.stamp.set(res.stampOnDisk());
+        *res.error.borrow_mut() = res.load().err();
+
+        res
+    }
+
+    #[allow(dead_code)]
+    pub fn interval(self, interval: Duration) -> Self {
+        Self {
+            interval,
+            ..self
+        }
+    }
+
+    /// the modification time alone misses saves on file systems with coarse timestamps
+    fn stampOnDisk(&self) -> Option<(SystemTime, u64)> {
+        std::fs::metadata(&self.path).and_then(|m|Ok((m.modified()?, m.len()))).ok()
+    }
+
+    fn load(&self) -> Result<(), String> {
+        let layout = Layout::load(&self.path, &self.handlers).map_err(|e|e.to_string())?;
+        *self.layout.borrow_mut() = Some(layout);
+
+        Ok(())
+    }
+}
+
+impl Renderable for LayoutFile {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        self.layout.borrow().as_ref().map(|layout|layout.root.clone())
+    }
+
+    fn reload(&self) -> Result<bool, String> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let stamp = self.stampOnDisk();
+        if stamp == self.stamp.get() {
+            return Ok(false);
+        }
+        self.stamp.set(stamp);
+
+        self.load().map(|_|true)
+    }
+
+    fn reloadInterval(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(layout: &Layout) -> Box<UIContext> {
+        layout.render(UIContext::create(Rc::new(HeadlessBackend::new())), "/", "0", None)
+    }
+
+    fn handlers() -> Handlers {
+        ["quit", "completeAll", "setNewTitle", "save"].iter()
+            .fold(Handlers::new(), |h, name|h.handler(name, |_|{}))
+    }
+
+    fn error(text: &str) -> String {
+        Layout::parse(text, &handlers()).err().map(|e|e.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn it_renders_xml_layout() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("layouts/todo.xml");
+        let layout = Layout::load(path, &handlers()).unwrap();
+
+        let snapshot = render(&layout).snapshot();
+        assert!(snapshot.starts_with("0 window class=\"window#1\" title=\"TODO: Example\" rect=(0, 0, 500, 310)"));
+        assert!(snapshot.contains("  0:1_1 panel class=\"panel#0x8002\" title=\"create new todo\""));
+        assert!(snapshot.contains("    0:1_1:1_3 text-box class=\"Edit\" title=\"\" rect=(170, 6, 260, 21) anchor=ANF_TOP|ANF_LEFT|ANF_RIGHT"));
+        assert!(snapshot.contains("    0:1_3:1_2 radio-box class=\"Button\" title=\"All\" rect=(130, 2, 45, 25) anchor=ANF_TOP|ANF_LEFT selected"));
+    }
+
+    #[test]
+    fn it_renders_json_layout_like_xml() {
+        let xml = r#"<panel Title="a &amp; b" ControlId="1"><button Title='Go' DidClick="save"/><label/></panel>"#;
+        let json = r#"{"type": "panel", "Title": "a & b", "ControlId": 1, "children": [
+            {"type": "button", "Title": "Go", "DidClick": "save"},
+            {"type": "label"}
+        ]}"#;
+
+        let xml = render(&Layout::parse(xml, &handlers()).unwrap()).snapshot();
+        let json = render(&Layout::parse(json, &handlers()).unwrap()).snapshot();
+        assert_eq!(xml, json);
+        assert!(xml.contains("title=\"a & b\""));
+    }
+
+    #[test]
+    fn it_binds_named_handlers() {
+        let clicks = Rc::new(RefCell::new(vec![]));
+        let handlers = Handlers::new().handler("save", {
+            let clicks = clicks.clone();
+            move|_|clicks.borrow_mut().push("save")
+        });
+        let layout = Layout::parse(r#"<panel><button DidClick="save"/></panel>"#, &handlers).unwrap();
+
+        let context = render(&layout);
+        let context = layout.render(context, "/", "0", Some(Message { ..Default::default() }));
+        for listener in context.items["0:1_1"].listeners.iter() {
+            if let SharedProps::DidClick(h) = listener {
+                h.lock().unwrap().exec(ClickEvent::default());
+            }
+        }
+
+        assert_eq!(*clicks.borrow(), vec!["save"]);
+    }
+
+    #[test]
+    fn it_reports_position_of_errors() {
+        assert_eq!(error("<panel>\n  <image/>\n</panel>"), "2:3: unknown element <image>");
+        assert_eq!(error("<panel>\n  <label Colour=\"red\"/>\n</panel>"), "2:10: unknown attribute \"Colour\" of <label>");
+        assert_eq!(error("<panel PosX=\"left\"/>"), "1:8: invalid value of PosX: \"left\"");
+        assert_eq!(error("<v-stack AlignItems=\"middle\"/>"), "1:10: invalid value of AlignItems: \"middle\"");
+        assert_eq!(error("<grid Columns=\"auto, x*\"/>"), "1:7: invalid value of Columns: \"auto, x*\"");
+        assert_eq!(error("<button DidClick=\"launch\"/>"), "1:9: unknown handler \"launch\"");
+        assert_eq!(error("<label>\n<label/></label>"), "2:1: <label> can't have children");
+        assert_eq!(error("<panel>\n</window>"), "2:3: expected </panel>, found </window>");
+        assert_eq!(error("{\"type\": \"panel\",\n \"Anchor\": \"ANF_MIDDLE\"}"), "2:2: invalid value of Anchor: \"ANF_MIDDLE\"");
+        assert_eq!(error("{\"type\": \"panel\", \"children\": [\n  {\"type\": \"image\"}]}"), "2:3: unknown element <image>");
+    }
+
+    #[test]
+    fn it_reports_load_errors_without_position_when_file_is_unreadable() {
+        let path = std::env::temp_dir().join(format!("muffui-load-error-{}.xml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let error = Layout::load(&path, &handlers()).err().unwrap();
+        assert!(matches!(error, LoadError::Io { .. }));
+        assert!(error.to_string().starts_with(&format!("{}: ", path.display())));
+
+        std::fs::write(&path, "<panel PosX=\"left\"/>").unwrap();
+        let error = Layout::load(&path, &handlers()).err().unwrap();
+        assert_eq!(error.to_string(), format!("{}:1:8: invalid value of PosX: \"left\"", path.display()));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn it_reloads_changed_layout_file() {
+        let path = std::env::temp_dir().join(format!("muffui-reload-{}.xml", std::process::id()));
+        let write = |text: &str, secs: u64| {
+            std::fs::write(&path, text).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs)).unwrap();
+        };
+        write(r#"<panel><label Title="a"/><button Title="b"/></panel>"#, 1000);
+
+        let layout = LayoutFile::new(&path, handlers());
+        let context = layout.render(UIContext::create(Rc::new(HeadlessBackend::new())), "/", "0", None);
+        let label = context.items["0:1_1"].hwnd;
+        assert_eq!(layout.reload(), Ok(false));
+
+        write(r#"<panel><label Title="a"/><label Title="c"/></panel>"#, 2000);
+        assert_eq!(layout.reload(), Ok(true));
+        let context = context.update(&layout);
+        let titles = context.items.iter()
+            .map(|(p, ci)|(p.as_str(), ci.renderer.as_str(), context.backend.getTitle(ci.hwnd)))
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(context.items["0:1_1"].hwnd, label);
+        assert_eq!(titles.into_iter().collect::<Vec<_>>(), vec![
+            ("0", "panel", String::new()), ("0:1_1", "label", String::from("a")), ("0:1_2", "label", String::from("c")),
+        ]);
+
+        write(r#"<panel><label Title="a"/>"#, 3000);
+        assert!(layout.reload().is_err());
+        let context = context.update(&layout);
+        assert_eq!(context.backend.getTitle(context.items["0:1_2"].hwnd), "c");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn it_reports_layout_file_error_once_per_change() {
+        let path = std::env::temp_dir().join(format!("muffui-reload-error-{}.xml", std::process::id()));
+        let write = |text: &str| {
+            std::fs::write(&path, text).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000)).unwrap();
+        };
+        let _ = std::fs::remove_file(&path);
+
+        let layout = LayoutFile::new(&path, handlers());
+        assert!(layout.reload().is_err());
+        assert_eq!(layout.reload(), Ok(false));
+
+        // same modification time, the length tells the saves apart
+        write(r#"<panel><label Title="a"/>"#);
+        assert!(layout.reload().is_err());
+        assert_eq!(layout.reload(), Ok(false));
+        write(r#"<panel><label Title="a"/></panel>"#);
+        assert_eq!(layout.reload(), Ok(true));
+
+        let _ = std::fs::remove_file(&path);
+        assert!(layout.reload().is_err());
+        assert_eq!(layout.reload(), Ok(false));
+        assert!(layout.childs().is_some());
+    }
+
+    #[test]
+    fn it_reloads_layout_file_in_app_and_keeps_model() {
+        let path = std::env::temp_dir().join(format!("muffui-app-reload-{}.xml", std::process::id()));
+        let write = |text: &str, secs: u64| {
+            std::fs::write(&path, text).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+        };
+        write(r#"<window Width="300" Height="200"><text-box ControlId="1" DidChange="setTitle"/><button Title="Save" DidClick="save"/></window>"#, 1000);
+        let (title, saved) = (Rc::new(RefCell::new(String::new())), Rc::new(RefCell::new(vec![])));
+        let handlers = Handlers::new()
+            .handler("setTitle", {
+                let title = title.clone();
+                move|props: Vec<SharedProps>|if let Some(SharedProps::Title(text)) = props.prop(&SP::Title("")) {
+                    *title.borrow_mut() = text.to_string();
+                }
+            })
+            .handler("save", {
+                let (title, saved) = (title.clone(), saved.clone());
+                move|_|saved.borrow_mut().push(title.take())
+            });
+        // polled on every pass of the loop
+        let mut driver = TestDriver::mountView(LayoutFile::new(&path, handlers).interval(Duration::ZERO)).unwrap();
+        driver.typeText(By::ControlId(1), "milk");
+        driver.click(By::Title("Save"));
+        driver.typeText(By::ControlId(1), "bread");
+        let textBox = driver.get(By::ControlId(1)).hwnd;
+        assert_eq!(*saved.borrow(), vec!["milk"]);
+
+        write(r#"<window Width="300" Height="200"><text-box ControlId="1" DidChange="setTitle"/><button Title="Save" DidClick="save"/><label Title="hint"/></window>"#, 2000);
+        assert!(driver.settle());
+        assert!(driver.find(By::Title("hint")).is_some());
+        assert_eq!(driver.get(By::ControlId(1)).hwnd, textBox);
+        assert_eq!((saved.borrow().clone(), title.borrow().clone()), (vec![String::from("milk")], String::from("bread")));
+        assert!(driver.app.takeErrors().is_empty());
+
+        // the broken file is reported once and the last good tree stays
+        write(r#"<window Width="300" Height="200"><label Title="broken"/>"#, 3000);
+        assert!(driver.settle());
+        assert_eq!(driver.app.takeErrors().len(), 1);
+        assert!(driver.settle());
+        assert!(driver.app.takeErrors().is_empty());
+        assert!(driver.find(By::Title("hint")).is_some());
+        assert!(driver.find(By::Title("broken")).is_none());
+        assert_eq!((saved.borrow().clone(), title.borrow().clone()), (vec![String::from("milk")], String::from("bread")));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
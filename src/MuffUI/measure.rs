@@ -0,0 +1,170 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use crate::muffui::*;
+
+/// room around the title of a button
+const BUTTON_PADDING: Size = Size { cx: 10, cy: 4 };
+/// box of a check box or radio box and the gap to its title
+const CHECK_BOX_SIZE: Size = Size { cx: 20, cy: 13 };
+/// text boxes and selects get the width of this many average chars when the view leaves it out
+const TEXT_BOX_CHARS: i32 = 20;
+/// border and caption of a group box
+const GROUP_BOX_PADDING: Size = Size { cx: 16, cy: 24 };
+
+/// smallest and largest size a control may take, from MinWidth, MaxWidth, MinHeight and MaxHeight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeLimits {
+    pub minWidth: i32,
+    pub maxWidth: i32,
+    pub minHeight: i32,
+    pub maxHeight: i32,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self { minWidth: 0, maxWidth: i32::MAX, minHeight: 0, maxHeight: i32::MAX }
+    }
+}
+
+impl From<&Vec<SharedProps>> for SizeLimits {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        props.iter().fold(Self { ..Default::default() }, |limits, prop|match prop {
+            SharedProps::MinWidth(v) => Self { minWidth: (*v).max(0), ..limits },
+            SharedProps::MaxWidth(v) => Self { maxWidth: (*v).max(0), ..limits },
+            SharedProps::MinHeight(v) => Self { minHeight: (*v).max(0), ..limits },
+            SharedProps::MaxHeight(v) => Self { maxHeight: (*v).max(0), ..limits },
+            _ => limits,
+        })
+    }
+}
+
+impl SizeLimits {
+    /// the minimum wins when the limits cross
+    pub fn width(&self, width: i32) -> i32 {
+        width.min(self.maxWidth).max(self.minWidth)
+    }
+
+    pub fn height(&self, height: i32) -> i32 {
+        height.min(self.maxHeight).max(self.minHeight)
+    }
+
+    pub fn clamp(&self, size: Size) -> Size {
+        Size { cx: self.width(size.cx), cy: self.height(size.cy) }
+    }
+
+    #[allow(dead_code)]
+    pub fn isLimited(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+/// pixels a control of `renderer` needs to show `title` in `fontFace` of `fontSize` pixels in a window with `dpi`,
+/// 0 where the content doesn't decide it
+pub fn preferredSize(backend: &dyn Backend, renderer: &str, title: &str, fontFace: &str, fontSize: i32, dpi: u32) -> Size {
+    let text = backend.measureText(title, fontFace, fontSize);
+    let grow = |size: Size, by: Size| Size { cx: size.cx + scale(by.cx, dpi), cy: size.cy + scale(by.cy, dpi) };
+    let padding = BUTTON_PADDING;
+
+    match renderer {
+        "label" => text,
+        "button" => grow(text, Size { cx: 2 * padding.cx, cy: 2 * padding.cy }),
+        "check-box" | "radio-box" => Size {
+            cx: grow(text, CHECK_BOX_SIZE).cx,
+            cy: text.cy.max(scale(CHECK_BOX_SIZE.cy, dpi)) + scale(padding.cy, dpi),
+        },
+        "group-box" => grow(text, GROUP_BOX_PADDING),
+        "text-box" | "select" => {
+            let average = backend.measureText("x", fontFace, fontSize);
+            Size { cx: text.cx.max(TEXT_BOX_CHARS * average.cx), cy: grow(text, padding).cy }
+        },
+        _ => Size::default(),
+    }
+}
+
+/// the measure pass: props in pixels of a window with `dpi`, with the font size resolved,
+/// the Width and Height the view left out taken from the content of the control and both kept in its limits
+pub fn measure(backend: &dyn Backend, props: &[SharedProps], dpi: u32) -> Vec<SharedProps> {
+    let props = scaleProps(props, dpi);
+    let mut renderer: Rc<str> = Rc::from("");
+    let mut title: Rc<str> = Rc::from("");
+    let mut fontFace: Option<Rc<str>> = None;
+    let mut fontSize = None;
+    let (mut width, mut height) = (CW_USEDEFAULT, CW_USEDEFAULT);
+    for prop in props.iter() {
+        match prop {
+            SharedProps::Renderer(v) => renderer = v.clone(),
+            SharedProps::Title(v) => title = v.clone(),
+            SharedProps::FontFace(v) => fontFace = Some(v.clone()),
+            SharedProps::FontSize(v) => fontSize = Some(*v),
+            SharedProps::Width(v) => width = *v,
+            SharedProps::Height(v) => height = *v,
+            _ => (),
+        }
+    }
+
+    // the system font is sized for the system dpi
+    let fontSize = fontSize.unwrap_or_else(||rescale(backend.systemFontSize(), backend.systemDpi(), dpi));
+    if width == CW_USEDEFAULT || height == CW_USEDEFAULT {
+        let fontFace = fontFace.map(|f|f.to_string()).unwrap_or_else(||backend.systemFontFace());
+        let preferred = preferredSize(backend, &renderer, &title, &fontFace, fontSize, dpi);
+        if width == CW_USEDEFAULT && preferred.cx > 0 {
+            width = preferred.cx;
+        }
+        if height == CW_USEDEFAULT && preferred.cy > 0 {
+            height = preferred.cy;
+        }
+    }
+
+    let limits = SizeLimits::from(&props);
+    let size = [
+        (width, limits.width(width), SharedProps::Width as fn(i32) -> SharedProps),
+        (height, limits.height(height), SharedProps::Height),
+    ];
+    let sizes = size.into_iter()
+        .filter(|(v, _, _)|*v != CW_USEDEFAULT)
+        .map(|(_, v, prop)|prop(v))
+        .chain([SharedProps::FontSize(fontSize)])
+        .collect::<Vec<_>>();
+
+    props.merge(sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(props: Vec<SharedProps>) -> (i32, i32) {
+        let props = measure(&HeadlessBackend::new(), &props, BASE_DPI);
+        let width = match props.prop(&SP::Width(0)) { Some(SharedProps::Width(w)) => *w, _ => CW_USEDEFAULT };
+        let height = match props.prop(&SP::Height(0)) { Some(SharedProps::Height(h)) => *h, _ => CW_USEDEFAULT };
+        (width, height)
+    }
+
+    #[test]
+    fn it_measures_controls_from_title() {
+        // headless text is 6 px per char and 16 px high
+        assert_eq!(size(Label::new(SP::Title("Name:")).props), (30, 16));
+        assert_eq!(size(Button::new(SP::Title("Save")).props), (44, 24));
+        assert_eq!(size(CheckBox::new(SP::Title("Mark All")).props), (68, 20));
+        assert_eq!(size(Label::new([SP::Title("Name:"), SP::Width(100)]).props), (100, 16));
+        assert_eq!(size(Panel::new(SP::Title("Name:")).props), (CW_USEDEFAULT, CW_USEDEFAULT));
+    }
+
+    #[test]
+    fn it_measures_in_pixels_of_the_window() {
+        let props = measure(&HeadlessBackend::new(), &Button::new([SP::Title("Save"), SP::PosX(10)]).props, 192);
+        assert_eq!(format!("{:?}", props.prop(&SP::PosX(0))), "Some(PosX(20))");
+        // the font doubles with the dpi, the padding around the title too
+        assert_eq!(format!("{:?}", props.prop(&SP::FontSize(0))), "Some(FontSize(32))");
+        assert_eq!(format!("{:?}", props.prop(&SP::Width(0))), "Some(Width(88))");
+    }
+
+    #[test]
+    fn it_keeps_sizes_in_limits() {
+        assert_eq!(size(Label::new([SP::Title("a long label"), SP::MaxWidth(50), SP::MinHeight(20)]).props), (50, 20));
+        assert_eq!(size(Panel::new([SP::Width(10), SP::Height(300), SP::MinWidth(40), SP::MaxHeight(200)]).props), (40, 200));
+        // the minimum wins over a smaller maximum
+        assert_eq!(SizeLimits { minWidth: 30, maxWidth: 20, ..Default::default() }.width(25), 30);
+    }
+}
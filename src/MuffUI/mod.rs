@@ -1,40 +1,90 @@
 extern crate self as MuffUI;
 
+#[cfg(windows)]
 pub mod win;
 mod app;
+mod backend;
 mod action;
 mod button;
 mod check_box;
 mod command;
+mod dock_panel;
+mod dpi;
 mod event_hub;
+mod event_recorder;
+mod events;
+mod flex;
+mod grid;
 mod group_box;
+mod headless_backend;
 mod label;
+mod layout_debug;
+mod markup;
+mod measure;
 mod uicontext;
 mod panel;
 mod radio_box;
 mod renderable;
 mod resize_layout;
+mod scroll_view;
 mod select;
 mod shared_properties;
+mod snapshot;
+mod split;
 mod text_box;
+mod view;
+mod virtual_list;
+#[cfg(test)]
+mod test_driver;
 mod window;
+#[cfg(windows)]
+mod win32_backend;
 pub mod utils;
 
+#[cfg(windows)]
 pub use win as Win;
 pub use app::*;
+pub use backend::*;
 pub use action::*;
 pub use button::*;
 pub use check_box::*;
 pub use command::*;
+#[allow(unused_imports)]
+pub use dock_panel::*;
+pub use dpi::*;
 pub use event_hub::*;
+pub use event_recorder::*;
+pub use events::*;
+pub use flex::*;
 pub use label::*;
+pub use layout_debug::*;
+#[allow(unused_imports)]
+pub use markup::*;
+pub use measure::*;
+#[allow(unused_imports)]
+pub use grid::*;
+#[allow(unused_imports)]
 pub use group_box::*;
+#[allow(unused_imports)]
+pub use headless_backend::*;
 pub use panel::*;
 pub use radio_box::*;
 pub use renderable::*;
 pub use resize_layout::*;
+pub use scroll_view::*;
+#[allow(unused_imports)]
 pub use select::*;
 pub use uicontext::*;
 pub use shared_properties::*;
+#[allow(unused_imports)]
+pub use snapshot::*;
+#[allow(unused_imports)]
+pub use split::*;
 pub use text_box::*;
+#[cfg(test)]
+pub use test_driver::*;
+#[allow(unused_imports)]
+pub use virtual_list::*;
 pub use window::*;
+#[cfg(windows)]
+pub use win32_backend::*;
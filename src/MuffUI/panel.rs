@@ -1,32 +1,22 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
-use crate::Win;
 use crate::muffui::*;
 use crate::muffui::SharedProps::*;
 
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Panel {
     pub children: Option<Rc<dyn Renderable>>,
     pub props: Vec<SharedProps>,
 }
 
-impl Default for Panel {
-    fn default() -> Self {
-        Self {
-            children: Default::default(),
-            props: vec![]
-        }
-    }
-}
-
 impl Renderable for Panel {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         Some(self.children.as_ref()?.clone())
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let context = context.render(parent, index, self, msg);
         match &self.children {
             Some(children) => children.render(context, index, &format!("{}:1", index), msg),
@@ -58,6 +48,7 @@ impl Panel {
         }
     }
 
+    #[allow(dead_code)]
     pub fn posX(self, posX: i32) -> Self {
         Self {
             props: self.props.merge(PosX(posX)),
@@ -65,6 +56,7 @@ impl Panel {
         }
     }
 
+    #[allow(dead_code)]
     pub fn posY(self, posY: i32) -> Self {
         Self {
             props: self.props.merge(PosY(posY)),
@@ -72,6 +64,7 @@ impl Panel {
         }
     }
 
+    #[allow(dead_code)]
     pub fn width(self, width: i32) -> Self {
         Self {
             props: self.props.merge(Width(width)),
@@ -79,6 +72,7 @@ impl Panel {
         }
     }
 
+    #[allow(dead_code)]
     pub fn height(self, height: i32) -> Self {
         Self {
             props: self.props.merge(Height(height)),
@@ -86,21 +80,9 @@ impl Panel {
         }
     }
 
-    pub fn content<A: 'static, B: 'static, C: 'static, D: 'static, E: 'static, F: 'static, G: 'static, T: 'static, FF>(self, mut children: FF) -> Self
-        where
-            A: Renderable,
-            B: Renderable,
-            C: Renderable,
-            D: Renderable,
-            E: Renderable,
-            F: Renderable,
-            G: Renderable,
-            T: Into<ContentArgs<A, B, C, D, E, F, G>>,
-            FF: FnMut() -> T
-    {
-        let args: ContentArgs<A, B, C, D, E, F, G> = children().into();
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
         Self {
-            children: Some(Rc::new(args)),
+            children: Some(Rc::new(children().intoChildren())),
             ..self
         }
     }
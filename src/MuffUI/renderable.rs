@@ -1,14 +1,17 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
-use crate::Win;
+use std::fmt::Write;
+use std::collections::HashSet;
+use std::time::Duration;
 use crate::muffui::uicontext::UIContext;
-use crate::muffui::SharedProps;
+use crate::muffui::{SharedProps, SP, VectorExtention};
+use crate::muffui::Message;
 
 
 pub trait Renderable {
     fn childs(&self) -> Option<Rc<dyn Renderable>>;
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let childs = self.childs();
         match childs {
             Some(children) => children.render(context, parent, index, msg),
@@ -19,8 +22,30 @@ pub trait Renderable {
     fn toViewState(&self) -> Vec<SharedProps> {
         vec![]
     }
+
+    /// polled every `reloadInterval`, true when the view changed by itself and has to be rendered again,
+    /// an error is reported once and the view stays as it was
+    fn reload(&self) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    /// how often the app polls `reload`, never when None
+    fn reloadInterval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// controls this view renders at `index`, with their paths and props, stacks place them one after another
+    fn layoutItems(&self, index: &str) -> Vec<(String, Vec<SharedProps>)> {
+        let props = self.toViewState();
+        if props.prop(&SP::Renderer("")).is_some() {
+            return vec![(String::from(index), props)];
+        }
+
+        self.childs().map(|c|c.layoutItems(index)).unwrap_or_default()
+    }
 }
 
+#[allow(dead_code)]
 pub struct EmptyRenderable {
 
 }
@@ -29,7 +54,7 @@ impl Renderable for EmptyRenderable {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         None
     }
-    fn render(&self, context: Box<UIContext>, _: &str, _: &str, _: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, _: &str, _: &str, _: Option<Message>) -> Box<UIContext> {
         context
     }
     fn toViewState(&self) -> Vec<SharedProps> {
@@ -37,122 +62,98 @@ impl Renderable for EmptyRenderable {
     }
 }
 
-pub enum ContentArgs<A: Renderable, B: Renderable, C: Renderable, D: Renderable, E: Renderable, F: Renderable, G: Renderable> {
-    OneArg(A),
-    TwoArgs(A, B),
-    ThreeArgs(A, B, C),
-    FourArgs(A, B, C, D),
-    FiveArgs(A, B, C, D, E),
-    SixArgs(A, B, C, D, E, F),
-    SevenArgs(A, B, C, D, E, F, G),
+/// children of a container, the n-th one is rendered at "<index>_<n>" counting from 1
+#[derive(Clone, Default)]
+pub struct Children {
+    pub items: Vec<Rc<dyn Renderable>>,
 }
 
-impl<A, B, C, D, E, F, G> ContentArgs<A, B, C, D, E, F, G> where A: Renderable, B: Renderable, C: Renderable, D: Renderable, E: Renderable, F: Renderable, G: Renderable {
-    #[allow(dead_code)]
-    pub fn new<T>(args: T) -> ContentArgs<A, B, C, D, E, F, G>
-        where T: Into<ContentArgs<A, B, C, D, E, F, G>>
-    {
-        args.into()
+impl Renderable for Children {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        None
     }
-}
 
-impl<A: Renderable> From<A> for ContentArgs<A, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-    fn from(a: A) -> ContentArgs<A, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-        ContentArgs::OneArg(a)
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut idx = 0;
+        self.items.iter().fold(context, |res, item|{
+            idx += 1;
+            item.render(res, parent, &format!("{}_{}", index, idx), msg)
+        })
     }
-}
 
-impl<A: Renderable, B: Renderable> From<(A, B)> for ContentArgs<A, B, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-    fn from((a, b): (A, B)) -> ContentArgs<A, B, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-        ContentArgs::TwoArgs(a, b)
+    fn layoutItems(&self, index: &str) -> Vec<(String, Vec<SharedProps>)> {
+        self.items.iter().enumerate()
+            .flat_map(|(idx, item)|item.layoutItems(&format!("{}_{}", index, idx + 1)))
+            .collect()
     }
-}
 
-impl<A: Renderable, B: Renderable, C: Renderable> From<(A, B, C)> for ContentArgs<A, B, C, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-    fn from((a, b, c): (A, B, C)) -> ContentArgs<A, B, C, EmptyRenderable, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-        ContentArgs::ThreeArgs(a, b, c)
+    fn toViewState(&self) -> Vec<SharedProps> {
+        vec![]
     }
 }
 
-impl<A: Renderable, B: Renderable, C: Renderable, D: Renderable> From<(A, B, C, D)> for ContentArgs<A, B, C, D, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-    fn from((a, b, c, d): (A, B, C, D)) -> ContentArgs<A, B, C, D, EmptyRenderable, EmptyRenderable, EmptyRenderable> {
-        ContentArgs::FourArgs(a, b, c, d)
-    }
+/// what `content()` of a container accepts: a single child, a tuple of children or a vector of them
+pub trait IntoChildren {
+    fn intoChildren(self) -> Children;
 }
 
-impl<A: Renderable, B: Renderable, C: Renderable, D: Renderable, E: Renderable> From<(A, B, C, D, E)> for ContentArgs<A, B, C, D, E, EmptyRenderable, EmptyRenderable> {
-    fn from((a, b, c, d, e): (A, B, C, D, E)) -> ContentArgs<A, B, C, D, E, EmptyRenderable, EmptyRenderable> {
-        ContentArgs::FiveArgs(a, b, c, d, e)
+impl<A: Renderable + 'static> IntoChildren for A {
+    fn intoChildren(self) -> Children {
+        Children { items: vec![Rc::new(self)] }
     }
 }
 
-impl<A: Renderable, B: Renderable, C: Renderable, D: Renderable, E: Renderable, F: Renderable> From<(A, B, C, D, E, F)> for ContentArgs<A, B, C, D, E, F, EmptyRenderable> {
-    fn from((a, b, c, d, e, f): (A, B, C, D, E, F)) -> ContentArgs<A, B, C, D, E, F, EmptyRenderable> {
-        ContentArgs::SixArgs(a, b, c, d, e, f)
+impl IntoChildren for Vec<Rc<dyn Renderable>> {
+    fn intoChildren(self) -> Children {
+        Children { items: self }
     }
 }
 
-impl<A: Renderable, B: Renderable, C: Renderable, D: Renderable, E: Renderable, F: Renderable, G: Renderable> From<(A, B, C, D, E, F, G)> for ContentArgs<A, B, C, D, E, F, G> {
-    fn from((a, b, c, d, e, f, g): (A, B, C, D, E, F, G)) -> ContentArgs<A, B, C, D, E, F, G> {
-        ContentArgs::SevenArgs(a, b, c, d, e, f, g)
+impl IntoChildren for Vec<Box<dyn Renderable>> {
+    fn intoChildren(self) -> Children {
+        Children { items: self.into_iter().map(Rc::from).collect() }
     }
 }
 
-impl<A: Renderable, B: Renderable, C: Renderable, D: Renderable, E: Renderable, F: Renderable, G: Renderable> Renderable for ContentArgs<A, B, C, D, E, F, G> {
-    fn childs(&self) -> Option<Rc<dyn Renderable>> {
-        None
-    }
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
-        match self {
-            ContentArgs::SevenArgs(a, b, c, d, e, f, g) => {
-                let context = a.render(context, parent, &format!("{}_1", index), msg);
-                let context = b.render(context, parent, &format!("{}_2", index), msg);
-                let context = c.render(context, parent, &format!("{}_3", index), msg);
-                let context = d.render(context, parent, &format!("{}_4", index), msg);
-                let context = e.render(context, parent, &format!("{}_5", index), msg);
-                let context = f.render(context, parent, &format!("{}_6", index), msg);
-                g.render(context, parent, &format!("{}_7", index), msg)
-            },
-            ContentArgs::SixArgs(a, b, c, d, e, f) => {
-                let context = a.render(context, parent, &format!("{}_1", index), msg);
-                let context = b.render(context, parent, &format!("{}_2", index), msg);
-                let context = c.render(context, parent, &format!("{}_3", index), msg);
-                let context = d.render(context, parent, &format!("{}_4", index), msg);
-                let context = e.render(context, parent, &format!("{}_5", index), msg);
-                f.render(context, parent, &format!("{}_6", index), msg)
-            },
-            ContentArgs::FiveArgs(a, b, c, d, e) => {
-                let context = a.render(context, parent, &format!("{}_1", index), msg);
-                let context = b.render(context, parent, &format!("{}_2", index), msg);
-                let context = c.render(context, parent, &format!("{}_3", index), msg);
-                let context = d.render(context, parent, &format!("{}_4", index), msg);
-                e.render(context, parent, &format!("{}_5", index), msg)
-            },
-            ContentArgs::FourArgs(a, b, c, d) => {
-                let context = a.render(context, parent, &format!("{}_1", index), msg);
-                let context = b.render(context, parent, &format!("{}_2", index), msg);
-                let context = c.render(context, parent, &format!("{}_3", index), msg);
-                d.render(context, parent, &format!("{}_4", index), msg)
-            },
-            ContentArgs::ThreeArgs(a, b, c) => {
-                let context = a.render(context, parent, &format!("{}_1", index), msg);
-                let context = b.render(context, parent, &format!("{}_2", index), msg);
-                c.render(context, parent, &format!("{}:3", index), msg)
-            },
-            ContentArgs::TwoArgs(a, b) => {
-                let context = a.render(context, parent, &format!("{}_1", index), msg);
-                b.render(context, parent, &format!("{}_2", index), msg)
-            },
-            ContentArgs::OneArg(a) => a.render(context, parent, &format!("{}_1", index), msg),
+macro_rules! tuple_children {
+    ($($name:ident),+) => {
+        impl<$($name: Renderable + 'static),+> IntoChildren for ($($name,)+) {
+            fn intoChildren(self) -> Children {
+                let ($($name,)+) = self;
+                Children { items: vec![$(Rc::new($name)),+] }
+            }
         }
-    }
-    fn toViewState(&self) -> Vec<SharedProps> {
-        vec![]
-    }
+    };
 }
 
+tuple_children!(A, B);
+tuple_children!(A, B, C);
+tuple_children!(A, B, C, D);
+tuple_children!(A, B, C, D, E);
+tuple_children!(A, B, C, D, E, F);
+tuple_children!(A, B, C, D, E, F, G);
+tuple_children!(A, B, C, D, E, F, G, H);
+tuple_children!(A, B, C, D, E, F, G, H, I);
+tuple_children!(A, B, C, D, E, F, G, H, I, J);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W);
+tuple_children!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X);
+
 pub struct ForEach<T: Renderable> {
     pub children: Vec<T>,
+    /// keys of the children, empty when children are matched by position
+    pub keys: Vec<String>,
 }
 
 impl<T: Renderable> Renderable for ForEach<T> {
@@ -160,15 +161,25 @@ impl<T: Renderable> Renderable for ForEach<T> {
         None
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let mut idx = 0;
         self.children.iter().fold(context, |res, item|{
-            let res = item.render(res, parent, &format!("{}[{}]", index, idx), msg);
+            let res = match self.keys.get(idx) {
+                Some(key) => item.render(res, parent, &format!("{}[{}]", index, key), msg),
+                _ => item.render(res, parent, &format!("{}[{}]", index, idx), msg),
+            };
             idx += 1;
             res
         })
     }
 
+    fn layoutItems(&self, index: &str) -> Vec<(String, Vec<SharedProps>)> {
+        self.children.iter().enumerate().flat_map(|(idx, item)|match self.keys.get(idx) {
+            Some(key) => item.layoutItems(&format!("{}[{}]", index, key)),
+            _ => item.layoutItems(&format!("{}[{}]", index, idx)),
+        }).collect()
+    }
+
     fn toViewState(&self) -> Vec<SharedProps> {
         vec![]
     }
@@ -184,6 +195,85 @@ impl<T: Renderable> ForEach<T> {
         }).collect();
         Self {
             children,
+            keys: vec![],
+        }
+    }
+
+    /// children keep their controls while their `key` stays the same, wherever they move in the list.
+    /// the keys have to be unique, two children with the same key would share one control
+    pub fn keyed<A, K: ToString>(items: Vec<A>, key: impl Fn(&A) -> K, f: impl Fn(A, i32) -> T) -> Self {
+        let keys = items.iter().map(|i|escapeKey(&key(i).to_string())).collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        let duplicate = keys.iter().find(|k|!seen.insert(*k));
+        debug_assert!(duplicate.is_none(), "ForEach key {:?} is used by more than one child", duplicate.map(String::as_str).unwrap_or_default());
+
+        Self {
+            keys,
+            ..Self::new(items, f)
         }
     }
 }
+
+/// keys go into paths as "index[key]", the characters paths are split at are escaped
+/// so that a key can't pass for a nested path
+fn escapeKey(key: &str) -> String {
+    key.chars().fold(String::new(), |mut res, c|{
+        match c {
+            '%' | ':' | '_' | '[' | ']' => { let _ = write!(res, "%{:02X}", c as u32); },
+            _ => res.push(c),
+        }
+        res
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::muffui::*;
+
+    #[test]
+    fn it_renders_any_number_of_children() {
+        let view = Panel::new([]).content(||(
+            Label::new([]), Label::new([]), Label::new([]), Label::new([]), Label::new([]),
+            Label::new([]), Label::new([]), Label::new([]), Label::new([]), Label::new([]),
+        ));
+
+        let context = view.render(UIContext::create(Rc::new(HeadlessBackend::new())), "/", "0", None);
+
+        assert_eq!(context.items.len(), 11);
+        assert!(context.items.contains_key("0:1_3"));
+        assert!(context.items.contains_key("0:1_10"));
+    }
+
+    #[test]
+    fn it_renders_vector_of_children() {
+        let view = Panel::new([]).content(||vec![
+            Rc::new(Label::new([])) as Rc<dyn Renderable>,
+            Rc::new(Panel::new([]).content(||Label::new([]))),
+        ]);
+
+        let context = view.render(UIContext::create(Rc::new(HeadlessBackend::new())), "/", "0", None);
+
+        let mut paths = context.items.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["0", "0:1_1", "0:1_2", "0:1_2:1_1"]);
+    }
+
+    #[test]
+    fn it_escapes_path_separators_in_keys() {
+        let keys = vec!["a", "a]:1_1[b", "1_1", "50%"];
+        let view = Panel::new([]).content(move||ForEach::keyed(keys.clone(), |k|*k, |_, _|Label::new([])));
+
+        let context = view.render(UIContext::create(Rc::new(HeadlessBackend::new())), "/", "0", None);
+
+        let mut paths = context.items.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["0", "0:1_1[1%5F1]", "0:1_1[50%25]", "0:1_1[a%5D%3A1%5F1%5Bb]", "0:1_1[a]"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ForEach key \"b\" is used by more than one child")]
+    fn it_rejects_duplicate_keys() {
+        ForEach::keyed(vec!["a", "b", "b"], |k|*k, |_, _|Label::new([]));
+    }
+}
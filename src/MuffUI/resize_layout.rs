@@ -1,8 +1,8 @@
 #![allow(non_snake_case)]
 
-use std::sync::Once;
-use std::mem::MaybeUninit;
-use std::sync::Mutex;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 macro_rules! if_then {
     ($c:expr, $v:expr) => {
@@ -10,7 +10,7 @@ macro_rules! if_then {
     };
 }
 
-use crate::Win;
+use crate::muffui::*;
 
 /// proportinally moves the control with the size of the window
 #[allow(dead_code)]
@@ -66,6 +66,62 @@ pub const ANF_TOPBOTTOM: usize = ANF_TOP | ANF_BOTTOM;
 /// some combinations
 pub const ANF_LEFTRIGHT: usize = ANF_LEFT | ANF_RIGHT;
 
+const ANCHOR_NAMES: [(usize, &str); 14] = [
+    (ANF_DOCK_TOP, "ANF_DOCK_TOP"),
+    (ANF_DOCK_BOTTOM, "ANF_DOCK_BOTTOM"),
+    (ANF_DOCK_LEFT, "ANF_DOCK_LEFT"),
+    (ANF_DOCK_RIGHT, "ANF_DOCK_RIGHT"),
+    (ANF_TOP, "ANF_TOP"),
+    (ANF_BOTTOM, "ANF_BOTTOM"),
+    (ANF_LEFT, "ANF_LEFT"),
+    (ANF_RIGHT, "ANF_RIGHT"),
+    (ANF_AUTOMATIC, "ANF_AUTOMATIC"),
+    (ANF_DOCK_TOP_EX, "ANF_DOCK_TOP_EX"),
+    (ANF_DOCK_BOTTOM_EX, "ANF_DOCK_BOTTOM_EX"),
+    (ANF_DOCK_LEFT_EX, "ANF_DOCK_LEFT_EX"),
+    (ANF_DOCK_RIGHT_EX, "ANF_DOCK_RIGHT_EX"),
+    (ANF_ERASE, "ANF_ERASE"),
+];
+
+/// e.g. "ANF_TOP|ANF_LEFT"
+pub fn anchorToString(flags: usize) -> String {
+    let names = ANCHOR_NAMES.iter()
+        .filter(|(flag, _)|flags & flag == *flag)
+        .map(|(_, name)|*name)
+        .collect::<Vec<_>>();
+
+    if names.is_empty() {
+        String::from("ANF_NONE")
+    } else {
+        names.join("|")
+    }
+}
+
+/// parses "ANF_TOP|ANF_LEFT", combinations like ANF_DOCK_ALL and plain numbers are accepted too
+#[allow(dead_code)]
+pub fn anchorFromString(value: &str) -> Option<usize> {
+    let combinations = [
+        (ANF_NONE, "ANF_NONE"),
+        (ANF_DOCK_ALL, "ANF_DOCK_ALL"),
+        (ANF_TOPLEFT, "ANF_TOPLEFT"),
+        (ANF_TOPRIGHT, "ANF_TOPRIGHT"),
+        (ANF_BOTTOMLEFT, "ANF_BOTTOMLEFT"),
+        (ANF_BOTTOMRIGHT, "ANF_BOTTOMRIGHT"),
+        (ANF_TOPBOTTOM, "ANF_TOPBOTTOM"),
+        (ANF_LEFTRIGHT, "ANF_LEFTRIGHT"),
+    ];
+
+    value.split('|').map(|name|name.trim()).try_fold(ANF_NONE, |flags, name|{
+        let flag = ANCHOR_NAMES.iter().chain(combinations.iter())
+            .find(|(_, n)|*n == name)
+            .map(|(flag, _)|*flag)
+            .or_else(||name.parse().ok())?;
+
+        Some(flags | flag)
+    })
+}
+
+
 /// flags for InitAnchors 
 ///             
 /// calculate size occupied by all controls, useful for formviews       
@@ -75,64 +131,167 @@ pub const ANIF_CALCSIZE: usize =  0x0001;
 /// add a sizing-grip to the parent window
 pub const ANIF_SIZEGRIP: usize =  0x0002;
 
-#[derive(Debug)]
-struct FRECT {
-    top: f32,
-    left: f32,
-    right: f32,
-    bottom: f32,
+/// flags for an ANF_AUTOMATIC control, picked by the quarter of the parent the control sits in
+pub fn automaticAnchors(rect: &Rect, client: Size) -> usize {
+    let mut flags = 0;
+
+    if_then!(rect.top < client.cy / 2, flags |= ANF_TOP);
+    if_then!(rect.bottom >= client.cy / 2, flags |= ANF_BOTTOM);
+
+    if_then!(rect.left < client.cx / 2, flags |= ANF_LEFT);
+    if_then!(rect.right >= client.cx / 2, flags |= ANF_RIGHT);
+
+    flags
 }
 
-impl FRECT {
-    pub fn set(&mut self, left: f32, top: f32, right: f32, bottom: f32) {
-        self.left = left;
-        self.top = top;
-        self.right = right;
-        self.bottom = bottom;
+/// moves one axis of a rect, `near` and `far` are the anchor flags of its two sides
+fn solveAxis(start: i32, end: i32, delta: i32, flags: usize, near: usize, far: usize) -> (i32, i32) {
+    match (flags & near > 0, flags & far > 0) {
+        (true, false) => (start, end),
+        (false, true) => (start + delta, end + delta),
+        (true, true) => (start, (end + delta).max(start)),
+        _ => (start + delta / 2, end + delta / 2),
     }
 }
 
-impl Default for FRECT {
-    fn default() -> Self {
-        Self {
-            top: 0f32,
-            left: 0f32,
-            right: 0f32,
-            bottom: 0f32,
+/// rects of the controls once the client area of their parent changes from `prev` to `current`.
+/// `entries` are (rect, flags) as they were when the parent had the `prev` size
+pub fn solveAnchors(prev: Size, current: Size, entries: &[(Rect, usize)]) -> Vec<Rect> {
+    let (dx, dy) = (current.cx - prev.cx, current.cy - prev.cy);
+
+    entries.iter().map(|(rect, flags)|{
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+        let (flags, rect) = (*flags, *rect);
+
+        if flags & ANF_DOCK_ALL == ANF_DOCK_ALL {
+            return Rect { left: 0, top: 0, right: current.cx, bottom: current.cy };
+        } else if flags & ANF_DOCK_TOP > 0 {
+            return Rect { left: 0, top: 0, right: current.cx, bottom: height };
+        } else if flags & ANF_DOCK_BOTTOM > 0 {
+            return Rect { left: 0, top: current.cy - height, right: current.cx, bottom: current.cy };
+        } else if flags & ANF_DOCK_LEFT > 0 {
+            return Rect { left: 0, top: 0, right: width, bottom: current.cy };
+        } else if flags & ANF_DOCK_RIGHT > 0 {
+            return Rect { left: current.cx - width, top: 0, right: current.cx, bottom: current.cy };
+        }
+
+        let (mut left, mut right) = solveAxis(rect.left, rect.right, dx, flags, ANF_LEFT, ANF_RIGHT);
+        let (mut top, mut bottom) = solveAxis(rect.top, rect.bottom, dy, flags, ANF_TOP, ANF_BOTTOM);
+
+        if flags & ANF_DOCK_LEFT_EX > 0 {
+            (left, right) = (0, right - left);
+        }
+        if flags & ANF_DOCK_RIGHT_EX > 0 {
+            right = current.cx.max(left);
+        }
+        if flags & ANF_DOCK_TOP_EX > 0 {
+            (top, bottom) = (0, bottom - top);
         }
+        if flags & ANF_DOCK_BOTTOM_EX > 0 {
+            bottom = current.cy.max(top);
+        }
+
+        Rect { left, top, right, bottom }
+    }).collect()
+}
+
+/// keeps a solved rect in `limits`, the anchored side stays where it is
+pub fn limitRect(rect: Rect, flags: usize, limits: &SizeLimits) -> Rect {
+    let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+    let (limitedWidth, limitedHeight) = (limits.width(width), limits.height(height));
+    let fromFar = |near: usize, far: usize| flags & far > 0 && flags & near == 0;
+
+    let (left, right) = match fromFar(ANF_LEFT | ANF_DOCK_LEFT | ANF_DOCK_LEFT_EX, ANF_RIGHT | ANF_DOCK_RIGHT) {
+        true => (rect.right - limitedWidth, rect.right),
+        _ => (rect.left, rect.left + limitedWidth),
+    };
+    let (top, bottom) = match fromFar(ANF_TOP | ANF_DOCK_TOP | ANF_DOCK_TOP_EX, ANF_BOTTOM | ANF_DOCK_BOTTOM) {
+        true => (rect.bottom - limitedHeight, rect.bottom),
+        _ => (rect.top, rect.top + limitedHeight),
+    };
+
+    Rect { left, top, right, bottom }
+}
+
+/// smallest client size along one axis that keeps a control of `start..end` whole and at least `min` large
+fn minimumAxis(base: i32, start: i32, end: i32, min: i32, flags: usize, (near, far): (usize, usize), (nearEx, farEx): (usize, usize)) -> i32 {
+    let start = if flags & nearEx > 0 { 0 } else { start };
+    if flags & farEx > 0 {
+        return start + min;
+    }
+
+    match (flags & near > 0, flags & far > 0) {
+        (true, false) => end,
+        (false, true) => base - start,
+        (true, true) => base - (end - start) + min,
+        _ => (base - 2 * start).max(2 * end - base),
     }
 }
 
-#[derive(Debug)]
-struct FSIZE {
-    cx: f32,
-    cy: f32,
+/// smallest client size of a parent that has `entries` of (rect, flags, limits) at the `base` size,
+/// so no control has to get smaller than its minimum or move out of the parent
+pub fn minimumClientSize(base: Size, entries: &[(Rect, usize, SizeLimits)]) -> Size {
+    entries.iter().fold(Size::default(), |size, (rect, flags, limits)|{
+        let (flags, width, height) = (*flags, rect.right - rect.left, rect.bottom - rect.top);
+        let (cx, cy) = if flags & ANF_DOCK_ALL == ANF_DOCK_ALL {
+            (limits.minWidth, limits.minHeight)
+        } else if flags & (ANF_DOCK_TOP | ANF_DOCK_BOTTOM) > 0 {
+            (limits.minWidth, limits.height(height))
+        } else if flags & (ANF_DOCK_LEFT | ANF_DOCK_RIGHT) > 0 {
+            (limits.width(width), limits.minHeight)
+        } else {
+            (
+                minimumAxis(base.cx, rect.left, rect.right, limits.minWidth, flags, (ANF_LEFT, ANF_RIGHT), (ANF_DOCK_LEFT_EX, ANF_DOCK_RIGHT_EX)),
+                minimumAxis(base.cy, rect.top, rect.bottom, limits.minHeight, flags, (ANF_TOP, ANF_BOTTOM), (ANF_DOCK_TOP_EX, ANF_DOCK_BOTTOM_EX)),
+            )
+        };
+
+        Size { cx: size.cx.max(cx), cy: size.cy.max(cy) }
+    })
 }
 
 #[derive(Debug)]
 pub struct ControlEntry {
     controlId: i32,
     flags: usize,
-    rect: FRECT,
-    hwnd: Win::HWND,
+    /// rect of the control when the parent had the `base` size of the map
+    rect: Rect,
+    hwnd: Handle,
+    limits: SizeLimits,
+}
+
+impl ControlEntry {
+    pub fn flags(&self) -> usize {
+        self.flags
+    }
+
+    /// rect the anchors are solved from, at the `base` size of the map
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
 }
 
 #[derive(Debug)]
 pub struct AnchorMap {
-    prev: Win::RECT,
+    prev: Rect,
     isInitialized: bool,
-    current: Win::RECT,
-    client: Win::RECT,
+    current: Rect,
+    client: Rect,
+    delta: Size,
+    /// ANF_LEFT/ANF_TOP/ANF_RIGHT/ANF_BOTTOM of the borders the last resize has moved
     sizedBorders: usize,
-    delta: Win::SIZE,
-    pub parent: Win::HWND,
+    /// client size the rects of the entries belong to
+    base: Size,
+    pub parent: Handle,
     count: i32,
     controls: Vec<ControlEntry>,
     defaultEntry: bool,
     defaultFlags: usize,
-    #[allow(dead_code)]
-    backgroundColor: u32,
-    sizeGrip: Win::HWND,
+    sizeGrip: Handle,
+    /// limits of the window or container itself
+    limits: SizeLimits,
+    /// dpi the rects and limits are in
+    dpi: u32,
 }
 
 impl Default for AnchorMap {
@@ -142,15 +301,17 @@ impl Default for AnchorMap {
             isInitialized: false,
             current: Default::default(),
             client: Default::default(),
-            sizedBorders: 0,
             delta: Default::default(),
-            parent: Win::HWND(0),
+            sizedBorders: ANF_NONE,
+            base: Default::default(),
+            parent: Handle(0),
             controls: vec![],
             count: 0,
             defaultEntry: false,
             defaultFlags: 0,
-            backgroundColor: unsafe { Win::GetSysColor(Win::COLOR_BTNFACE) },
             sizeGrip: Default::default(),
+            limits: Default::default(),
+            dpi: 0,
         }
     }
 }
@@ -162,41 +323,57 @@ impl AnchorMap {
         }
     }
 
-    pub fn shared() -> &'static Mutex<AnchorMap> {
-        static mut CONF: MaybeUninit<Mutex<AnchorMap>> = MaybeUninit::uninit();
-        static ONCE: Once = Once::new();
-    
-        ONCE.call_once(|| unsafe {
-            CONF.as_mut_ptr().write(Mutex::new(AnchorMap::new()));
-        });
+    fn registry() -> &'static RefCell<HashMap<Handle, Rc<RefCell<AnchorMap>>>> {
+        thread_local! {
+            static CONF: &'static RefCell<HashMap<Handle, Rc<RefCell<AnchorMap>>>> = Box::leak(Box::new(RefCell::new(HashMap::new())));
+        }
+
+        CONF.with(|conf|*conf)
+    }
+
+    /// map of the controls placed inside `parent`, one per window and container, created on first use
+    pub fn of(parent: Handle) -> Rc<RefCell<AnchorMap>> {
+        Self::registry().borrow_mut().entry(parent)
+            .or_insert_with(||Rc::new(RefCell::new(AnchorMap::new())))
+            .clone()
+    }
 
-        unsafe { &*CONF.as_ptr() }
+    pub fn find(parent: Handle) -> Option<Rc<RefCell<AnchorMap>>> {
+        Self::registry().borrow().get(&parent).cloned()
     }
 
-    pub fn addControl(&mut self, controlId: i32, flags: usize, hwnd: Option<Win::HWND>) -> bool {
+    /// forgets the control in the map of its parent and drops the map of its own children
+    pub fn release(backend: &dyn Backend, hwnd: Handle) {
+        if let Some(am) = Self::find(backend.getParent(hwnd)) {
+            if let Ok(mut am) = am.try_borrow_mut() {
+                am.removeControl(hwnd);
+            }
+        }
+        Self::registry().borrow_mut().remove(&hwnd);
+    }
+
+    /// lays the children of `hwnd` out again after it has been resized
+    pub fn layout(backend: &dyn Backend, hwnd: Handle) -> Option<()> {
+        let am = Self::find(hwnd)?;
+        let mut am = am.try_borrow_mut().ok()?;
+
+        am.handleAnchors(backend, None)
+    }
+
+    pub fn addControl(&mut self, backend: &dyn Backend, controlId: i32, flags: usize, hwnd: Option<Handle>) -> bool {
         if controlId == 0 {
             self.defaultEntry = true;
             self.defaultFlags = flags;
         } else {
-            let mut entry = ControlEntry {
+            self.rebase();
+            let rect = hwnd.and_then(|hwnd|backend.getControlRect(hwnd)).unwrap_or_default();
+            self.controls.push(ControlEntry {
                 controlId,
                 flags,
-                rect: FRECT { left: 0f32, top: 0f32, right: 0f32, bottom: 0f32 },
-                hwnd: hwnd.unwrap_or(Win::HWND(0)),
-            };
-
-            if let Some(hwnd) = hwnd {
-                if let Some(rect) = Win::GetWindowRect(hwnd) {
-                    let parent = Win::GetParent(hwnd);
-                    if let Some(rect) = AnchorMap::ScreenToClient(parent, &rect) {
-                        entry.rect.left = rect.left as _;
-                        entry.rect.top = rect.top as _;
-                        entry.rect.right = rect.right as _;
-                        entry.rect.bottom = rect.bottom as _;
-                    }
-                }
-            }
-            self.controls.push(entry);
+                rect,
+                hwnd: hwnd.unwrap_or(Handle(0)),
+                limits: Default::default(),
+            });
 
             self.count = self.controls.len() as i32;
         }
@@ -204,324 +381,529 @@ impl AnchorMap {
         true
     }
     #[allow(dead_code)]
-    pub fn removeControl(&mut self, hwnd: Win::HWND) {
+    pub fn removeControl(&mut self, hwnd: Handle) {
         if let Some(index) = self.controls.iter().position(|c|c.hwnd == hwnd) {
             self.controls.remove(index);
         }
     }
+    /// takes the new rect of a control moved by the view, anchors apply from there
+    pub fn updateControl(&mut self, hwnd: Handle, rect: &Rect) {
+        self.rebase();
+        if let Some(item) = self.controls.iter_mut().find(|c|c.hwnd == hwnd) {
+            item.rect = *rect;
+        }
+    }
+    /// MinWidth, MaxWidth, MinHeight and MaxHeight of a control in the map
+    pub fn limitControl(&mut self, hwnd: Handle, limits: SizeLimits) {
+        if let Some(item) = self.controls.iter_mut().find(|c|c.hwnd == hwnd) {
+            item.limits = limits;
+        }
+    }
+
+    /// MinWidth, MaxWidth, MinHeight and MaxHeight of the window or container owning the map
+    pub fn setLimits(&mut self, limits: SizeLimits) {
+        self.limits = limits;
+    }
+
+    /// smallest client size of `hwnd` its own limits and the minimum of its children allow,
+    /// children with maps of their own count with their minimum too
+    pub fn minimumSize(hwnd: Handle) -> Size {
+        let am = match Self::find(hwnd) {
+            Some(am) => am,
+            _ => return Size::default(),
+        };
+        let am = match am.try_borrow() {
+            Ok(am) => am,
+            _ => return Size::default(),
+        };
+
+        let own = Size { cx: am.limits.minWidth, cy: am.limits.minHeight };
+        if !am.isInitialized() {
+            return own;
+        }
+
+        let entries = am.controls.iter().map(|c|{
+            let inner = Self::minimumSize(c.hwnd);
+            let limits = SizeLimits {
+                minWidth: c.limits.minWidth.max(inner.cx),
+                minHeight: c.limits.minHeight.max(inner.cy),
+                ..c.limits
+            };
+            (c.rect, c.flags, limits)
+        }).collect::<Vec<_>>();
+        let content = minimumClientSize(am.base, &entries);
+
+        Size { cx: own.cx.max(content.cx), cy: own.cy.max(content.cy) }
+    }
+
+    /// takes the maps of `hwnd` and all the containers in it to `dpi`, so a resize from there keeps
+    /// every control as large in logical units as it was. gives the dpi the maps had before
+    pub fn changeDpi(backend: &dyn Backend, hwnd: Handle, dpi: u32) -> Option<u32> {
+        let prev = {
+            let am = Self::find(hwnd)?;
+            let mut am = am.try_borrow_mut().ok()?;
+            am.rescale(dpi)?
+        };
+
+        let mut stack = backend.getChildren(hwnd);
+        while let Some(child) = stack.pop() {
+            if let Some(am) = Self::find(child) {
+                am.try_borrow_mut().ok().map(|mut am|am.rescale(dpi));
+            }
+            stack.extend(backend.getChildren(child));
+        }
+
+        Some(prev)
+    }
+
+    fn rescale(&mut self, dpi: u32) -> Option<u32> {
+        let prev = self.dpi;
+        if !self.isInitialized || prev == dpi || prev == 0 {
+            return None;
+        }
+
+        let limits = |l: SizeLimits| SizeLimits {
+            minWidth: rescale(l.minWidth, prev, dpi),
+            maxWidth: rescale(l.maxWidth, prev, dpi),
+            minHeight: rescale(l.minHeight, prev, dpi),
+            maxHeight: rescale(l.maxHeight, prev, dpi),
+        };
+        let size = |r: Rect| {
+            let size = rescaleSize(Size { cx: r.right - r.left, cy: r.bottom - r.top }, prev, dpi);
+            Rect { right: r.left + size.cx, bottom: r.top + size.cy, ..r }
+        };
+
+        for item in self.controls.iter_mut() {
+            item.rect = rescaleRect(&item.rect, prev, dpi);
+            item.limits = limits(item.limits);
+        }
+        self.limits = limits(self.limits);
+        self.base = rescaleSize(self.base, prev, dpi);
+        self.client = size(self.client);
+        self.prev = size(self.prev);
+        self.current = size(self.current);
+        self.dpi = dpi;
+
+        Some(prev)
+    }
+
+    /// outer size limits of the window `hwnd`, the minimum grows to what its children need
+    pub fn windowLimits(backend: &dyn Backend, hwnd: Handle) -> SizeLimits {
+        let own = Self::find(hwnd).and_then(|am|am.try_borrow().ok().map(|am|am.limits)).unwrap_or_default();
+        let min = Self::minimumSize(hwnd);
+        let outer = |cx: i32, cy: i32| {
+            let rect = backend.adjustWindowRect(hwnd, Rect { left: 0, top: 0, right: cx, bottom: cy });
+            Size { cx: rect.right - rect.left, cy: rect.bottom - rect.top }
+        };
+        let frame = outer(0, 0);
+        let grow = |v: i32, by: i32| if v == i32::MAX || v == 0 { v } else { v + by };
+
+        SizeLimits {
+            minWidth: grow(min.cx, frame.cx),
+            minHeight: grow(min.cy, frame.cy),
+            maxWidth: grow(own.maxWidth.max(min.cx), frame.cx),
+            maxHeight: grow(own.maxHeight.max(min.cy), frame.cy),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn isInitialized(&self) -> bool {
         self.isInitialized
     }
 
-    #[allow(dead_code)]
-    pub fn findWindow(&self, hwnd: Win::HWND) -> Option<&ControlEntry> {
-        self.controls.iter().find(|c|c.hwnd == hwnd)
+    /// change of the parent size the last resize has made
+    pub fn delta(&self) -> Size {
+        self.delta
     }
 
-    pub fn ScreenToClient(hwnd: Win::HWND, rect: &Win::RECT) -> Option<Win::RECT> {
-        let mut pt1 = Win::POINT { ..Default::default() };
-        let mut pt2 = Win::POINT { ..Default::default() };
-        pt1.x = rect.left;
-        pt1.y = rect.top;
-        pt2.x = rect.right;
-        pt2.y = rect.bottom;
+    pub fn sizedBorders(&self) -> usize {
+        self.sizedBorders
+    }
 
-        let pt1 = Win::ScreenToClient(hwnd, &pt1)?;
-        let pt2 = Win::ScreenToClient(hwnd, &pt2)?;
+    #[allow(dead_code)]
+    pub fn findWindow(&self, hwnd: Handle) -> Option<&ControlEntry> {
+        self.controls.iter().find(|c|c.hwnd == hwnd)
+    }
 
-        Some(Win::RECT {
-            left: pt1.x,
-            top: pt1.y,
-            right: pt2.x,
-            bottom: pt2.y,
-        })
+    fn clientSize(&self) -> Size {
+        Size { cx: self.client.right - self.client.left, cy: self.client.bottom - self.client.top }
     }
 
-    pub fn preProcess(&mut self, rect: &Win::RECT) {
-        self.current.left = rect.left;
-        self.current.top = rect.top;
-        self.current.right = rect.right;
-        self.current.bottom = rect.bottom;
+    /// moves the rects of the entries to the current client size, so new rects can be taken as they are
+    fn rebase(&mut self) {
+        let current = self.clientSize();
+        if !self.isInitialized || current == self.base {
+            return;
+        }
 
-        self.sizedBorders = 0;
+        let entries = self.controls.iter().map(|c|(c.rect, c.flags)).collect::<Vec<_>>();
+        for (item, rect) in self.controls.iter_mut().zip(solveAnchors(self.base, current, &entries)) {
+            item.rect = rect;
+        }
+        self.base = current;
+    }
 
-        if_then!(self.current.left != self.prev.left, self.sizedBorders |= ANF_LEFT);
-        if_then!(self.current.top != self.prev.top, self.sizedBorders |= ANF_TOP);
-        if_then!(self.current.right != self.prev.right, self.sizedBorders |= ANF_RIGHT);
-        if_then!(self.current.bottom != self.prev.bottom, self.sizedBorders |= ANF_BOTTOM);
+    pub fn preProcess(&mut self, rect: &Rect) {
+        self.current = *rect;
 
         self.delta.cx = (self.current.right - self.current.left) - (self.prev.right - self.prev.left);
         self.delta.cy = (self.current.bottom - self.current.top) - (self.prev.bottom - self.prev.top);
+        // a moved window has no sized borders
+        self.sizedBorders = [
+            (self.delta.cx != 0 && self.current.left != self.prev.left, ANF_LEFT),
+            (self.delta.cy != 0 && self.current.top != self.prev.top, ANF_TOP),
+            (self.delta.cx != 0 && self.current.right != self.prev.right, ANF_RIGHT),
+            (self.delta.cy != 0 && self.current.bottom != self.prev.bottom, ANF_BOTTOM),
+        ].iter().filter(|(sized, _)|*sized).fold(ANF_NONE, |flags, (_, flag)|flags | flag);
 
         self.client.right += self.delta.cx;
         self.client.bottom += self.delta.cy;
     }
 
     pub fn postProcess(&mut self) {
-        self.prev.left = self.current.left;
-        self.prev.top = self.current.top;
-        self.prev.right = self.current.right;
-        self.prev.bottom = self.current.bottom;
+        self.prev = self.current;
     }
 
-    pub fn initialize(&mut self, parent: Win::HWND, flags: usize) -> Option<()> {
+    pub fn initialize(&mut self, backend: &dyn Backend, parent: Handle, flags: usize) -> Option<()> {
         self.parent = parent;
-        self.prev = Win::GetWindowRect(self.parent)?;
-        self.current = self.prev.clone();
-        self.client = Win::GetClientRect(self.parent)?;
+        self.prev = backend.getWindowRect(self.parent)?;
+        self.current = self.prev;
+        self.client = backend.getClientRect(self.parent)?;
 
+        let children = backend.getChildren(self.parent);
         for item in self.controls.iter_mut() {
-            let hwndControl = Win::GetDlgItem(self.parent, item.controlId)
-                .unwrap_or(item.hwnd);
+            // control ids are not unique, only the entries added without a handle are looked up by them
+            let hwndControl = match item.hwnd {
+                Handle(0) => children.iter()
+                    .find(|c|backend.getControlId(**c) == item.controlId)
+                    .copied()
+                    .unwrap_or(item.hwnd),
+                hwnd => hwnd,
+            };
 
-            if Win::HWND(0) != hwndControl {
+            if Handle(0) != hwndControl {
                 item.hwnd = hwndControl;
-                let rect = Win::GetWindowRect(hwndControl)?;
-                let parent = Win::GetParent(hwndControl);
-                let rect = AnchorMap::ScreenToClient(parent, &rect)?;
-
-                item.rect.left = rect.left as _;
-                item.rect.top = rect.top as _;
-                item.rect.right = rect.right as _;
-                item.rect.bottom = rect.bottom as _;
+                item.rect = backend.getControlRect(hwndControl)?;
             }
         }
 
         if self.defaultEntry {
-            unsafe {
-                let lparam = self as *mut _ as *mut core::ffi::c_void;
-                Win::EnumChildWindows(parent, Some(InitDefaultControls), Win::LPARAM(lparam as isize));
-                //for (_, ci) in windows.clone().iter() {
-                //    println!("Initializeing resizing");
-                //    InitDefaultControls(ci.hwnd, Win::LPARAM(lparam as isize));
-                //}
+            for hwnd in children {
+                self.initDefaultControl(backend, hwnd);
             }
         }
 
-        let mut sz1 = Win::SIZE { ..Default::default() };
         if flags & ANIF_SIZEGRIP > 0 {
             let dw1 = self.defaultFlags;
             self.defaultFlags = ANF_RIGHT | ANF_BOTTOM;
-            unsafe {
-                sz1.cx = Win::GetSystemMetrics(Win::SM_CXVSCROLL);
-                sz1.cy = Win::GetSystemMetrics(Win::SM_CYHSCROLL);
-            }
-            self.sizeGrip = Win::CreateWindowEx(
-                Default::default(),
-                Win::WS_CHILD | Win::WS_VISIBLE | (Win::SBS_SIZEGRIP as Win::WINDOW_STYLE),
-                "ScrollBar", Some(parent), 0, "",
-                self.client.right - sz1.cx, self.client.bottom - sz1.cy, sz1.cx, sz1.cy, &0
-            );
-            unsafe {
-                Win::SetWindowPos(
-                    self.sizeGrip, Win::HWND_TOP,
-                    0,0,0,0,
-                    Win::SWP_NOMOVE | Win::SWP_NOACTIVATE | Win::SWP_NOSIZE
-                );
-            }
-            let lparam = self as *mut _ as *mut core::ffi::c_void;
-            InitDefaultControls(self.sizeGrip, Win::LPARAM(lparam as isize));
+            let sz1 = backend.sizeGripSize();
+            self.sizeGrip = backend.createControl(Some(parent), &ControlDesc {
+                renderer: "size-grip", className: "ScrollBar", title: "",
+                controlId: 0, anchor: 0,
+                posX: self.client.right - sz1.cx, posY: self.client.bottom - sz1.cy, width: sz1.cx, height: sz1.cy,
+            });
+            self.initDefaultControl(backend, self.sizeGrip);
             self.defaultFlags = dw1;
         }
 
         if flags & ANIF_CALCSIZE > 0 {
-            let mut max = Win::RECT { ..Default::default() };
+            let mut max = Rect { ..Default::default() };
             for item in self.controls.iter() {
-                if_then!(item.rect.right > max.right as _, max.right = item.rect.right as _);
-                if_then!(item.rect.bottom > max.bottom as _, max.bottom = item.rect.bottom as _);
+                if_then!(item.rect.right > max.right, max.right = item.rect.right);
+                if_then!(item.rect.bottom > max.bottom, max.bottom = item.rect.bottom);
             }
             self.prev.right = self.prev.left + max.right;
             self.prev.bottom = self.prev.top + max.bottom;
-            unsafe {
-                let style = Win::GetWindowLong(parent, Win::GWL_STYLE);
-                let menu = Win::GetMenu(parent);
-                Win::AdjustWindowRect(&mut self.prev, style as Win::WINDOW_STYLE, menu != Win::HMENU(0));
-            }
+            self.prev = backend.adjustWindowRect(parent, self.prev);
             self.client.right = self.client.left + (self.prev.right - self.prev.left);
             self.client.bottom = self.client.top + (self.prev.bottom - self.prev.top);
         }
 
+        let client = self.clientSize();
         for item in self.controls.iter_mut() {
-            let mut client = Win::SIZE { ..Default::default() };
-            client.cx = self.client.right - self.client.left;
-            client.cy = self.client.bottom - self.client.top;
-
             if item.flags == ANF_AUTOMATIC {
-                item.flags = 0;
-
-                if_then!(item.rect.top < (client.cy / 2) as _, item.flags |= ANF_TOP);
-                if_then!(item.rect.bottom >= (client.cy / 2) as _, item.flags |= ANF_BOTTOM);
-
-                if_then!(item.rect.left < (client.cx / 2) as _, item.flags |= ANF_LEFT);
-                if_then!(item.rect.right >= (client.cx / 2) as _, item.flags |= ANF_RIGHT);
+                item.flags = automaticAnchors(&item.rect, client);
             }
         }
 
+        self.base = client;
+        self.dpi = backend.getDpi(parent);
         self.isInitialized = true;
 
         Some(())
     }
 
-    pub fn handleAnchors(&mut self, parentRect: Option<Win::RECT>) -> Option<()> {
+    pub fn handleAnchors(&mut self, backend: &dyn Backend, parentRect: Option<Rect>) -> Option<()> {
         if !self.isInitialized() {
             return Some(());
         }
 
-        parentRect
-            .or_else(||Win::GetWindowRect(self.parent))
-            .map(|pr|self.preProcess(&pr));
+        if let Some(pr) = parentRect.or_else(||backend.getWindowRect(self.parent)) {
+            self.preProcess(&pr);
+        }
 
-        if self.sizeGrip != Win::HWND(0) {
-            if let Some(wp) = Win::GetWindowPlacement(self.parent) {
-                if wp.showCmd == Win::SW_MAXIMIZE && Win::IsWindowVisible(self.sizeGrip) {
-                    Win::ShowWindow(self.sizeGrip, Win::SW_HIDE);
-                } else if Win::IsWindowVisible(self.sizeGrip) {
-                    Win::ShowWindow(self.sizeGrip, Win::SW_SHOW);
-                }
+        if self.sizeGrip != Handle(0) {
+            if backend.isMaximized(self.parent) && backend.isVisible(self.sizeGrip) {
+                backend.setVisible(self.sizeGrip, false);
+            } else if backend.isVisible(self.sizeGrip) {
+                backend.setVisible(self.sizeGrip, true);
             }
         }
 
-        let mut szControl = FSIZE { cx: 0f32, cy: 0f32 };
-        for item in self.controls.iter_mut() {
-            let mut isChanged = false;
-            if item.hwnd == Win::HWND(0) {
-                continue;
+        let entries = self.controls.iter().map(|c|(c.rect, c.flags)).collect::<Vec<_>>();
+        let rects = solveAnchors(self.base, self.clientSize(), &entries);
+        for (item, rect) in self.controls.iter().zip(rects) {
+            let rect = limitRect(rect, item.flags, &item.limits);
+            if item.hwnd != Handle(0) && backend.getControlRect(item.hwnd) != Some(rect) {
+                backend.moveControl(item.hwnd, &rect);
             }
+        }
 
-            szControl.cx = item.rect.right - item.rect.left;
-            szControl.cy = item.rect.bottom - item.rect.top;
-
-            if (item.flags & ANF_DOCK_ALL) == ANF_DOCK_ALL {
-                item.rect.set(0f32, 0f32, self.client.right as _, self.client.bottom as _);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_TOP > 0 {
-                item.rect.set(0f32, 0f32, self.client.right as _, szControl.cy);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_BOTTOM > 0 {
-                item.rect.set(0f32, self.client.bottom as f32 - szControl.cy, self.client.right as _, self.client.bottom as _);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_LEFT > 0 {
-                item.rect.set(0f32, 0f32, szControl.cx, self.client.bottom as _);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_RIGHT > 0 {
-                item.rect.set(self.client.right as f32 - szControl.cx, 0f32, self.client.right as _, self.client.bottom as _);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_LEFT_EX > 0 {
-                item.rect.set(0f32, item.rect.top, szControl.cx, item.rect.bottom);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_RIGHT_EX > 0 {
-                item.rect.set(item.rect.left, item.rect.top, self.client.right as _, item.rect.bottom as _);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_TOP_EX > 0 {
-                item.rect.set(item.rect.left, 0f32, item.rect.right, item.rect.bottom);
-                isChanged = true;
-            } else if item.flags & ANF_DOCK_BOTTOM_EX > 0 {
-                item.rect.set(item.rect.left, item.rect.top, item.rect.right, self.client.bottom as _);
-                isChanged = true;
-            }
+        self.postProcess();
 
-            if (self.sizedBorders & ANF_LEFTRIGHT) > 0 && self.delta.cx != 0 && !isChanged {
-                match item.flags & ANF_LEFTRIGHT {
-                    ANF_LEFT => {},
-                    ANF_RIGHT => {
-                        item.rect.left += self.delta.cx as f32;
-                        item.rect.right = item.rect.left + szControl.cx;
-                        isChanged = true;
-                    },
-                    ANF_LEFTRIGHT => {
-                        item.rect.right += self.delta.cx as f32;
-                        isChanged = true;
-                    },
-                    _ => {
-                        item.rect.left += (self.delta.cx / 2) as f32;
-                        item.rect.right = item.rect.left + szControl.cx;
-                        isChanged = true;
-                    },
-                };
-            }
+        Some(())
+    }
 
-            if self.sizedBorders & ANF_TOPBOTTOM > 0 && self.delta.cy != 0 {
-                match item.flags & ANF_TOPBOTTOM {
-                    ANF_TOP => {},
-                    ANF_BOTTOM => {
-                        item.rect.top += self.delta.cy as f32;
-                        item.rect.bottom = item.rect.top + szControl.cy;
-                        isChanged = true;
-                    },
-                    ANF_TOPBOTTOM => {
-                        item.rect.bottom += self.delta.cy as f32;
-                        isChanged = true;
-                    },
-                    _ => {
-                        item.rect.top += (self.delta.cy / 2) as f32;
-                        item.rect.bottom = item.rect.top + szControl.cy;
-                        isChanged = true;
-                    },
-                };
-            }
+    fn initDefaultControl(&mut self, backend: &dyn Backend, hwnd: Handle) {
+        if backend.getParent(hwnd) != self.parent {
+            return;
+        }
 
-            if isChanged {
-                szControl.cx = item.rect.right - item.rect.left;
-                szControl.cy = item.rect.bottom - item.rect.top;
-                let posInfo = Win::BeginDeferWindowPos(self.count);
-                Win::DeferWindowPos(
-                    posInfo, item.hwnd, Win::HWND(0),
-                    item.rect.left as _, item.rect.top as _, szControl.cx as _, szControl.cy as _,
-                    Win::SWP_NOZORDER | Win::SWP_NOOWNERZORDER | Win::SWP_SHOWWINDOW
-                );
-
-                Win::EndDeferWindowPos(posInfo);
+        for item in self.controls.iter() {
+            if item.hwnd == hwnd {
+                return;
             }
         }
 
-        self.postProcess();
+        let mut count = self.count;
+        let defaultFlags = self.defaultFlags;
+        if let Some(entry) = self.controls.get_mut(count as usize) {
+            entry.hwnd = hwnd;
+            entry.controlId = backend.getControlId(hwnd);
+            entry.flags = defaultFlags;
 
-        Some(())
+            if let Some(rect) = backend.getControlRect(hwnd) {
+                entry.rect = rect;
+
+                count += 1;
+            }
+        }
+        self.count = count;
     }
 }
 
-extern "system" fn InitDefaultControls(hwnd: Win::HWND, lParam: Win::LPARAM) -> Win::BOOL {
-    let mut anchorMap: &mut AnchorMap = unsafe {
-        let Win::LPARAM(lParam) = lParam;
-        let closurePointer = lParam as *mut core::ffi::c_void;
-        &mut *(closurePointer as *mut _)
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_names_anchor_flags() {
+        assert_eq!(anchorToString(ANF_NONE), "ANF_NONE");
+        assert_eq!(anchorToString(ANF_TOP | ANF_LEFTRIGHT), "ANF_TOP|ANF_LEFT|ANF_RIGHT");
+        assert_eq!(anchorToString(ANF_DOCK_ALL), "ANF_DOCK_TOP|ANF_DOCK_BOTTOM|ANF_DOCK_LEFT|ANF_DOCK_RIGHT");
+    }
+
+    fn place(backend: &HeadlessBackend, parent: Option<Handle>, controlId: i32, anchor: usize, rect: [i32; 4]) -> Handle {
+        let [posX, posY, width, height] = rect;
+        let hwnd = backend.createControl(parent, &ControlDesc {
+            renderer: "panel", className: "panel", title: "",
+            controlId, anchor, posX, posY, width, height,
+        });
+        if let Some(parent) = parent {
+            AnchorMap::of(parent).borrow_mut().addControl(backend, controlId, anchor, Some(hwnd));
+        }
+
+        hwnd
+    }
+
+    fn rect(backend: &HeadlessBackend, hwnd: Handle) -> [i32; 4] {
+        let r = backend.getControlRect(hwnd).unwrap();
+        [r.left, r.top, r.right - r.left, r.bottom - r.top]
+    }
+
+    #[test]
+    fn it_keeps_anchor_map_per_container() {
+        let backend = HeadlessBackend::new();
+        let first = place(&backend, None, 0, 0, [0, 0, 300, 200]);
+        let panel = place(&backend, Some(first), 1, ANF_TOP | ANF_LEFTRIGHT, [10, 10, 280, 100]);
+        let button = place(&backend, Some(panel), 2, ANF_TOP | ANF_RIGHT, [200, 10, 60, 20]);
+        let second = place(&backend, None, 0, 0, [400, 0, 300, 200]);
+        let other = place(&backend, Some(second), 1, ANF_TOP | ANF_RIGHT, [200, 10, 60, 20]);
+        for hwnd in [first, panel, second] {
+            AnchorMap::of(hwnd).borrow_mut().initialize(&backend, hwnd, 0);
+        }
+
+        backend.resize(first, 400, 200);
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+
+        assert_eq!(rect(&backend, panel), [10, 10, 380, 100]);
+        assert_eq!(rect(&backend, button), [300, 10, 60, 20]);
+        assert_eq!(rect(&backend, other), [200, 10, 60, 20]);
+
+        AnchorMap::release(&backend, panel);
+        assert!(AnchorMap::find(panel).is_none());
+        assert!(AnchorMap::find(first).unwrap().borrow().findWindow(panel).is_none());
+    }
+
+    #[test]
+    fn it_keeps_controls_apart_when_ids_repeat() {
+        let backend = HeadlessBackend::new();
+        let window = place(&backend, None, 0, 0, [0, 0, 300, 200]);
+        let panel = place(&backend, Some(window), 1, ANF_TOP | ANF_LEFTRIGHT, [10, 10, 280, 30]);
+        let check = place(&backend, Some(panel), 400, ANF_TOP | ANF_LEFT, [4, 1, 50, 24]);
+        let edit = place(&backend, Some(panel), 400, ANF_TOP | ANF_LEFTRIGHT, [55, 1, 150, 24]);
+        let button = place(&backend, Some(panel), 400, ANF_TOP | ANF_RIGHT, [210, 1, 60, 22]);
+        for hwnd in [window, panel] {
+            AnchorMap::of(hwnd).borrow_mut().initialize(&backend, hwnd, 0);
+        }
+
+        backend.resize(window, 400, 200);
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+
+        assert_eq!(rect(&backend, check), [4, 1, 50, 24]);
+        assert_eq!(rect(&backend, edit), [55, 1, 250, 24]);
+        assert_eq!(rect(&backend, button), [310, 1, 60, 22]);
+    }
 
-    let parentHwnd = Win::GetParent(hwnd);
-    if parentHwnd != anchorMap.parent {
-        return Win::BOOL::from(true);
+    fn solve(flags: usize, current: Size) -> Rect {
+        let rect = Rect { left: 20, top: 10, right: 60, bottom: 30 };
+        solveAnchors(Size { cx: 200, cy: 100 }, current, &[(rect, flags)])[0]
     }
 
-    for item in anchorMap.controls.iter() {
-        if item.hwnd == hwnd {
-            return Win::BOOL::from(true);
+    fn r(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+        Rect { left, top, right, bottom }
+    }
+
+    #[test]
+    fn it_solves_every_anchor_flag() {
+        let grown = Size { cx: 300, cy: 150 };
+        let cases = [
+            (ANF_NONE, r(70, 35, 110, 55)),
+            (ANF_TOP, r(70, 10, 110, 30)),
+            (ANF_BOTTOM, r(70, 60, 110, 80)),
+            (ANF_LEFT, r(20, 35, 60, 55)),
+            (ANF_RIGHT, r(120, 35, 160, 55)),
+            (ANF_TOPLEFT, r(20, 10, 60, 30)),
+            (ANF_TOPRIGHT, r(120, 10, 160, 30)),
+            (ANF_BOTTOMLEFT, r(20, 60, 60, 80)),
+            (ANF_BOTTOMRIGHT, r(120, 60, 160, 80)),
+            (ANF_TOPBOTTOM, r(70, 10, 110, 80)),
+            (ANF_LEFTRIGHT, r(20, 35, 160, 55)),
+            (ANF_TOPBOTTOM | ANF_LEFTRIGHT, r(20, 10, 160, 80)),
+            (ANF_DOCK_TOP, r(0, 0, 300, 20)),
+            (ANF_DOCK_BOTTOM, r(0, 130, 300, 150)),
+            (ANF_DOCK_LEFT, r(0, 0, 40, 150)),
+            (ANF_DOCK_RIGHT, r(260, 0, 300, 150)),
+            (ANF_DOCK_ALL, r(0, 0, 300, 150)),
+            (ANF_DOCK_TOP | ANF_BOTTOMRIGHT, r(0, 0, 300, 20)),
+            (ANF_DOCK_LEFT_EX | ANF_TOP, r(0, 10, 40, 30)),
+            (ANF_DOCK_RIGHT_EX | ANF_TOPLEFT, r(20, 10, 300, 30)),
+            (ANF_DOCK_TOP_EX | ANF_LEFT, r(20, 0, 60, 20)),
+            (ANF_DOCK_BOTTOM_EX | ANF_TOPLEFT, r(20, 10, 60, 150)),
+            (ANF_DOCK_LEFT_EX | ANF_DOCK_RIGHT_EX | ANF_BOTTOM, r(0, 60, 300, 80)),
+            (ANF_DOCK_TOP_EX | ANF_DOCK_BOTTOM_EX | ANF_RIGHT, r(120, 0, 160, 150)),
+            (ANF_ERASE | ANF_TOPLEFT, r(20, 10, 60, 30)),
+        ];
+
+        for (flags, expected) in cases {
+            assert_eq!(solve(flags, grown), expected, "{}", anchorToString(flags));
         }
     }
 
-    let mut count = anchorMap.count;
-    let parent = anchorMap.parent;
-    let defaultFlags = anchorMap.defaultFlags;
-    if let Some(mut entry) = anchorMap.controls.get_mut(count as usize) {
-        entry.hwnd = hwnd;
-        unsafe {
-            entry.controlId = Win::GetDlgCtrlID(hwnd);
+    #[test]
+    fn it_keeps_rects_when_parent_is_not_resized() {
+        let same = Size { cx: 200, cy: 100 };
+        for flags in [ANF_NONE, ANF_TOPLEFT, ANF_BOTTOMRIGHT, ANF_TOPBOTTOM | ANF_LEFTRIGHT, ANF_RIGHT] {
+            assert_eq!(solve(flags, same), r(20, 10, 60, 30), "{}", anchorToString(flags));
         }
-        entry.flags = defaultFlags;
-        let rect = Win::GetWindowRect(hwnd)
-            .and_then(|rect|AnchorMap::ScreenToClient(parent, &rect))
-            ;
+        assert_eq!(solve(ANF_DOCK_TOP, same), r(0, 0, 200, 20));
+        assert_eq!(solve(ANF_DOCK_RIGHT_EX | ANF_TOPLEFT, same), r(20, 10, 200, 30));
+    }
+
+    #[test]
+    fn it_never_turns_rects_inside_out() {
+        let shrunk = Size { cx: 100, cy: 50 };
+        assert_eq!(solve(ANF_TOPBOTTOM | ANF_LEFTRIGHT, shrunk), r(20, 10, 20, 10));
+        assert_eq!(solve(ANF_BOTTOMRIGHT, shrunk), r(-80, -40, -40, -20));
+        assert_eq!(solve(ANF_DOCK_RIGHT_EX | ANF_TOPLEFT, Size { cx: 10, cy: 50 }), r(20, 10, 20, 30));
+    }
 
-        if let Some(rect) = rect {
-            entry.rect.top = rect.top as _;
-            entry.rect.left = rect.left as _;
-            entry.rect.right = rect.right as _;
-            entry.rect.bottom = rect.bottom as _;
+    #[test]
+    fn it_picks_automatic_anchors_by_position() {
+        let client = Size { cx: 200, cy: 100 };
+        assert_eq!(automaticAnchors(&r(10, 10, 50, 30), client), ANF_TOPLEFT);
+        assert_eq!(automaticAnchors(&r(150, 60, 190, 90), client), ANF_BOTTOMRIGHT);
+        assert_eq!(automaticAnchors(&r(10, 10, 190, 90), client), ANF_TOPBOTTOM | ANF_LEFTRIGHT);
+    }
+
+    #[test]
+    fn it_does_not_drift_on_small_resizes() {
+        let backend = HeadlessBackend::new();
+        let window = place(&backend, None, 0, 0, [0, 0, 200, 100]);
+        let centered = place(&backend, Some(window), 1, ANF_NONE, [80, 40, 40, 20]);
+        AnchorMap::of(window).borrow_mut().initialize(&backend, window, 0);
 
-            count += 1;
+        for width in 201..=210 {
+            backend.resize(window, width, 100);
+            backend.dispatchMessage(&backend.peekMessage().unwrap());
         }
+
+        assert_eq!(rect(&backend, centered), [85, 40, 40, 20]);
     }
-    anchorMap.count = count;
 
-    return Win::BOOL::from(true);
+    #[test]
+    fn it_keeps_anchored_side_when_limiting_rects() {
+        let limits = SizeLimits { minWidth: 50, maxHeight: 10, ..Default::default() };
+        let rect = r(20, 10, 60, 30);
+
+        assert_eq!(limitRect(rect, ANF_TOPLEFT, &limits), r(20, 10, 70, 20));
+        assert_eq!(limitRect(rect, ANF_BOTTOMRIGHT, &limits), r(10, 20, 60, 30));
+        assert_eq!(limitRect(rect, ANF_TOPBOTTOM | ANF_LEFTRIGHT, &limits), r(20, 10, 70, 20));
+    }
+
+    #[test]
+    fn it_computes_minimum_client_size() {
+        let base = Size { cx: 200, cy: 100 };
+        let none = SizeLimits::default();
+        let min = |cx, cy| SizeLimits { minWidth: cx, minHeight: cy, ..Default::default() };
+        let entries = [
+            (r(10, 10, 50, 30), ANF_TOPLEFT, none),
+            (r(150, 10, 190, 30), ANF_TOPRIGHT, none),
+            (r(10, 40, 190, 90), ANF_TOPBOTTOM | ANF_LEFTRIGHT, min(100, 20)),
+            (r(0, 0, 200, 5), ANF_DOCK_TOP, none),
+        ];
+
+        // the stretched control can give 80 px of its width and 30 px of its height
+        assert_eq!(minimumClientSize(base, &entries), Size { cx: 120, cy: 70 });
+        assert_eq!(minimumClientSize(base, &entries[..2]), Size { cx: 50, cy: 30 });
+        assert_eq!(minimumClientSize(base, &[(r(0, 0, 200, 20), ANF_DOCK_TOP, min(80, 0))]), Size { cx: 80, cy: 20 });
+    }
+
+    #[test]
+    fn it_stops_resize_at_minimum_of_children() {
+        let backend = HeadlessBackend::new();
+        let window = place(&backend, None, 0, 0, [0, 0, 300, 200]);
+        let panel = place(&backend, Some(window), 1, ANF_TOP | ANF_LEFTRIGHT, [10, 10, 280, 100]);
+        let edit = place(&backend, Some(panel), 2, ANF_TOP | ANF_LEFTRIGHT, [10, 10, 200, 20]);
+        AnchorMap::of(panel).borrow_mut().limitControl(edit, SizeLimits { minWidth: 150, ..Default::default() });
+        AnchorMap::of(window).borrow_mut().setLimits(SizeLimits { maxHeight: 250, ..Default::default() });
+        for hwnd in [window, panel] {
+            AnchorMap::of(hwnd).borrow_mut().initialize(&backend, hwnd, 0);
+        }
+
+        backend.resize(window, 100, 400);
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+
+        // the edit keeps 150 of its 200 px, the panel its 80 px of margins and the window 20 px more
+        assert_eq!(rect(&backend, window), [0, 0, 250, 250]);
+        assert_eq!(rect(&backend, edit), [10, 10, 150, 20]);
+    }
+
+    #[test]
+    fn it_parses_anchor_flags() {
+        assert_eq!(anchorFromString("ANF_TOP | ANF_LEFTRIGHT"), Some(ANF_TOP | ANF_LEFT | ANF_RIGHT));
+        assert_eq!(anchorFromString("ANF_DOCK_ALL"), Some(ANF_DOCK_ALL));
+        assert_eq!(anchorFromString("16|64"), Some(ANF_TOP | ANF_LEFT));
+        assert_eq!(anchorFromString("ANF_MIDDLE"), None);
+    }
 }
@@ -0,0 +1,332 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::muffui::*;
+
+/// logical units a line of the scroll bars scrolls
+pub const SCROLL_LINE: i32 = 20;
+/// lines a notch of the mouse wheel scrolls
+const WHEEL_LINES: i32 = 3;
+
+/// what part of its content a scroll view shows, in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScrollState {
+    pub offset: Point,
+    pub content: Size,
+    pub viewport: Size,
+    /// pixels a line of the scroll bars scrolls
+    pub line: i32,
+    /// ScrollX and ScrollY of the last render, the view moves the offset only when it changes them
+    pub declared: Option<Point>,
+}
+
+impl ScrollState {
+    fn registry() -> &'static RefCell<HashMap<Handle, ScrollState>> {
+        thread_local! {
+            static CONF: &'static RefCell<HashMap<Handle, ScrollState>> = Box::leak(Box::new(RefCell::new(HashMap::new())));
+        }
+
+        CONF.with(|conf|*conf)
+    }
+
+    /// state of the scroll view `hwnd`, None for other controls
+    pub fn find(hwnd: Handle) -> Option<ScrollState> {
+        Self::registry().borrow().get(&hwnd).copied()
+    }
+
+    /// state of the scroll view `hwnd` is in, `hwnd` included
+    pub fn enclosing(backend: &dyn Backend, hwnd: Handle) -> Option<(Handle, ScrollState)> {
+        let mut current = hwnd;
+        while current != Handle(0) {
+            if let Some(state) = Self::find(current) {
+                return Some((current, state));
+            }
+            current = backend.getParent(current);
+        }
+
+        None
+    }
+
+    pub fn release(hwnd: Handle) {
+        Self::registry().borrow_mut().remove(&hwnd);
+    }
+
+    /// takes the content and viewport of a render, the offset follows ScrollX and ScrollY when the view changes them
+    pub fn update(backend: &dyn Backend, hwnd: Handle, content: Size, viewport: Size, line: i32, declared: Option<Point>) -> Self {
+        let prev = Self::find(hwnd);
+        let mut state = Self { content, viewport, line, declared, ..prev.unwrap_or_default() };
+        if let Some(offset) = declared.filter(|_|declared != prev.and_then(|p|p.declared)) {
+            state.offset = offset;
+        }
+        let state = state.scrollTo(state.offset);
+
+        Self::registry().borrow_mut().insert(hwnd, state);
+        if prev.map(|p|(p.offset, p.content, p.viewport)) != Some((state.offset, content, viewport)) {
+            backend.setScrollInfo(hwnd, content, viewport, state.offset);
+        }
+
+        state
+    }
+
+    /// handles a scroll bar or mouse wheel message of the scroll view `msg.hwnd`,
+    /// moves its children right away so they don't wait for the next render
+    pub fn scroll(backend: &dyn Backend, msg: &Message) -> Option<Self> {
+        let prev = Self::find(msg.hwnd)?;
+        let state = prev.scrollBy(msg.message, msg.wParam);
+        if state.offset == prev.offset {
+            return Some(state);
+        }
+
+        let (dx, dy) = (prev.offset.x - state.offset.x, prev.offset.y - state.offset.y);
+        for child in backend.getChildren(msg.hwnd) {
+            if let Some(rect) = backend.getControlRect(child) {
+                backend.moveControl(child, &offsetRect(&rect, Point { x: dx, y: dy }));
+            }
+        }
+        Self::registry().borrow_mut().insert(msg.hwnd, state);
+        backend.setScrollInfo(msg.hwnd, state.content, state.viewport, state.offset);
+
+        Some(state)
+    }
+
+    /// largest offset that still fills the viewport
+    pub fn maxOffset(&self) -> Point {
+        Point {
+            x: (self.content.cx - self.viewport.cx).max(0),
+            y: (self.content.cy - self.viewport.cy).max(0),
+        }
+    }
+
+    pub fn scrollTo(self, offset: Point) -> Self {
+        let max = self.maxOffset();
+        let offset = Point { x: offset.x.clamp(0, max.x), y: offset.y.clamp(0, max.y) };
+
+        Self { offset, ..self }
+    }
+
+    /// offset after a WM_VSCROLL, WM_HSCROLL or WM_MOUSEWHEEL with `wParam`
+    pub fn scrollBy(self, message: u32, wParam: usize) -> Self {
+        let max = self.maxOffset();
+        let page = |viewport: i32| (viewport - self.line).max(self.line);
+        let step = |pos: i32, max: i32, page: i32| match LOWORD(wParam) as u32 {
+            SB_LINEUP => pos - self.line,
+            SB_LINEDOWN => pos + self.line,
+            SB_PAGEUP => pos - page,
+            SB_PAGEDOWN => pos + page,
+            SB_THUMBPOSITION | SB_THUMBTRACK => HIWORD(wParam) as i32,
+            SB_TOP => 0,
+            SB_BOTTOM => max,
+            _ => pos,
+        };
+        let offset = self.offset;
+        let offset = match message {
+            WM_VSCROLL => Point { y: step(offset.y, max.y, page(self.viewport.cy)), ..offset },
+            WM_HSCROLL => Point { x: step(offset.x, max.x, page(self.viewport.cx)), ..offset },
+            WM_MOUSEWHEEL => {
+                // wheel up is positive and shows what is above
+                let delta = HIWORD(wParam) as u16 as i16 as i32;
+                Point { y: offset.y - delta * WHEEL_LINES * self.line / WHEEL_DELTA, ..offset }
+            },
+            _ => offset,
+        };
+
+        self.scrollTo(offset)
+    }
+}
+
+/// offset ScrollX and ScrollY of `props` ask for, None when the view leaves both out
+pub fn declaredOffset(props: &Vec<SharedProps>) -> Option<Point> {
+    match (props.prop(&SP::ScrollX(0)), props.prop(&SP::ScrollY(0))) {
+        (None, None) => None,
+        (x, y) => Some(Point {
+            x: match x { Some(SharedProps::ScrollX(x)) => *x, _ => 0 },
+            y: match y { Some(SharedProps::ScrollY(y)) => *y, _ => 0 },
+        }),
+    }
+}
+
+pub fn offsetRect(rect: &Rect, by: Point) -> Rect {
+    Rect { left: rect.left + by.x, top: rect.top + by.y, right: rect.right + by.x, bottom: rect.bottom + by.y }
+}
+
+/// rects of the children of a scroll view with `viewport` client area, stacked like `style` does it but never shrunk
+/// to the viewport, in content coordinates, and the size of the content they make with the padding
+pub fn layoutScroll(style: &FlexStyle, viewport: Size, items: &[FlexItem]) -> (Vec<Rect>, Size) {
+    let style = FlexStyle { wrap: false, ..*style };
+    let isRow = style.direction == Direction::Row;
+    let size = |item: &FlexItem| item.limits.clamp(Size { cx: item.width, cy: item.height });
+    let gaps = style.spacing * (items.len() as i32 - 1).max(0);
+    let padding = 2 * style.padding;
+
+    // the stack gets all the room its children ask for, the viewport at least
+    let needed = items.iter().map(size).fold(Size { cx: 0, cy: 0 }, |res, s|match isRow {
+        true => Size { cx: res.cx + s.cx, cy: res.cy.max(s.cy) },
+        _ => Size { cx: res.cx.max(s.cx), cy: res.cy + s.cy },
+    });
+    let needed = match isRow {
+        true => Size { cx: needed.cx + gaps + padding, cy: needed.cy + padding },
+        _ => Size { cx: needed.cx + padding, cy: needed.cy + gaps + padding },
+    };
+    let stack = Size { cx: needed.cx.max(viewport.cx), cy: needed.cy.max(viewport.cy) };
+
+    let rects = layoutFlex(&style, stack, items);
+    let content = rects.iter().fold(Size { cx: 0, cy: 0 }, |res, r|Size {
+        cx: res.cx.max(r.right + style.padding),
+        cy: res.cy.max(r.bottom + style.padding),
+    });
+
+    (rects, content)
+}
+
+/// container that stacks its children like `Flex` in content that may be larger than itself and scrolls it,
+/// ScrollX and ScrollY move the offset, DidScroll reports where the user has scrolled to
+#[derive(Clone)]
+pub struct ScrollView {
+    pub children: Option<Rc<dyn Renderable>>,
+    pub props: Vec<SharedProps>,
+}
+
+impl Renderable for ScrollView {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        Some(self.children.as_ref()?.clone())
+    }
+
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut context = context.render(parent, index, self, msg);
+        let children = match &self.children {
+            Some(children) => children,
+            _ => return context,
+        };
+
+        let childIndex = format!("{}:1", index);
+        if let Some(hwnd) = context.items.get(index).map(|ci|ci.hwnd).filter(|_|msg.is_none()) {
+            let items = children.layoutItems(&childIndex);
+            let dpi = context.dpi(index);
+            let props = scaleProps(&self.props, dpi);
+            let viewport = context.clientSize(index);
+            let (rects, content) = layoutScroll(&FlexStyle::from(&props), viewport, &items.iter()
+                .map(|(_, props)|FlexItem::from(&measure(&*context.backend, props, dpi)))
+                .collect::<Vec<_>>());
+
+            let state = ScrollState::update(&*context.backend, hwnd, content, viewport, scale(SCROLL_LINE, dpi), declaredOffset(&props));
+            let by = Point { x: -state.offset.x, y: -state.offset.y };
+
+            context.place(&childIndex, items.into_iter().map(|(path, _)|path).zip(rects.iter().map(|r|offsetRect(r, by))));
+        }
+
+        children.render(context, index, &childIndex, msg)
+    }
+
+    fn toViewState(&self) -> Vec<SharedProps> {
+        self.props.clone()
+    }
+}
+
+impl ScrollView {
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
+        let defaultProps = vec![
+            SP::ClassName("panel#0x8002"),
+            SP::Renderer("scroll-view"),
+            SP::Direction(Direction::Column),
+        ];
+        Self {
+            children: None,
+            props: defaultProps.merge(props.into()),
+        }
+    }
+
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
+        Self {
+            children: Some(Rc::new(children().intoChildren())),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(width: i32, height: i32) -> FlexItem {
+        FlexItem { width, height, ..Default::default() }
+    }
+
+    #[test]
+    fn it_measures_content_larger_than_viewport() {
+        let style = FlexStyle { direction: Direction::Column, spacing: 2, padding: 2, align: Align::Stretch, ..Default::default() };
+        let (rects, content) = layoutScroll(&style, Size { cx: 100, cy: 50 }, &[item(0, 30), item(0, 30), item(0, 30)]);
+
+        assert_eq!(rects.iter().map(|r|[r.left, r.top, r.right - r.left, r.bottom - r.top]).collect::<Vec<_>>(),
+            vec![[2, 2, 96, 30], [2, 34, 96, 30], [2, 66, 96, 30]]);
+        assert_eq!(content, Size { cx: 100, cy: 98 });
+
+        // short content keeps the viewport and lets children grow into it
+        let (rects, content) = layoutScroll(&style, Size { cx: 100, cy: 50 }, &[FlexItem { grow: 1, ..item(0, 10) }]);
+        assert_eq!((rects[0].bottom, content), (48, Size { cx: 100, cy: 50 }));
+    }
+
+    #[test]
+    fn it_scrolls_within_content() {
+        let state = ScrollState { content: Size { cx: 100, cy: 300 }, viewport: Size { cx: 100, cy: 100 }, line: 20, ..Default::default() };
+        let scroll = |state: ScrollState, message, code: u32, pos: usize| state.scrollBy(message, pos << 16 | code as usize).offset;
+
+        assert_eq!(scroll(state, WM_VSCROLL, SB_LINEDOWN, 0), Point { x: 0, y: 20 });
+        assert_eq!(scroll(state, WM_VSCROLL, SB_PAGEDOWN, 0), Point { x: 0, y: 80 });
+        assert_eq!(scroll(state, WM_VSCROLL, SB_BOTTOM, 0), Point { x: 0, y: 200 });
+        assert_eq!(scroll(state, WM_VSCROLL, SB_THUMBTRACK, 150), Point { x: 0, y: 150 });
+        assert_eq!(scroll(state, WM_VSCROLL, SB_LINEUP, 0), Point { x: 0, y: 0 });
+        // nothing to scroll across
+        assert_eq!(scroll(state, WM_HSCROLL, SB_LINEDOWN, 0), Point { x: 0, y: 0 });
+        // a notch down is 3 lines
+        assert_eq!(state.scrollBy(WM_MOUSEWHEEL, ((-WHEEL_DELTA) as u16 as usize) << 16).offset, Point { x: 0, y: 60 });
+        assert_eq!(state.scrollTo(Point { x: 0, y: 999 }).offset, Point { x: 0, y: 200 });
+    }
+
+    #[test]
+    fn it_scrolls_rendered_rows() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let scrolled = Rc::new(RefCell::new(vec![]));
+        let view = |rows: Vec<i32>, scrollY: Option<i32>| {
+            let scrolled = scrolled.clone();
+            let props = vec![SP::Width(100), SP::Height(100), SP::Spacing(5), SP::AlignItems(Align::Stretch),
+                SP::DidScroll(move|event: ScrollEvent|scrolled.borrow_mut().push(event.y))];
+            ScrollView::new(props.into_iter().chain(scrollY.map(SP::ScrollY)).collect::<Vec<_>>()).content(move||
+                ForEach::keyed(rows.clone(), |i|*i, |i, _|Button::new([SP::ControlId(i), SP::Title("row"), SP::Height(20)]))
+            )
+        };
+        let top = |context: &UIContext, path: &str| backend.getControlRect(context.items[path].hwnd).unwrap().top;
+
+        let context = view((1..=8).collect(), None).render(UIContext::create(backend.clone()), "/", "0", None);
+        let hwnd = context.items["0"].hwnd;
+        assert_eq!(ScrollState::find(hwnd).unwrap().content, Size { cx: 100, cy: 195 });
+        assert_eq!(backend.node(hwnd).unwrap().scroll, (Size { cx: 100, cy: 195 }, Point { x: 0, y: 0 }));
+
+        // the wheel over a row scrolls the view the row is in
+        backend.wheel(context.items["0:1_1[3]"].hwnd, -WHEEL_DELTA);
+        let msg = backend.peekMessage().unwrap();
+        backend.dispatchMessage(&msg);
+        let context = view((1..=8).collect(), None).render(context, "/", "0", Some(msg));
+        let mut hub = EventHub::new();
+        hub.enqueueEvent(&*backend, msg);
+        hub.putListeners(&context);
+        hub.dispatchEvents(&*backend);
+        assert_eq!(*scrolled.borrow(), vec![60]);
+        assert_eq!(top(&context, "0:1_1[1]"), -60);
+        assert_eq!(top(&context, "0:1_1[8]"), 115);
+
+        // a render keeps the offset, less content pulls it back
+        let context = view((1..=8).collect(), None).render(context, "/", "0", None);
+        assert_eq!(top(&context, "0:1_1[2]"), -35);
+        let context = context.update(&view((1..=6).collect(), None));
+        assert_eq!(ScrollState::find(hwnd).unwrap().offset, Point { x: 0, y: 45 });
+        assert_eq!(top(&context, "0:1_1[1]"), -45);
+
+        // the view moves the offset by changing ScrollY
+        let context = context.update(&view((1..=6).collect(), Some(10)));
+        assert_eq!(top(&context, "0:1_1[1]"), -10);
+        let context = context.update(&view((1..=6).collect(), Some(10)));
+        assert_eq!(top(&context, "0:1_1[1]"), -10);
+    }
+}
@@ -1,34 +1,22 @@
 #![allow(non_snake_case)]
 
 use std::rc::Rc;
-use std::sync::Mutex;
-use std::sync::Arc;
-use crate::Win;
 use crate::muffui::*;
 use crate::muffui::SharedProps::*;
 
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Select {
     pub children: Option<Rc<dyn Renderable>>,
     pub props: Vec<SharedProps>,
 }
 
-impl Default for Select {
-    fn default() -> Self {
-        Self {
-            children: Default::default(),
-            props: vec![],
-        }
-    }
-}
-
 impl Renderable for Select {
     fn childs(&self) -> Option<Rc<dyn Renderable>> {
         Some(self.children.as_ref()?.clone())
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let context = context.render(parent, index, self, msg);
         match &self.children {
             Some(children) => children.render(context, index, &format!("{}:1", index), msg),
@@ -37,7 +25,7 @@ impl Renderable for Select {
     }
 
     fn toViewState(&self) -> Vec<SharedProps> {
-        self.props.iter().map(|item|item.clone()).collect()
+        self.props.to_vec()
     }
 }
 
@@ -91,26 +79,21 @@ impl Select {
     #[allow(dead_code)]
     pub fn didChange<C: Into<Command<Vec<SharedProps>>>>(self, handler: C) -> Self {
         Self {
-            props: self.props.merge(SharedProps::DidChange(Arc::new(Mutex::new(handler.into())))),
+            props: self.props.merge(SP::DidChange(handler)),
+            ..self
+        }
+    }
+    #[allow(dead_code)]
+    pub fn onChange<C: Into<Command<ChangeEvent>>>(self, handler: C) -> Self {
+        Self {
+            props: self.props.merge(SP::OnChange(handler)),
             ..self
         }
     }
     #[allow(dead_code)]
-    pub fn content<A: 'static, B: 'static, C: 'static, D: 'static, E: 'static, F: 'static, G: 'static, T: 'static, FF>(self, mut children: FF) -> Self
-        where
-            A: Renderable,
-            B: Renderable,
-            C: Renderable,
-            D: Renderable,
-            E: Renderable,
-            F: Renderable,
-            G: Renderable,
-            T: Into<ContentArgs<A, B, C, D, E, F, G>>,
-            FF: FnMut() -> T
-    {
-        let args: ContentArgs<A, B, C, D, E, F, G> = children().into();
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
         Self {
-            children: Some(Rc::new(args)),
+            children: Some(Rc::new(children().intoChildren())),
             ..self
         }
     }
@@ -3,7 +3,7 @@ use std::fmt;
 use std::rc::Rc;
 use std::sync::Mutex;
 use std::sync::Arc;
-use crate::muffui::Command;
+use crate::muffui::{Action, Align, ChangeEvent, ClickEvent, Command, CreateEvent, DestroyEvent, Direction, Dock, FocusEvent, GridLength, Justify, KeyEvent, MouseEvent, ResizeEvent, ScrollEvent, SplitEvent};
 
 #[derive(Clone)]
 pub enum SharedProps {
@@ -15,18 +15,58 @@ pub enum SharedProps {
     Height(i32),
     PosX(i32),
     PosY(i32),
+    MinWidth(i32),
+    MaxWidth(i32),
+    MinHeight(i32),
+    MaxHeight(i32),
     FontFace(Rc<str>),
+    FontSize(i32),
     Anchor(usize),
     Selected(bool),
     SelectItems(Vec<String>),
     SelectedIndex(usize),
     ClientRect((usize, usize, usize, usize)),
+    Direction(Direction),
+    Spacing(i32),
+    Padding(i32),
+    AlignItems(Align),
+    JustifyContent(Justify),
+    Wrap(bool),
+    Grow(i32),
+    Shrink(i32),
+    Rows(Vec<GridLength>),
+    Columns(Vec<GridLength>),
+    Row(usize),
+    Column(usize),
+    RowSpan(usize),
+    ColumnSpan(usize),
+    HorizontalAlign(Align),
+    VerticalAlign(Align),
+    ScrollX(i32),
+    ScrollY(i32),
+    RowHeight(i32),
+    SplitRatio(f32),
+    SplitPosition(i32),
+    Dock(Dock),
 
-    DidCreate(Arc<Mutex<Command<Vec<SharedProps>>>>),
-    DidClick(Arc<Mutex<Command<Vec<SharedProps>>>>),
-    DidChange(Arc<Mutex<Command<Vec<SharedProps>>>>),
-    DidResize(Arc<Mutex<Command<Vec<SharedProps>>>>),
-    DidDestroy(Arc<Mutex<Command<Vec<SharedProps>>>>),
+    DidCreate(Arc<Mutex<Command<CreateEvent>>>),
+    DidClick(Arc<Mutex<Command<ClickEvent>>>),
+    DidChange(Arc<Mutex<Command<ChangeEvent>>>),
+    DidResize(Arc<Mutex<Command<ResizeEvent>>>),
+    DidDestroy(Arc<Mutex<Command<DestroyEvent>>>),
+    DidScroll(Arc<Mutex<Command<ScrollEvent>>>),
+    DidSplit(Arc<Mutex<Command<SplitEvent>>>),
+    DidFocus(Arc<Mutex<Command<FocusEvent>>>),
+    DidBlur(Arc<Mutex<Command<FocusEvent>>>),
+    DidKeyDown(Arc<Mutex<Command<KeyEvent>>>),
+    DidKeyUp(Arc<Mutex<Command<KeyEvent>>>),
+    DidMouseDown(Arc<Mutex<Command<MouseEvent>>>),
+    DidMouseUp(Arc<Mutex<Command<MouseEvent>>>),
+    DidMouseMove(Arc<Mutex<Command<MouseEvent>>>),
+    DidMouseEnter(Arc<Mutex<Command<MouseEvent>>>),
+    DidMouseLeave(Arc<Mutex<Command<MouseEvent>>>),
+    /// listeners called on the way down from the window to the target, before the ones of the target
+    Capture(Vec<SharedProps>),
 }
 
 impl fmt::Debug for SharedProps {
@@ -40,18 +80,57 @@ impl fmt::Debug for SharedProps {
             SharedProps::Height(t) => write!(f, "Height({})", t),
             SharedProps::PosX(t) => write!(f, "PosX({})", t),
             SharedProps::PosY(t) => write!(f, "PosY({})", t),
+            SharedProps::MinWidth(t) => write!(f, "MinWidth({})", t),
+            SharedProps::MaxWidth(t) => write!(f, "MaxWidth({})", t),
+            SharedProps::MinHeight(t) => write!(f, "MinHeight({})", t),
+            SharedProps::MaxHeight(t) => write!(f, "MaxHeight({})", t),
             SharedProps::FontFace(t) => write!(f, "FontFace({})", t),
+            SharedProps::FontSize(t) => write!(f, "FontSize({})", t),
             SharedProps::Anchor(t) => write!(f, "Anchor({})", t),
             SharedProps::Selected(t) => write!(f, "Selected({})", t),
             SharedProps::SelectItems(t) => write!(f, "SelectItems({:?})", t),
             SharedProps::SelectedIndex(t) => write!(f, "SelectedIndex({})", t),
             SharedProps::ClientRect((x,y,w,h)) => write!(f, "ClientRect({},{},{},{})",x,y,w,h),
+            SharedProps::Direction(t) => write!(f, "Direction({:?})", t),
+            SharedProps::Spacing(t) => write!(f, "Spacing({})", t),
+            SharedProps::Padding(t) => write!(f, "Padding({})", t),
+            SharedProps::AlignItems(t) => write!(f, "AlignItems({:?})", t),
+            SharedProps::JustifyContent(t) => write!(f, "JustifyContent({:?})", t),
+            SharedProps::Wrap(t) => write!(f, "Wrap({})", t),
+            SharedProps::Grow(t) => write!(f, "Grow({})", t),
+            SharedProps::Shrink(t) => write!(f, "Shrink({})", t),
+            SharedProps::Rows(t) => write!(f, "Rows({:?})", t),
+            SharedProps::Columns(t) => write!(f, "Columns({:?})", t),
+            SharedProps::Row(t) => write!(f, "Row({})", t),
+            SharedProps::Column(t) => write!(f, "Column({})", t),
+            SharedProps::RowSpan(t) => write!(f, "RowSpan({})", t),
+            SharedProps::ColumnSpan(t) => write!(f, "ColumnSpan({})", t),
+            SharedProps::HorizontalAlign(t) => write!(f, "HorizontalAlign({:?})", t),
+            SharedProps::VerticalAlign(t) => write!(f, "VerticalAlign({:?})", t),
+            SharedProps::ScrollX(t) => write!(f, "ScrollX({})", t),
+            SharedProps::ScrollY(t) => write!(f, "ScrollY({})", t),
+            SharedProps::RowHeight(t) => write!(f, "RowHeight({})", t),
+            SharedProps::SplitRatio(t) => write!(f, "SplitRatio({})", t),
+            SharedProps::SplitPosition(t) => write!(f, "SplitPosition({})", t),
+            SharedProps::Dock(t) => write!(f, "Dock({:?})", t),
 
             SharedProps::DidCreate(_) => write!(f, "fn:didCreate"),
             SharedProps::DidChange(_) => write!(f, "fn:didChange"),
             SharedProps::DidClick(_) => write!(f, "fn:didClick"),
             SharedProps::DidResize(_) => write!(f, "fn:didResize"),
             SharedProps::DidDestroy(_) => write!(f, "fn:didDestroy"),
+            SharedProps::DidScroll(_) => write!(f, "fn:didScroll"),
+            SharedProps::DidSplit(_) => write!(f, "fn:didSplit"),
+            SharedProps::DidFocus(_) => write!(f, "fn:didFocus"),
+            SharedProps::DidBlur(_) => write!(f, "fn:didBlur"),
+            SharedProps::DidKeyDown(_) => write!(f, "fn:didKeyDown"),
+            SharedProps::DidKeyUp(_) => write!(f, "fn:didKeyUp"),
+            SharedProps::DidMouseDown(_) => write!(f, "fn:didMouseDown"),
+            SharedProps::DidMouseUp(_) => write!(f, "fn:didMouseUp"),
+            SharedProps::DidMouseMove(_) => write!(f, "fn:didMouseMove"),
+            SharedProps::DidMouseEnter(_) => write!(f, "fn:didMouseEnter"),
+            SharedProps::DidMouseLeave(_) => write!(f, "fn:didMouseLeave"),
+            SharedProps::Capture(listeners) => write!(f, "Capture({:?})", listeners),
         }
     }
 }
@@ -64,6 +143,8 @@ impl fmt::Display for SharedProps {
 
 pub struct SP;
 
+// handlers are made and run on the UI thread, the variants share them as Arc<Mutex<..>> all the same
+#[allow(clippy::arc_with_non_send_sync)]
 impl SP {
     pub fn ClassName(className: &str) -> SharedProps {
         SharedProps::ClassName(Rc::from(className))
@@ -77,25 +158,279 @@ impl SP {
     pub fn FontFace(face: &str) -> SharedProps {
         SharedProps::FontFace(Rc::from(face))
     }
+    /// height of the font in logical units, the system font size when left out
+    #[allow(dead_code)]
+    pub fn FontSize(size: i32) -> SharedProps {
+        SharedProps::FontSize(size)
+    }
+    pub fn ControlId(id: i32) -> SharedProps {
+        SharedProps::ControlId(id)
+    }
+    pub fn Width(width: i32) -> SharedProps {
+        SharedProps::Width(width)
+    }
+    pub fn Height(height: i32) -> SharedProps {
+        SharedProps::Height(height)
+    }
+    pub fn PosX(x: i32) -> SharedProps {
+        SharedProps::PosX(x)
+    }
+    pub fn PosY(y: i32) -> SharedProps {
+        SharedProps::PosY(y)
+    }
+    /// the layout never makes the control narrower, Width included
+    #[allow(dead_code)]
+    pub fn MinWidth(width: i32) -> SharedProps {
+        SharedProps::MinWidth(width)
+    }
+    #[allow(dead_code)]
+    pub fn MaxWidth(width: i32) -> SharedProps {
+        SharedProps::MaxWidth(width)
+    }
+    #[allow(dead_code)]
+    pub fn MinHeight(height: i32) -> SharedProps {
+        SharedProps::MinHeight(height)
+    }
+    #[allow(dead_code)]
+    pub fn MaxHeight(height: i32) -> SharedProps {
+        SharedProps::MaxHeight(height)
+    }
+    pub fn Anchor(flags: usize) -> SharedProps {
+        SharedProps::Anchor(flags)
+    }
+    pub fn Selected(selected: bool) -> SharedProps {
+        SharedProps::Selected(selected)
+    }
+    #[allow(dead_code)]
+    pub fn SelectItems<T: ToString>(items: impl IntoIterator<Item = T>) -> SharedProps {
+        SharedProps::SelectItems(items.into_iter().map(|i|i.to_string()).collect())
+    }
+    #[allow(dead_code)]
+    pub fn SelectedIndex(index: usize) -> SharedProps {
+        SharedProps::SelectedIndex(index)
+    }
+    #[allow(dead_code)]
     pub fn ClientRect(x: usize, y: usize, width: usize, height: usize) -> SharedProps {
         SharedProps::ClientRect((x, y, width, height))
     }
 
+    pub fn Direction(direction: Direction) -> SharedProps {
+        SharedProps::Direction(direction)
+    }
+    /// gap between the children of a stack
+    pub fn Spacing(spacing: i32) -> SharedProps {
+        SharedProps::Spacing(spacing)
+    }
+    /// gap between the border of a stack and its children
+    pub fn Padding(padding: i32) -> SharedProps {
+        SharedProps::Padding(padding)
+    }
+    /// where a stack puts its children across the stacking direction
+    pub fn AlignItems(align: Align) -> SharedProps {
+        SharedProps::AlignItems(align)
+    }
+    /// how a stack spreads its children along the stacking direction
+    pub fn JustifyContent(justify: Justify) -> SharedProps {
+        SharedProps::JustifyContent(justify)
+    }
+    /// lets a stack break its children into more lines
+    pub fn Wrap(wrap: bool) -> SharedProps {
+        SharedProps::Wrap(wrap)
+    }
+    /// share of the free space of a stack the child takes
+    pub fn Grow(grow: i32) -> SharedProps {
+        SharedProps::Grow(grow)
+    }
+    /// share of the missing space of a stack the child gives back, 1 by default
+    pub fn Shrink(shrink: i32) -> SharedProps {
+        SharedProps::Shrink(shrink)
+    }
+    /// rows of a grid, top to bottom
+    #[allow(dead_code)]
+    pub fn Rows(rows: impl IntoIterator<Item = GridLength>) -> SharedProps {
+        SharedProps::Rows(rows.into_iter().collect())
+    }
+    /// columns of a grid, left to right
+    #[allow(dead_code)]
+    pub fn Columns(columns: impl IntoIterator<Item = GridLength>) -> SharedProps {
+        SharedProps::Columns(columns.into_iter().collect())
+    }
+    /// grid row of the child, counting from 0
+    #[allow(dead_code)]
+    pub fn Row(row: usize) -> SharedProps {
+        SharedProps::Row(row)
+    }
+    /// grid column of the child, counting from 0
+    #[allow(dead_code)]
+    pub fn Column(column: usize) -> SharedProps {
+        SharedProps::Column(column)
+    }
+    #[allow(dead_code)]
+    pub fn RowSpan(span: usize) -> SharedProps {
+        SharedProps::RowSpan(span)
+    }
+    #[allow(dead_code)]
+    pub fn ColumnSpan(span: usize) -> SharedProps {
+        SharedProps::ColumnSpan(span)
+    }
+    /// where the child sits across its grid cells
+    #[allow(dead_code)]
+    pub fn HorizontalAlign(align: Align) -> SharedProps {
+        SharedProps::HorizontalAlign(align)
+    }
+    /// where the child sits down its grid cells
+    #[allow(dead_code)]
+    pub fn VerticalAlign(align: Align) -> SharedProps {
+        SharedProps::VerticalAlign(align)
+    }
+    /// scroll offset of a scroll view in logical units, applied when the view changes it
+    #[allow(dead_code)]
+    pub fn ScrollX(offset: i32) -> SharedProps {
+        SharedProps::ScrollX(offset)
+    }
+    #[allow(dead_code)]
+    pub fn ScrollY(offset: i32) -> SharedProps {
+        SharedProps::ScrollY(offset)
+    }
+    /// height of every row of a virtual list, the rows are measured when left out
+    #[allow(dead_code)]
+    pub fn RowHeight(height: i32) -> SharedProps {
+        SharedProps::RowHeight(height)
+    }
+    /// share of a split the first pane takes, kept until the view changes it
+    #[allow(dead_code)]
+    pub fn SplitRatio(ratio: f32) -> SharedProps {
+        SharedProps::SplitRatio(ratio)
+    }
+    /// size of the first pane of a split in logical units, the second pane takes the rest
+    #[allow(dead_code)]
+    pub fn SplitPosition(position: i32) -> SharedProps {
+        SharedProps::SplitPosition(position)
+    }
+    /// side of a dock panel the child takes
+    #[allow(dead_code)]
+    pub fn Dock(dock: Dock) -> SharedProps {
+        SharedProps::Dock(dock)
+    }
+
+    /// DidCreate, DidClick, DidChange, DidResize and DidDestroy handlers taking props get the payload as PosX, PosY,
+    /// Width and Height, Selected or Title and SelectedIndex, OnCreate and friends take it typed
     #[allow(dead_code)]
     pub fn DidCreate<C: Into<Command<Vec<SharedProps>>>>(handler: C) -> SharedProps {
-        SharedProps::DidCreate(Arc::new(Mutex::new(handler.into())))
+        let mut handler = handler.into();
+        SP::OnCreate(move|event: CreateEvent|handler.exec(event.into()))
+    }
+    pub fn DidClick<C: Into<Command<Vec<SharedProps>>>>(handler: C) -> SharedProps {
+        let mut handler = handler.into();
+        SP::OnClick(move|event: ClickEvent|handler.exec(event.into()))
     }
     #[allow(dead_code)]
     pub fn DidChange<C: Into<Command<Vec<SharedProps>>>>(handler: C) -> SharedProps {
-        SharedProps::DidChange(Arc::new(Mutex::new(handler.into())))
+        let mut handler = handler.into();
+        SP::OnChange(move|event: ChangeEvent|handler.exec(event.into()))
     }
     #[allow(dead_code)]
     pub fn DidResize<C: Into<Command<Vec<SharedProps>>>>(handler: C) -> SharedProps {
+        let mut handler = handler.into();
+        SP::OnResize(move|event: ResizeEvent|handler.exec(event.into()))
+    }
+    #[allow(dead_code)]
+    pub fn OnCreate<C: Into<Command<CreateEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidCreate(Arc::new(Mutex::new(handler.into())))
+    }
+    pub fn OnClick<C: Into<Command<ClickEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidClick(Arc::new(Mutex::new(handler.into())))
+    }
+    pub fn OnChange<C: Into<Command<ChangeEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidChange(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn OnResize<C: Into<Command<ResizeEvent>>>(handler: C) -> SharedProps {
         SharedProps::DidResize(Arc::new(Mutex::new(handler.into())))
     }
     pub fn DidDestroy<C: Into<Command<Vec<SharedProps>>>>(handler: C) -> SharedProps {
+        let mut handler = handler.into();
+        SP::OnDestroy(move|event: DestroyEvent|handler.exec(event.into()))
+    }
+    #[allow(dead_code)]
+    pub fn OnDestroy<C: Into<Command<DestroyEvent>>>(handler: C) -> SharedProps {
         SharedProps::DidDestroy(Arc::new(Mutex::new(handler.into())))
     }
+    /// called with the offset a scroll view has been scrolled to
+    #[allow(dead_code)]
+    pub fn DidScroll<C: Into<Command<ScrollEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidScroll(Arc::new(Mutex::new(handler.into())))
+    }
+    /// called with the ratio and position a split divider has been dragged to
+    #[allow(dead_code)]
+    pub fn DidSplit<C: Into<Command<SplitEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidSplit(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidFocus<C: Into<Command<FocusEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidFocus(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidBlur<C: Into<Command<FocusEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidBlur(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidKeyDown<C: Into<Command<KeyEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidKeyDown(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidKeyUp<C: Into<Command<KeyEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidKeyUp(Arc::new(Mutex::new(handler.into())))
+    }
+    /// mouse events bubble up to the parents but DidMouseEnter and DidMouseLeave
+    #[allow(dead_code)]
+    pub fn DidMouseDown<C: Into<Command<MouseEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidMouseDown(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidMouseUp<C: Into<Command<MouseEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidMouseUp(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidMouseMove<C: Into<Command<MouseEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidMouseMove(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidMouseEnter<C: Into<Command<MouseEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidMouseEnter(Arc::new(Mutex::new(handler.into())))
+    }
+    #[allow(dead_code)]
+    pub fn DidMouseLeave<C: Into<Command<MouseEvent>>>(handler: C) -> SharedProps {
+        SharedProps::DidMouseLeave(Arc::new(Mutex::new(handler.into())))
+    }
+    /// listeners of the capture phase, they see the events of the children before the children do
+    #[allow(dead_code)]
+    pub fn Capture<T: Into<Vec<SharedProps>>>(listeners: T) -> SharedProps {
+        SharedProps::Capture(listeners.into())
+    }
+}
+
+impl SharedProps {
+    /// handlers the event hub calls
+    pub fn isListener(&self) -> bool {
+        matches!(self, SharedProps::DidCreate(_)
+            | SharedProps::DidClick(_)
+            | SharedProps::DidChange(_)
+            | SharedProps::DidResize(_)
+            | SharedProps::DidDestroy(_)
+            | SharedProps::DidScroll(_)
+            | SharedProps::DidSplit(_)
+            | SharedProps::DidFocus(_)
+            | SharedProps::DidBlur(_)
+            | SharedProps::DidKeyDown(_)
+            | SharedProps::DidKeyUp(_)
+            | SharedProps::DidMouseDown(_)
+            | SharedProps::DidMouseUp(_)
+            | SharedProps::DidMouseMove(_)
+            | SharedProps::DidMouseEnter(_)
+            | SharedProps::DidMouseLeave(_)
+            | SharedProps::Capture(_))
+    }
 }
 
 impl From<SharedProps> for Vec<SharedProps> {
@@ -107,18 +442,13 @@ impl From<SharedProps> for Vec<SharedProps> {
 pub trait VectorExtention {
     fn prop(&self, variant: &SharedProps) -> Option<&SharedProps>;
     fn merge<T: Into<Vec<SharedProps>>>(self, right: T) -> Self;
+    #[allow(dead_code)]
     fn update<T: Into<Vec<SharedProps>>>(self, right: T) -> Self;
 }
 
 impl VectorExtention for Vec<SharedProps> {
     fn prop(&self, variant: &SharedProps) -> Option<&SharedProps> {
-        self.iter().find_map(|d|{
-            if std::mem::discriminant(d) == std::mem::discriminant(variant) {
-                Some(d)
-            } else {
-                None
-            }
-        })
+        self.iter().find(|d|std::mem::discriminant(*d) == std::mem::discriminant(variant))
     }
     fn merge<T: Into<Vec<SharedProps>>>(self, right: T) -> Self {
         let mut difference = vec![];
@@ -136,7 +466,7 @@ impl VectorExtention for Vec<SharedProps> {
 
         for i in right.into_iter() {
             let prop = inst.prop(&i);
-            if let Some(_) = prop {
+            if prop.is_some() {
                 inst = inst.merge(i);
             }
         }
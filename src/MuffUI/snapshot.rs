@@ -0,0 +1,190 @@
+#![allow(non_snake_case)]
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::muffui::*;
+
+/// compares paths so that "0:1_2" goes before "0:1_10"
+pub fn comparePaths(left: &str, right: &str) -> Ordering {
+    let split = |path: &str| {
+        let mut parts: Vec<(bool, String)> = vec![];
+        for c in path.chars() {
+            match parts.last_mut() {
+                Some((isDigit, part)) if *isDigit == c.is_ascii_digit() => part.push(c),
+                _ => parts.push((c.is_ascii_digit(), c.to_string())),
+            }
+        }
+        parts
+    };
+
+    let (left, right) = (split(left), split(right));
+    for ((lDigit, l), (rDigit, r)) in left.iter().zip(right.iter()) {
+        let ord = match (lDigit, rDigit) {
+            (true, true) => l.len().cmp(&r.len()).then(l.cmp(r)),
+            _ => l.cmp(r),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    left.len().cmp(&right.len())
+}
+
+impl UIContext {
+    /// stable text dump of the rendered tree, one control per line, children indented under the parent
+    #[allow(dead_code)]
+    pub fn snapshot(&self) -> String {
+        let backend = &*self.backend;
+        let paths = self.items.iter()
+            .map(|(path, ci)|(ci.hwnd, path.as_str()))
+            .collect::<HashMap<_, _>>();
+        let mut roots = vec![];
+        let mut children: HashMap<Handle, Vec<&str>> = HashMap::new();
+        for (path, ci) in self.items.iter() {
+            let parent = backend.getParent(ci.hwnd);
+            if paths.contains_key(&parent) {
+                children.entry(parent).or_default().push(path);
+            } else {
+                roots.push(path.as_str());
+            }
+        }
+
+        let mut res = String::new();
+        let mut stack = vec![];
+        roots.sort_by(|a, b|comparePaths(b, a));
+        stack.extend(roots.into_iter().map(|path|(0, path)));
+        while let Some((depth, path)) = stack.pop() {
+            let ci = &self.items[path];
+            let rect = backend.getControlRect(ci.hwnd).unwrap_or_default();
+            let _ = write!(res, "{}{} {} class={:?} title={:?} rect=({}, {}, {}, {}) anchor={}",
+                "  ".repeat(depth), path, ci.renderer,
+                backend.getClassName(ci.hwnd).unwrap_or_default(), backend.getTitle(ci.hwnd),
+                rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top,
+                anchorToString(ci.anchor)
+            );
+            if backend.isSelected(ci.hwnd) {
+                res.push_str(" selected");
+            }
+            if let Some(index) = backend.getSelectedIndex(ci.hwnd) {
+                let _ = write!(res, " index={}", index);
+            }
+            res.push('\n');
+
+            if let Some(items) = children.get_mut(&ci.hwnd) {
+                items.sort_by(|a, b|comparePaths(b, a));
+                stack.extend(items.iter().map(|path|(depth + 1, *path)));
+            }
+        }
+
+        res
+    }
+}
+
+/// line diff of two texts, unchanged lines start with "  ", removed with "- " and added with "+ "
+#[allow(dead_code)]
+pub fn diffLines(expected: &str, actual: &str) -> String {
+    let left = expected.lines().collect::<Vec<_>>();
+    let right = actual.lines().collect::<Vec<_>>();
+    // lcs[i][j] is the longest common subsequence of left[i..] and right[j..]
+    let mut lcs = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lcs[i][j] = if left[i] == right[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut res = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            let _ = writeln!(res, "  {}", left[i]);
+            i += 1;
+            j += 1;
+        } else if i < left.len() && (j == right.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            let _ = writeln!(res, "- {}", left[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(res, "+ {}", right[j]);
+            j += 1;
+        }
+    }
+
+    res
+}
+
+/// compares `actual` with snapshots/<name>.snap, on mismatch writes snapshots/<name>.snap.diff and fails.
+/// a missing snapshot fails too, UPDATE_SNAPSHOTS=1 writes the snapshots from `actual`
+#[cfg(test)]
+pub fn assertSnapshot(name: &str, actual: &str) {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots");
+    let update = std::env::var("UPDATE_SNAPSHOTS").map(|v|v == "1").unwrap_or(false);
+
+    if let Err(e) = matchSnapshot(&dir, name, actual, update) {
+        panic!("{}", e);
+    }
+}
+
+#[cfg(test)]
+fn matchSnapshot(dir: &std::path::Path, name: &str, actual: &str, update: bool) -> Result<(), String> {
+    let file = dir.join(format!("{}.snap", name));
+    let diffFile = dir.join(format!("{}.snap.diff", name));
+    if update {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(&file, actual).unwrap();
+        let _ = std::fs::remove_file(&diffFile);
+        return Ok(());
+    }
+
+    let expected = std::fs::read_to_string(&file).map_err(|_|format!(
+        "snapshot {:?} is missing, run the test with UPDATE_SNAPSHOTS=1 to write {}", name, file.display()
+    ))?;
+    if expected == actual {
+        let _ = std::fs::remove_file(&diffFile);
+        return Ok(());
+    }
+
+    let diff = diffLines(&expected, actual);
+    std::fs::write(&diffFile, &diff).unwrap();
+    Err(format!("snapshot {:?} does not match, see {}\n{}", name, diffFile.display(), diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_orders_paths_by_number() {
+        let mut paths = vec!["0:1_10", "0:1_2[3]", "0", "0:1_2", "0:1_2[10]"];
+        paths.sort_by(|a, b|comparePaths(a, b));
+        assert_eq!(paths, vec!["0", "0:1_2", "0:1_2[3]", "0:1_2[10]", "0:1_10"]);
+    }
+
+    #[test]
+    fn it_diffs_lines() {
+        let diff = diffLines("a\nb\nc\n", "a\nc\nd\n");
+        assert_eq!(diff, "  a\n- b\n  c\n+ d\n");
+        assert_eq!(diffLines("a\nb\n", "a\nc\n"), "  a\n- b\n+ c\n");
+    }
+
+    #[test]
+    fn it_fails_on_missing_snapshot() {
+        let dir = std::env::temp_dir().join(format!("muffui-snapshots-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let e = matchSnapshot(&dir, "view", "a\n", false).unwrap_err();
+        assert!(e.contains("is missing, run the test with UPDATE_SNAPSHOTS=1"), "{}", e);
+        assert!(!dir.join("view.snap").exists());
+
+        assert_eq!(matchSnapshot(&dir, "view", "a\n", true), Ok(()));
+        assert_eq!(matchSnapshot(&dir, "view", "a\n", false), Ok(()));
+        assert!(matchSnapshot(&dir, "view", "b\n", false).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,334 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::muffui::*;
+
+/// logical width of the divider when the view leaves Spacing out
+const DIVIDER: i32 = 5;
+
+/// how the first pane of a split is sized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitMode {
+    /// share of the room both panes have
+    Ratio(f32),
+    /// pixels, the second pane takes the rest
+    Fixed(i32),
+}
+
+impl Default for SplitMode {
+    fn default() -> Self {
+        SplitMode::Ratio(0.5)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SplitStyle {
+    /// Row puts the panes side by side, Column one above the other
+    pub direction: Direction,
+    pub divider: i32,
+    pub mode: SplitMode,
+}
+
+impl From<&Vec<SharedProps>> for SplitStyle {
+    fn from(props: &Vec<SharedProps>) -> Self {
+        props.iter().fold(Self { ..Default::default() }, |style, prop|match prop {
+            SharedProps::Direction(direction) => Self { direction: *direction, ..style },
+            SharedProps::Spacing(divider) => Self { divider: (*divider).max(0), ..style },
+            SharedProps::SplitRatio(ratio) => Self { mode: SplitMode::Ratio(*ratio), ..style },
+            SharedProps::SplitPosition(position) => Self { mode: SplitMode::Fixed(*position), ..style },
+            _ => style,
+        })
+    }
+}
+
+/// size of the first pane out of `available` pixels for both, the limits of the panes win over `mode`
+/// and the minimum of the first pane wins when they can't all be kept
+pub fn splitPosition(mode: SplitMode, available: i32, first: (i32, i32), second: (i32, i32)) -> i32 {
+    let available = available.max(0);
+    let wanted = match mode {
+        SplitMode::Ratio(ratio) => (available as f32 * ratio.clamp(0.0, 1.0)).round() as i32,
+        SplitMode::Fixed(position) => position,
+    };
+    let (low, high) = (first.0.max(available - second.1), first.1.min(available - second.0));
+
+    wanted.min(high).max(low).clamp(0, available)
+}
+
+/// rects of the first pane, the divider and the second pane of a split with `size` client area
+pub fn layoutSplit(style: &SplitStyle, size: Size, limits: &[SizeLimits; 2]) -> [Rect; 3] {
+    let isRow = style.direction == Direction::Row;
+    let (main, cross) = if isRow { (size.cx, size.cy) } else { (size.cy, size.cx) };
+    let range = |l: &SizeLimits| if isRow { (l.minWidth, l.maxWidth) } else { (l.minHeight, l.maxHeight) };
+    let divider = style.divider.min(main.max(0));
+    let first = splitPosition(style.mode, main - divider, range(&limits[0]), range(&limits[1]));
+
+    let rect = |start: i32, end: i32| match isRow {
+        true => Rect { left: start, top: 0, right: end, bottom: cross },
+        _ => Rect { left: 0, top: start, right: cross, bottom: end },
+    };
+    [rect(0, first), rect(first, first + divider), rect(first + divider, main.max(first + divider))]
+}
+
+/// split a user is dragging or has dragged the divider of, the mode follows the divider until the view changes it
+#[derive(Clone, Debug, Default)]
+pub struct SplitState {
+    pub style: SplitStyle,
+    /// mode of the last render
+    pub declared: SplitMode,
+    pub size: Size,
+    pub limits: [SizeLimits; 2],
+    pub panes: Vec<Handle>,
+    /// where the divider has been grabbed, from its start, while it is dragged
+    pub grab: Option<i32>,
+}
+
+impl SplitState {
+    fn registry() -> &'static RefCell<HashMap<Handle, SplitState>> {
+        thread_local! {
+            static CONF: &'static RefCell<HashMap<Handle, SplitState>> = Box::leak(Box::new(RefCell::new(HashMap::new())));
+        }
+
+        CONF.with(|conf|*conf)
+    }
+
+    /// state of the split `hwnd`, None for other controls
+    pub fn find(hwnd: Handle) -> Option<SplitState> {
+        Self::registry().borrow().get(&hwnd).cloned()
+    }
+
+    pub fn release(hwnd: Handle) {
+        Self::registry().borrow_mut().remove(&hwnd);
+    }
+
+    /// takes the style, size and pane limits of a render, returns the rects of the panes and the divider
+    pub fn update(hwnd: Handle, style: SplitStyle, size: Size, limits: [SizeLimits; 2]) -> [Rect; 3] {
+        let prev = Self::find(hwnd).unwrap_or_else(||Self { declared: style.mode, ..Default::default() });
+        let mode = if prev.declared == style.mode { prev.style.mode } else { style.mode };
+        let state = Self { style: SplitStyle { mode, ..style }, declared: style.mode, size, limits, ..prev };
+        let rects = layoutSplit(&state.style, size, &limits);
+
+        Self::registry().borrow_mut().insert(hwnd, state);
+        rects
+    }
+
+    pub fn setPanes(hwnd: Handle, panes: Vec<Handle>) {
+        if let Some(state) = Self::registry().borrow_mut().get_mut(&hwnd) {
+            state.panes = panes;
+        }
+    }
+
+    pub fn rects(&self) -> [Rect; 3] {
+        layoutSplit(&self.style, self.size, &self.limits)
+    }
+
+    /// share of the room of both panes the first one has and its size in pixels
+    pub fn position(&self) -> (f32, i32) {
+        let [first, divider, _] = self.rects();
+        let (first, available) = match self.style.direction {
+            Direction::Row => (first.right - first.left, self.size.cx - (divider.right - divider.left)),
+            _ => (first.bottom - first.top, self.size.cy - (divider.bottom - divider.top)),
+        };
+
+        (first as f32 / available.max(1) as f32, first)
+    }
+
+    /// handles a mouse message of the split `msg.hwnd`, moves the panes while the divider is dragged
+    /// and posts WM_SPLITMOVE to the split every time it moves
+    pub fn drag(backend: &dyn Backend, msg: &Message) -> Option<Self> {
+        let mut state = Self::find(msg.hwnd)?;
+        let point = Point { x: LOWORD(msg.lParam as usize) as i16 as i32, y: HIWORD(msg.lParam as usize) as i16 as i32 };
+        let isRow = state.style.direction == Direction::Row;
+        let along = |x: i32, y: i32| if isRow { x } else { y };
+        let rects = state.rects();
+        let divider = rects[1];
+
+        match (msg.message, state.grab) {
+            (WM_LBUTTONDOWN, _) if point.x >= divider.left && point.x < divider.right && point.y >= divider.top && point.y < divider.bottom => {
+                state.grab = Some(along(point.x - divider.left, point.y - divider.top));
+            },
+            (WM_MOUSEMOVE, Some(grab)) => {
+                let available = along(state.size.cx, state.size.cy) - along(divider.right - divider.left, divider.bottom - divider.top);
+                let position = along(point.x, point.y) - grab;
+                state.style.mode = match state.style.mode {
+                    SplitMode::Ratio(_) => SplitMode::Ratio(position as f32 / available.max(1) as f32),
+                    SplitMode::Fixed(_) => SplitMode::Fixed(position),
+                };
+                // the mode keeps where the limits stopped the divider
+                let (ratio, position) = state.position();
+                state.style.mode = match state.style.mode {
+                    SplitMode::Ratio(_) => SplitMode::Ratio(ratio),
+                    SplitMode::Fixed(_) => SplitMode::Fixed(position),
+                };
+
+                let moved = state.rects();
+                if moved != rects {
+                    for (pane, rect) in state.panes.iter().zip([moved[0], moved[2]]) {
+                        backend.moveControl(*pane, &rect);
+                    }
+                    backend.postMessage(Message { hwnd: msg.hwnd, message: WM_SPLITMOVE, ..Default::default() });
+                }
+            },
+            (WM_LBUTTONUP, Some(_)) => state.grab = None,
+            _ => return Some(state),
+        }
+
+        Self::registry().borrow_mut().insert(msg.hwnd, state.clone());
+        Some(state)
+    }
+}
+
+/// container with two panes and a divider between them the user can drag,
+/// SplitRatio or SplitPosition size the first pane, MinWidth/MinHeight of the panes stop the divider
+#[derive(Clone)]
+pub struct Split {
+    pub children: Option<Rc<dyn Renderable>>,
+    pub props: Vec<SharedProps>,
+}
+
+impl Renderable for Split {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        Some(self.children.as_ref()?.clone())
+    }
+
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut context = context.render(parent, index, self, msg);
+        let children = match &self.children {
+            Some(children) => children,
+            _ => return context,
+        };
+
+        let childIndex = format!("{}:1", index);
+        let hwnd = match context.items.get(index) {
+            Some(ci) if msg.is_none() => ci.hwnd,
+            _ => return children.render(context, index, &childIndex, msg),
+        };
+
+        let panes = children.layoutItems(&childIndex).into_iter().take(2).collect::<Vec<_>>();
+        let dpi = context.dpi(index);
+        let limits = |idx: usize| panes.get(idx)
+            .map(|(_, props)|SizeLimits::from(&measure(&*context.backend, props, dpi)))
+            .unwrap_or_default();
+        let size = context.clientSize(index);
+        let [first, _, second] = SplitState::update(hwnd, SplitStyle::from(&scaleProps(&self.props, dpi)), size, [limits(0), limits(1)]);
+        let paths = panes.into_iter().map(|(path, _)|path).collect::<Vec<_>>();
+        context.place(&childIndex, paths.iter().cloned().zip([first, second]));
+
+        let context = children.render(context, index, &childIndex, msg);
+        SplitState::setPanes(hwnd, paths.iter().filter_map(|path|context.items.get(path)).map(|ci|ci.hwnd).collect());
+
+        context
+    }
+
+    fn toViewState(&self) -> Vec<SharedProps> {
+        self.props.clone()
+    }
+}
+
+#[allow(dead_code)]
+impl Split {
+    pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
+        let defaultProps = vec![
+            SP::ClassName("panel#0x8002"),
+            SP::Renderer("split"),
+            SP::Spacing(DIVIDER),
+        ];
+        Self {
+            children: None,
+            props: defaultProps.merge(props.into()),
+        }
+    }
+
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
+        Self {
+            children: Some(Rc::new(children().intoChildren())),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rects(style: SplitStyle, limits: [SizeLimits; 2]) -> Vec<[i32; 4]> {
+        layoutSplit(&style, Size { cx: 305, cy: 100 }, &limits).iter()
+            .map(|r|[r.left, r.top, r.right - r.left, r.bottom - r.top])
+            .collect()
+    }
+
+    #[test]
+    fn it_sizes_panes_by_ratio_or_position() {
+        let style = SplitStyle { divider: 5, mode: SplitMode::Ratio(0.25), ..Default::default() };
+        let none = [SizeLimits::default(); 2];
+        assert_eq!(rects(style, none), vec![[0, 0, 75, 100], [75, 0, 5, 100], [80, 0, 225, 100]]);
+
+        let fixed = SplitStyle { mode: SplitMode::Fixed(200), ..style };
+        assert_eq!(rects(fixed, none), vec![[0, 0, 200, 100], [200, 0, 5, 100], [205, 0, 100, 100]]);
+
+        let column = SplitStyle { direction: Direction::Column, mode: SplitMode::Ratio(0.5), ..style };
+        assert_eq!(rects(column, none), vec![[0, 0, 305, 48], [0, 48, 305, 5], [0, 53, 305, 47]]);
+    }
+
+    #[test]
+    fn it_keeps_panes_in_their_limits() {
+        let min = |minWidth| SizeLimits { minWidth, ..Default::default() };
+        assert_eq!(splitPosition(SplitMode::Ratio(0.1), 300, (50, i32::MAX), (0, i32::MAX)), 50);
+        assert_eq!(splitPosition(SplitMode::Fixed(280), 300, (0, i32::MAX), (40, i32::MAX)), 260);
+        assert_eq!(splitPosition(SplitMode::Ratio(0.9), 300, (0, 100), (0, i32::MAX)), 100);
+        assert_eq!(splitPosition(SplitMode::Ratio(0.1), 300, (0, i32::MAX), (0, 200)), 100);
+        // the first pane wins when both minimums don't fit
+        assert_eq!(splitPosition(SplitMode::Ratio(0.5), 300, (200, i32::MAX), (200, i32::MAX)), 200);
+        assert_eq!(splitPosition(SplitMode::Fixed(-20), 300, (0, i32::MAX), (0, i32::MAX)), 0);
+
+        let style = SplitStyle { divider: 5, mode: SplitMode::Ratio(0.0), ..Default::default() };
+        assert_eq!(rects(style, [min(60), min(0)])[2], [65, 0, 240, 100]);
+    }
+
+    #[test]
+    fn it_drags_divider_and_keeps_pane_anchors() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let moved = Rc::new(RefCell::new(vec![]));
+        let view = {
+            let moved = moved.clone();
+            Split::new([SP::Width(305), SP::Height(100), SP::SplitRatio(0.5),
+                SP::DidSplit(move|event: SplitEvent|moved.borrow_mut().push((event.target, event.position)))
+            ]).content(||(
+                Panel::new([SP::MinWidth(100)]).content(||Button::new([SP::ControlId(1), SP::Anchor(ANF_TOP | ANF_RIGHT), SP::PosX(100), SP::Width(40), SP::Height(20)])),
+                Panel::new([]).content(||Button::new([SP::ControlId(2), SP::Anchor(ANF_TOP | ANF_LEFTRIGHT), SP::PosX(10), SP::Width(130), SP::Height(20)])),
+            ))
+        };
+        let mut context = UIContext::create(backend.clone());
+        for _ in 0..2 {
+            context = context.update(&view);
+        }
+        let rect = |context: &UIContext, path: &str| {
+            let r = backend.node(context.items[path].hwnd).unwrap().rect;
+            [r.left, r.top, r.right - r.left, r.bottom - r.top]
+        };
+        assert_eq!(rect(&context, "0:1_1"), [0, 0, 150, 100]);
+        assert_eq!(rect(&context, "0:1_2"), [155, 0, 150, 100]);
+
+        // the first pane stops at its MinWidth
+        let split = context.items["0"].hwnd;
+        backend.drag(split, Point { x: 152, y: 50 }, Point { x: 40, y: 50 });
+        while let Some(msg) = backend.peekMessage() {
+            backend.dispatchMessage(&msg);
+            context = view.render(context, "/", "0", Some(msg));
+            let mut hub = EventHub::new();
+            hub.enqueueEvent(&*backend, msg);
+            hub.putListeners(&context);
+            hub.dispatchEvents(&*backend);
+        }
+        assert_eq!(rect(&context, "0:1_1"), [0, 0, 100, 100]);
+        assert_eq!(rect(&context, "0:1_2"), [105, 0, 200, 100]);
+        assert_eq!(rect(&context, "0:1_1:1_1"), [50, 0, 40, 20]);
+        assert_eq!(rect(&context, "0:1_2:1_1"), [10, 0, 180, 20]);
+        assert_eq!(*moved.borrow(), vec![(split, 100)]);
+
+        // renders keep the dragged ratio until the view changes its own
+        let context = context.update(&view);
+        assert_eq!(rect(&context, "0:1_1"), [0, 0, 100, 100]);
+    }
+}
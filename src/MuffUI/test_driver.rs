@@ -0,0 +1,163 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use crate::muffui::*;
+
+/// how `TestDriver` looks a control up
+#[derive(Clone, Copy, Debug)]
+pub enum By<'a> {
+    ControlId(i32),
+    Title(&'a str),
+    Renderer(&'a str),
+    /// key generated by UIContext, e.g. "0:1_2[3]"
+    Path(&'a str),
+}
+
+/// drives the App on the headless backend the way a user would
+pub struct TestDriver {
+    pub app: App,
+    pub backend: Rc<HeadlessBackend>,
+}
+
+impl TestDriver {
+    pub fn mount() -> Result<Self, String> {
+        let backend = Rc::new(HeadlessBackend::new());
+        let mut app = App::withBackend(backend.clone());
+        if !app.mount()? {
+            return Err(String::from("app has no view"));
+        }
+
+        Ok(Self { app, backend })
+    }
+
+    /// drives `view` instead of the view of the app
+    #[allow(dead_code)]
+    pub fn mountView<V: Renderable + 'static>(view: V) -> Result<Self, String> {
+        let backend = Rc::new(HeadlessBackend::new());
+        let mut app = App::withBackend(backend.clone());
+        app.mountView(view)?;
+
+        Ok(Self { app, backend })
+    }
+
+    /// matching controls, parents before children
+    pub fn findAll(&self, by: By) -> Vec<HeadlessNode> {
+        if let By::Path(path) = by {
+            return self.app.controls().into_iter()
+                .filter(|(p, _)|p == path)
+                .filter_map(|(_, hwnd)|self.backend.node(hwnd))
+                .collect();
+        }
+
+        self.backend.nodes().into_iter().filter(|n|match by {
+            By::ControlId(id) => n.controlId == id,
+            By::Title(title) => n.title == title,
+            By::Renderer(renderer) => n.renderer == renderer,
+            By::Path(_) => false,
+        }).collect()
+    }
+
+    pub fn find(&self, by: By) -> Option<HeadlessNode> {
+        self.findAll(by).into_iter().next()
+    }
+
+    /// same as `find`, but fails the test when nothing matches
+    pub fn get(&self, by: By) -> HeadlessNode {
+        self.find(by).unwrap_or_else(||panic!("no control matches {:?}", by))
+    }
+
+    pub fn path(&self, hwnd: Handle) -> Option<String> {
+        self.app.controls().into_iter().find(|(_, h)|*h == hwnd).map(|(p, _)|p)
+    }
+
+    /// fires DidClick on the control and waits for the re-render
+    pub fn click(&mut self, by: By) -> bool {
+        let node = self.get(by);
+        self.backend.postMessage(Message { hwnd: node.hwnd, message: BM_CLICK, ..Default::default() });
+
+        self.settle()
+    }
+
+    /// replaces the text of the control, fires DidChange and waits for the re-render
+    pub fn typeText(&mut self, by: By, text: &str) -> bool {
+        let node = self.get(by);
+        self.backend.setTitle(node.hwnd, text);
+        let lastChar = text.chars().last().map(|c|c as usize).unwrap_or_default();
+        self.backend.postMessage(Message { hwnd: node.hwnd, message: WM_CHAR, wParam: lastChar, ..Default::default() });
+
+        self.settle()
+    }
+
+    /// picks an item of a select, fires DidChange and waits for the re-render
+    #[allow(dead_code)]
+    pub fn select(&mut self, by: By, index: usize) -> bool {
+        let node = self.get(by);
+        self.backend.setSelectedIndex(node.hwnd, index);
+        if let Some(text) = self.backend.getSelectItemText(node.hwnd, index) {
+            self.backend.setTitle(node.hwnd, &text);
+        }
+        let Handle(control) = node.hwnd;
+        self.backend.postMessage(Message {
+            hwnd: node.parent,
+            message: WM_COMMAND,
+            wParam: ((CBN_SELCHANGE as usize) << 16) | (node.controlId as usize & 0xffff),
+            lParam: control,
+        });
+
+        self.settle()
+    }
+
+    /// turns the mouse wheel over the control by `notches`, positive towards the end of the content, and waits for the re-render
+    #[allow(dead_code)]
+    pub fn scroll(&mut self, by: By, notches: i32) -> bool {
+        let node = self.get(by);
+        self.backend.wheel(node.hwnd, -notches * WHEEL_DELTA);
+
+        self.settle()
+    }
+
+    /// plays events recorded by `EventRecorder` back in order, waiting for the re-render after each,
+    /// false once the app has quit and an error when the view has no control at the path of an event
+    #[allow(dead_code)]
+    pub fn replay(&mut self, events: &[RecordedEvent]) -> Result<bool, String> {
+        for event in events {
+            let hwnd = self.app.controls().into_iter()
+                .find(|(path, _)|*path == event.path)
+                .map(|(_, hwnd)|hwnd)
+                .ok_or_else(||format!("no control at {} for {} at {}ms", event.path, event.kind, event.time))?;
+            match &event.payload {
+                EventPayload::Click { selected } => self.backend.setSelected(hwnd, *selected),
+                EventPayload::Change { text, selectedIndex } => {
+                    if let Some(index) = selectedIndex {
+                        self.backend.setSelectedIndex(hwnd, *index);
+                    }
+                    self.backend.setTitle(hwnd, text);
+                },
+                EventPayload::Key { modifiers, .. } | EventPayload::Mouse { modifiers, .. } => self.backend.setModifiers(*modifiers),
+                _ => {},
+            }
+
+            let msg = Message { hwnd, message: event.message, wParam: event.wParam, lParam: event.lParam };
+            match (event.message, &event.payload) {
+                // controls tell their parent, by the handle of the control
+                (WM_COMMAND, _) => {
+                    let Handle(control) = hwnd;
+                    self.backend.postMessage(Message { hwnd: self.backend.getParent(hwnd), lParam: control, ..msg });
+                },
+                (WM_SIZE | WM_SIZING, EventPayload::Resize { rect }) => self.backend.resize(hwnd, rect.right - rect.left, rect.bottom - rect.top),
+                (WM_DESTROY, _) => self.backend.close(hwnd),
+                _ => self.backend.postMessage(msg),
+            }
+            if !self.settle() {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// handles everything queued so far, false once the app has quit
+    pub fn settle(&mut self) -> bool {
+        self.app.pumpMessages()
+    }
+}
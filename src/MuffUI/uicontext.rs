@@ -6,16 +6,19 @@ use std::cmp::Eq;
 use std::cmp::PartialEq;
 use std::hash::Hash;
 use std::hash::Hasher;
-use crate::Win;
 use crate::muffui::*;
 
 
 #[derive(Clone)]
 pub struct ControlInfo {
-    pub hwnd: Win::HWND,
-    pub hFont: Option<Win::HFONT>,
+    pub hwnd: Handle,
+    pub hFont: Option<Handle>,
     pub isInitialized: isize,
     pub listeners: Vec<SharedProps>,
+    pub renderer: String,
+    pub anchor: usize,
+    /// posX, posY, width and height as declared by the view, in logical units
+    pub geometry: [i32; 4],
 }
 
 impl fmt::Debug for ControlInfo {
@@ -31,20 +34,23 @@ impl Default for ControlInfo {
             hFont: Default::default(),
             isInitialized: Default::default(),
             listeners: vec![],
+            renderer: Default::default(),
+            anchor: Default::default(),
+            geometry: [CW_USEDEFAULT; 4],
         }
     }
 }
 
 impl Hash for ControlInfo {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        let Win::HWND(hwnd) = self.hwnd;
+        let Handle(hwnd) = self.hwnd;
         hwnd.hash(state);
     }
 }
 
 impl PartialEq for ControlInfo {
     fn eq(&self, other: &Self) -> bool {
-        let (Win::HWND(left), Win::HWND(right)) = (self.hwnd, other.hwnd);
+        let (Handle(left), Handle(right)) = (self.hwnd, other.hwnd);
         left == right
     }
 }
@@ -53,30 +59,40 @@ impl Eq for ControlInfo { }
 
 #[derive(Clone)]
 pub struct UIContext {
+    pub backend: Rc<dyn Backend>,
     pub items: HashMap<String, ControlInfo>,
     pub prevItems: HashMap<String, ControlInfo>,
+    /// rects stacks have given to their children, keyed by the path of the child
+    pub placements: HashMap<String, Rect>,
 }
 
 impl UIContext {
-    pub fn new() -> Self {
+    pub fn new(backend: Rc<dyn Backend>) -> Self {
         Self {
+            backend,
             items: HashMap::new(),
             prevItems: HashMap::new(),
+            placements: HashMap::new(),
         }
     }
 
-    pub fn create() -> Box<Self> {
-        Box::new(Self::new())
+    pub fn create(backend: Rc<dyn Backend>) -> Box<Self> {
+        Box::new(Self::new(backend))
     }
 
+    // render passes hand the context around boxed, taking the box keeps `context.clean()` chaining without unboxing
+    #[allow(clippy::boxed_local)]
     pub fn clean(self: Box<Self>) -> Box<Self> {
         let mut cleanKeys: Vec<String> = vec![];
         let mut items = self.items.clone();
         for (key, ci) in self.items.iter() {
             if !self.prevItems.contains_key(key) {
                 cleanKeys.push(key.clone());
-                AnchorMap::shared().try_lock().as_mut().ok().map(|am|am.removeControl(ci.hwnd));
-                Win::DestroyWindow(ci.hwnd);
+                AnchorMap::release(&*self.backend, ci.hwnd);
+                ScrollState::release(ci.hwnd);
+                SplitState::release(ci.hwnd);
+                RowHeights::release(ci.hwnd);
+                self.backend.destroyControl(ci.hwnd);
             }
         }
 
@@ -85,12 +101,47 @@ impl UIContext {
         }
 
         Box::new(UIContext {
+            backend: self.backend,
             items,
-            prevItems: self.prevItems
+            prevItems: self.prevItems,
+            placements: self.placements,
         })
     }
 
-    pub fn render<T: Renderable>(self: Box<Self>, parent: &str, index: &str, view: &T, msg: Option<Win::MSG>) -> Box<Self> {
+    /// dpi of the window the control rendered at `index` is in
+    pub fn dpi(&self, index: &str) -> u32 {
+        self.items.get(index)
+            .map(|ci|self.backend.getDpi(ci.hwnd))
+            .unwrap_or_else(||self.backend.systemDpi())
+    }
+
+    /// client area of the control rendered at `index`
+    pub fn clientSize(&self, index: &str) -> Size {
+        self.items.get(index)
+            .and_then(|ci|self.backend.getClientRect(ci.hwnd))
+            .map(|r|Size { cx: r.right - r.left, cy: r.bottom - r.top })
+            .unwrap_or_default()
+    }
+
+    /// replaces the rects given to the children rendered under `index`
+    pub fn place(&mut self, index: &str, placements: impl IntoIterator<Item = (String, Rect)>) {
+        let prefix = format!("{}_", index);
+        self.placements.retain(|path, _|!path.starts_with(&prefix));
+        self.placements.extend(placements);
+    }
+
+    /// renders `view` over the existing controls and destroys the ones it no longer has
+    pub fn update<T: Renderable>(self: Box<Self>, view: &T) -> Box<Self> {
+        let mut prev = self.clone();
+        prev.prevItems = HashMap::new();
+
+        let context = view.render(prev, "/", "0", None).clean();
+        LayoutDebug::record(&context);
+
+        context
+    }
+
+    pub fn render<T: Renderable>(self: Box<Self>, parent: &str, index: &str, view: &T, msg: Option<Message>) -> Box<Self> {
         let controlInfo = self.items.get(index);
         let parentHwnd = self.items.get(parent).map(|ci|ci.hwnd);
 
@@ -100,6 +151,7 @@ impl UIContext {
 
             if let Some(ci) = controlInfo {
                 return Box::new(Self {
+                    backend: self.backend.clone(),
                     prevItems: utils::merge(self.prevItems, HashMap::from([(String::from(index), ControlInfo {
                         ..ci.clone()
                     })])),
@@ -107,6 +159,7 @@ impl UIContext {
                         listeners,
                         ..ci.clone()
                     })])),
+                    placements: self.placements.clone(),
                 });
             } else {
                 return self;
@@ -119,7 +172,8 @@ impl UIContext {
             return self;
         }
 
-        let controlInfo = self.renderProperties(controlInfo, parentHwnd, props);
+        let placed = self.placements.get(index).copied();
+        let controlInfo = self.renderProperties(controlInfo, parentHwnd, props, placed);
 
         match controlInfo {
             Some(controlInfo) => Box::new(Self {
@@ -133,47 +187,38 @@ impl UIContext {
         }
     }
 
-    fn collectEvents(_controlInfo: Option<&ControlInfo>, _msg: Win::MSG, props: Vec<SharedProps>) -> Vec<SharedProps> {
-        let mut listeners = vec![];
-
-        for prop in props.into_iter() {
-            let isListener = match prop {
-                SharedProps::DidResize(_)
-                | SharedProps::DidClick(_)
-                | SharedProps::DidChange(_)
-                | SharedProps::DidDestroy(_)
-                | SharedProps::DidCreate(_) => true,
-                _ => false,
-            };
-
-            if isListener {
-                listeners.push(prop);
-            }
-        }
-
-        listeners
+    fn collectEvents(_controlInfo: Option<&ControlInfo>, _msg: Message, props: Vec<SharedProps>) -> Vec<SharedProps> {
+        props.into_iter().filter(|prop|prop.isListener()).collect()
     }
 
-    fn renderProperties(&self, controlInfo: Option<&ControlInfo>, parent: Option<Win::HWND>, props: Vec<SharedProps>) -> Option<ControlInfo> {
+    /// `placed` is the rect a stack has given to the control, it wins over the declared geometry and the anchors
+    fn renderProperties(&self, controlInfo: Option<&ControlInfo>, parent: Option<Handle>, props: Vec<SharedProps>, placed: Option<Rect>) -> Option<ControlInfo> {
         let mut hFont = None;
         let mut hwnd = None;
         let mut isInitialized = 1;
         let mut listeners = vec![];
+        let mut prevGeometry = [CW_USEDEFAULT; 4];
         if let Some(controlInfo) = controlInfo {
             hFont = controlInfo.hFont;
             hwnd = Some(controlInfo.hwnd);
             isInitialized = controlInfo.isInitialized;
             isInitialized += 1;
-            listeners = controlInfo.listeners.iter().map(|a|a.clone()).collect();
+            listeners = controlInfo.listeners.to_vec();
+            prevGeometry = controlInfo.geometry;
         }
-        let mut fontFace = Rc::from(Win::GetSystemFontFace().as_str());
+        let backend = &*self.backend;
+        let dpi = hwnd.or(parent).map(|h|backend.getDpi(h)).unwrap_or_else(||backend.systemDpi());
+        let props = measure(backend, &props, dpi);
+        let limits = SizeLimits::from(&props);
+        let mut fontFace = Rc::from(backend.systemFontFace().as_str());
+        let mut fontSize = 0;
         let mut className = Rc::from("");
         let mut title = Rc::from("");
         let mut renderer = Rc::from("");
-        let mut posX = Win::CW_USEDEFAULT;
-        let mut posY = Win::CW_USEDEFAULT;
-        let mut height = Win::CW_USEDEFAULT;
-        let mut width = Win::CW_USEDEFAULT;
+        let mut posX = CW_USEDEFAULT;
+        let mut posY = CW_USEDEFAULT;
+        let mut height = CW_USEDEFAULT;
+        let mut width = CW_USEDEFAULT;
         let mut idx = 0;
         let mut dock = 0;
         let mut isSelected = false;
@@ -190,6 +235,7 @@ impl UIContext {
                 SharedProps::Height(h) => height = h,
                 SharedProps::ControlId(i) => idx = i,
                 SharedProps::FontFace(f) => fontFace = f,
+                SharedProps::FontSize(s) => fontSize = s,
                 SharedProps::Anchor(d) => dock = d,
                 SharedProps::Selected(s) => isSelected = s,
                 SharedProps::SelectItems(i) => selectItems = i,
@@ -198,109 +244,112 @@ impl UIContext {
             };
         }
 
-        let renderer: &str = &renderer;
-        let style = match renderer {
-            "window" => Win::WS_OVERLAPPEDWINDOW | Win::WS_VISIBLE,
-            "panel" => Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_CLIPSIBLINGS | Win::WS_BORDER,
-            "check-box" => (Win::BS_CHECKBOX as Win::WINDOW_STYLE) | Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE,
-            "group-box" => (Win::BS_GROUPBOX as Win::WINDOW_STYLE) | Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE,
-            "text-box" => Win::WS_CHILD | Win::WS_VISIBLE,
-            "label" => Win::WS_CHILD | Win::WS_VISIBLE,
-            "button" => Win::WS_TABSTOP | Win::WS_BORDER | Win::WS_CHILD | Win::WS_VISIBLE,
-            "radio-box" => (Win::BS_RADIOBUTTON as Win::WINDOW_STYLE) | Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE,
-            "select0" => (Win::CBS_HASSTRINGS as Win::WINDOW_STYLE) | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_VSCROLL,
-            "select" => ((Win::CBS_DROPDOWN | Win::CBS_HASSTRINGS | Win::CBS_AUTOHSCROLL) as Win::WINDOW_STYLE) | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_VSCROLL,
-            "select2" => ((Win::CBS_DROPDOWNLIST | Win::BS_DEFSPLITBUTTON | Win::CBS_DROPDOWN | Win::CBS_HASSTRINGS) as Win::WINDOW_STYLE) | Win::WS_VISIBLE | Win::WS_CHILD | Win::WS_VSCROLL,  // Styles WS_VSCROLL | BS_DEFSPLITBUTTON WS_DISABLED | 
-            _ => Win::WS_BORDER,
-        };
+        if let Some(rect) = placed {
+            (posX, posY) = (rect.left, rect.top);
+            (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+        }
 
-        let exStyle: Win::WINDOW_EX_STYLE = match renderer {
-            "group-box" => Win::WS_EX_CONTROLPARENT,
-            //"panel" => Win::WS_EX_CLIENTEDGE,
-            "text-box" => Win::WS_EX_CLIENTEDGE,
-            _ => Default::default(),
+        let renderer: &str = &renderer;
+        let desc = ControlDesc {
+            renderer, className: &className, title: &title,
+            controlId: idx, anchor: dock,
+            posX, posY, width, height,
         };
 
-        let res = Win::GetClassInfoEx(format!("{}\0", className).as_str());
-        if res == None {
-            let atom = Win::RegisterClass(format!("{}\0", className).as_str());
-            debug_assert!(atom != 0);
-        }
-
         let mut hwnd = match hwnd {
             Some(hwnd) => hwnd,
             _ => {
-                let hwnd = Win::CreateWindowEx(
-                    exStyle, style, format!("{}\0", className).as_str(), parent, idx, &title,
-                    posX, posY, width, height,
-                    &(dock, idx, parent)
-                );
-                if &*renderer == "group-box" {
-                    Win::SetDefaultWindowProc(hwnd);
+                let hwnd = backend.createControl(parent, &desc);
+                if let Some(parent) = parent.filter(|_|placed.is_none()) {
+                    let am = AnchorMap::of(parent);
+                    let mut am = am.borrow_mut();
+                    am.addControl(backend, idx, dock, Some(hwnd));
+                    am.limitControl(hwnd, limits);
+                    am.handleAnchors(backend, None);
                 }
-                AnchorMap::shared().lock().as_mut().ok().and_then(|am|{
-                    am.addControl(idx, dock, Some(hwnd));
-                    am.handleAnchors(None)
-                });
 
                 hwnd
             }
         };
 
-        if let Some(oldClassName) = Win::GetClassName(hwnd) {
-            if oldClassName != &*className {
-                if Win::DestroyWindow(hwnd) {
-                    hwnd = Win::CreateWindowEx(
-                        exStyle, style, format!("{}\0", className).as_str(), parent, idx, &title,
-                        posX, posY, width, height,
-                        &(dock, idx, parent)
-                    );
+        let mut isCreated = controlInfo.is_none();
+        if let Some(oldClassName) = backend.getClassName(hwnd) {
+            if oldClassName != *className && backend.destroyControl(hwnd) {
+                hwnd = backend.createControl(parent, &desc);
+                isCreated = true;
+            }
+        }
+
+        let geometry = [posX, posY, width, height].map(|v|unscale(v, dpi));
+        if let Some(rect) = placed.filter(|rect|backend.getControlRect(hwnd) != Some(*rect)) {
+            backend.moveControl(hwnd, &rect);
+        } else if placed.is_none() && !isCreated && geometry != prevGeometry
+            && !geometry.contains(&CW_USEDEFAULT) && !prevGeometry.contains(&CW_USEDEFAULT) {
+            // shift by what the view changed, keep what the anchors changed
+            let prev = prevGeometry.map(|v|scale(v, dpi));
+            let (dx, dy) = (posX - prev[0], posY - prev[1]);
+            let (dw, dh) = (width - prev[2], height - prev[3]);
+            if let Some(rect) = backend.getControlRect(hwnd) {
+                let rect = Rect {
+                    left: rect.left + dx,
+                    top: rect.top + dy,
+                    right: rect.right + dx + dw,
+                    bottom: rect.bottom + dy + dh,
+                };
+                backend.moveControl(hwnd, &rect);
+                if let Some(am) = AnchorMap::find(backend.getParent(hwnd)) {
+                    am.borrow_mut().updateControl(hwnd, &rect);
                 }
             }
         }
 
-        let toString = |a:[Win::CHAR; 32]| String::from_iter(a.iter().filter(|Win::CHAR(a)|*a != 0).map(|Win::CHAR(a)|*a as char));
-        hFont = hFont.and_then(Win::GetFont).map(|f|f.lfFaceName).map(toString)
-            .filter(|f|f == &*fontFace).and(hFont)
-            .or_else(||{
-                Win::DeleteFont(hFont?);
-                None
-            });
-        hFont = hFont.or_else(||Win::SetWindowFontFace(hwnd, &fontFace));
-
-        if &*title != Win::GetWindowText(hwnd) {
-            Win::SetWindowText(hwnd, &title);
+        match parent {
+            Some(parent) => if let Some(am) = AnchorMap::find(parent) {
+                am.borrow_mut().limitControl(hwnd, limits);
+            },
+            _ => AnchorMap::of(hwnd).borrow_mut().setLimits(limits),
+        }
+
+        hFont = backend.updateFont(hwnd, hFont, &fontFace, fontSize);
+
+        if *title != backend.getTitle(hwnd) {
+            backend.setTitle(hwnd, &title);
         }
 
-        if isSelected != Win::IsSelected(hwnd) {
-            Win::MarkSelected(hwnd, isSelected);
+        if isSelected != backend.isSelected(hwnd) {
+            backend.setSelected(hwnd, isSelected);
         }
 
-        let oldSelectItems = Win::SelectGetItems(hwnd);
+        let oldSelectItems = backend.getSelectItems(hwnd);
         if oldSelectItems != selectItems {
-            Win::SelectSetItems(hwnd, &selectItems);
+            backend.setSelectItems(hwnd, &selectItems);
         }
 
-        let oldSelectedIndex = Win::SelectGetCurrentIndex(hwnd);
+        let oldSelectedIndex = backend.getSelectedIndex(hwnd);
         if let Some(oldSelectedIndex) = oldSelectedIndex {
             if selectedIndex != oldSelectedIndex {
-                Win::SelectSetCurrentIndex(hwnd, selectedIndex);
-            } else if let Some(currentText) = Win::SelectGetItemText(hwnd, selectedIndex) {
-                if &*title != currentText {
-                    Win::SelectSetCurrentIndex(hwnd, selectedIndex);
+                backend.setSelectedIndex(hwnd, selectedIndex);
+            } else if let Some(currentText) = backend.getSelectItemText(hwnd, selectedIndex) {
+                if *title != currentText {
+                    backend.setSelectedIndex(hwnd, selectedIndex);
                 }
             }
         }
 
-        if "window" == renderer && isInitialized == 2 {
-            AnchorMap::shared().lock().as_mut().ok().and_then(|am|am.initialize(
-                hwnd, ANF_TOP | ANF_LEFT | ANF_RIGHT
-            ));
-            isInitialized += 1;
-        } else if isInitialized > 2 {
+        // windows and containers get their children on the first render, the map takes their rects on the next one
+        if isInitialized >= 2 {
+            if let Some(am) = AnchorMap::find(hwnd) {
+                let mut am = am.borrow_mut();
+                if !am.isInitialized() {
+                    am.initialize(backend, hwnd, ANF_TOP | ANF_LEFT | ANF_RIGHT)
+                        .and_then(|_|am.handleAnchors(backend, None));
+                }
+            }
+        }
+        if isInitialized > 2 {
             isInitialized = 2;
         }
 
-        Some(ControlInfo { hwnd, hFont, isInitialized, listeners })
+        Some(ControlInfo { hwnd, hFont, isInitialized, listeners, renderer: String::from(renderer), anchor: dock, geometry })
     }
 }
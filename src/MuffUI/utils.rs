@@ -14,17 +14,25 @@ pub fn uniqId() -> usize {
 pub fn merge<L: Hash + Eq, R: Hash + Eq>(left: HashMap<L, R>, right: HashMap<L, R>) -> HashMap<L, R> {
     left.into_iter().chain(right).collect()
 }
+/// splits `amount` by `weights`, the rounding remainder goes to the last weighted part
+pub fn share(amount: i32, weights: &[i32]) -> Vec<i32> {
+    let total = weights.iter().map(|w|*w as i64).sum::<i64>();
+    if total == 0 {
+        return vec![0; weights.len()];
+    }
+
+    let mut res = weights.iter().map(|w|(amount as i64 * *w as i64 / total) as i32).collect::<Vec<_>>();
+    if let Some(last) = weights.iter().rposition(|w|*w > 0) {
+        res[last] += amount - res.iter().sum::<i32>();
+    }
+
+    res
+}
 #[allow(dead_code)]
 pub fn concat<T, C: Into<Vec<T>>>(left: Vec<T>, right: C) -> Vec<T> {
     left.into_iter().chain(right.into()).collect()
 }
 #[allow(dead_code)]
-pub fn prop<'a>(props: &'a Vec<SharedProps>, variant: &'a SharedProps) -> Option<&'a SharedProps> {
-    props.iter().find_map(|d|{
-        if std::mem::discriminant(d) == std::mem::discriminant(variant) {
-            Some(d)
-        } else {
-            None
-        }
-    })
+pub fn prop<'a>(props: &'a [SharedProps], variant: &'a SharedProps) -> Option<&'a SharedProps> {
+    props.iter().find(|d|std::mem::discriminant(*d) == std::mem::discriminant(variant))
 }
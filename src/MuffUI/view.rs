@@ -0,0 +1,140 @@
+/// declarative form of the component tree, expands to the `new`/`content` constructors and `SP` props
+///
+/// ```ignore
+/// view! {
+///     Panel(ControlId: 203, Anchor: ANF_TOPBOTTOM | ANF_LEFTRIGHT, PosX: 4, PosY: 40) {
+///         Label(Title: "Todos:"),
+///         if (items.is_empty()) {
+///             Label(Title: "nothing to do"),
+///         } else {
+///             Button(Title: "Clear", DidClick: move|_|vm.clear()),
+///         },
+///         for ((id, name, _), index in items, key = |(id, _, _)|*id) {
+///             TextBox(Title: name.as_str(), PosY: 20 + index * 29, DidChange: move|_|vm.update(id))
+///         },
+///     }
+/// }
+/// ```
+///
+/// * `Name: value` becomes `SP::Name(value)`, event handlers are attributes too (`DidClick`, `DidChange`, ...)
+/// * `{ ... }` after an element lists the children of a container
+/// * `if (cond) { ... } else { ... }` renders one of the branches, without `else` nothing is rendered
+/// * `for (pattern, index in items, key = |item|...) { element }` is a `ForEach`, `index` and `key` are optional
+#[macro_export]
+macro_rules! view {
+    (@index) => { _ };
+    (@index $index:ident) => { $index };
+
+    (@element $ty:ident ( $($attr:ident : $value:expr),* $(,)? ) { $($children:tt)* }) => {
+        $ty::new([$($crate::muffui::SP::$attr($value)),*])
+            .content(||$crate::view!(@children [] $($children)*))
+    };
+    (@element $ty:ident ( $($attr:ident : $value:expr),* $(,)? )) => {
+        $ty::new([$($crate::muffui::SP::$attr($value)),*])
+    };
+
+    (@children [$($out:expr),*]) => {{
+        let children: Vec<::std::rc::Rc<dyn $crate::muffui::Renderable>> = vec![$($out),*];
+        children
+    }};
+    (@children [$($out:expr),*] if ($cond:expr) { $($then:tt)* } else { $($else:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::view!(@children [$($out,)* {
+            let child: ::std::rc::Rc<dyn $crate::muffui::Renderable> = if $cond {
+                ::std::rc::Rc::new($crate::muffui::IntoChildren::intoChildren($crate::view!(@children [] $($then)*)))
+            } else {
+                ::std::rc::Rc::new($crate::muffui::IntoChildren::intoChildren($crate::view!(@children [] $($else)*)))
+            };
+            child
+        }] $($($rest)*)?)
+    };
+    (@children [$($out:expr),*] if ($cond:expr) { $($then:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::view!(@children [$($out),*] if ($cond) { $($then)* } else { } $(, $($rest)*)?)
+    };
+    (@children [$($out:expr),*] for ($pat:pat $(, $index:ident)? in $items:expr, key = $key:expr) { $($body:tt)+ } $(, $($rest:tt)*)?) => {
+        $crate::view!(@children [$($out,)* ::std::rc::Rc::new($crate::muffui::ForEach::keyed(
+            ::std::iter::IntoIterator::into_iter($items).collect::<Vec<_>>(),
+            $key,
+            |$pat, $crate::view!(@index $($index)?): i32|$crate::view!(@element $($body)+)
+        ))] $($($rest)*)?)
+    };
+    (@children [$($out:expr),*] for ($pat:pat $(, $index:ident)? in $items:expr) { $($body:tt)+ } $(, $($rest:tt)*)?) => {
+        $crate::view!(@children [$($out,)* ::std::rc::Rc::new($crate::muffui::ForEach::new(
+            ::std::iter::IntoIterator::into_iter($items).collect::<Vec<_>>(),
+            |$pat, $crate::view!(@index $($index)?): i32|$crate::view!(@element $($body)+)
+        ))] $($($rest)*)?)
+    };
+    (@children [$($out:expr),*] $ty:ident ( $($attrs:tt)* ) { $($children:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::view!(@children [$($out,)* ::std::rc::Rc::new($crate::view!(@element $ty ( $($attrs)* ) { $($children)* }))] $($($rest)*)?)
+    };
+    (@children [$($out:expr),*] $ty:ident ( $($attrs:tt)* ) $(, $($rest:tt)*)?) => {
+        $crate::view!(@children [$($out,)* ::std::rc::Rc::new($crate::view!(@element $ty ( $($attrs)* )))] $($($rest)*)?)
+    };
+
+    ($ty:ident ( $($attrs:tt)* ) $({ $($children:tt)* })?) => {
+        $crate::view!(@element $ty ( $($attrs)* ) $({ $($children)* })?)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::muffui::*;
+
+    fn render<T: Renderable>(view: T) -> Vec<(String, String)> {
+        let backend = Rc::new(HeadlessBackend::new());
+        let context = view.render(UIContext::create(backend.clone()), "/", "0", None);
+        let mut res = context.items.iter()
+            .map(|(path, ci)|(path.clone(), backend.getTitle(ci.hwnd)))
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    fn titles(done: bool, items: Vec<&str>) -> Vec<(String, String)> {
+        render(view! {
+            Panel(Title: "root") {
+                if (done) {
+                    Label(Title: "done"),
+                } else {
+                    Label(Title: "pending"),
+                    Label(Title: "more"),
+                },
+                if (!done) {
+                    Label(Title: "not done"),
+                },
+                for (title, index in items.clone()) {
+                    Label(Title: title, PosY: index * 20)
+                },
+                Button(Title: "last", DidClick: |_|{}),
+            }
+        })
+    }
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items.iter().map(|(p, t)|(p.to_string(), t.to_string())).collect()
+    }
+
+    #[test]
+    fn it_renders_conditional_children() {
+        assert_eq!(titles(true, vec![]), pairs(&[("0", "root"), ("0:1_1_1", "done"), ("0:1_4", "last")]));
+        assert_eq!(titles(false, vec![]), pairs(&[
+            ("0", "root"), ("0:1_1_1", "pending"), ("0:1_1_2", "more"), ("0:1_2_1", "not done"), ("0:1_4", "last"),
+        ]));
+    }
+
+    #[test]
+    fn it_renders_for_blocks_as_for_each() {
+        assert_eq!(titles(true, vec!["a", "b"]), pairs(&[
+            ("0", "root"), ("0:1_1_1", "done"), ("0:1_3[0]", "a"), ("0:1_3[1]", "b"), ("0:1_4", "last"),
+        ]));
+
+        let keyed = render(view! {
+            Panel() {
+                for ((id, title) in vec![(7, "a"), (9, "b")], key = |(id, _)|*id) {
+                    Label(Title: title, ControlId: id)
+                },
+            }
+        });
+        assert_eq!(keyed, pairs(&[("0", ""), ("0:1_1[7]", "a"), ("0:1_1[9]", "b")]));
+    }
+}
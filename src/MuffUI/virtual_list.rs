@@ -0,0 +1,297 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+use std::ops::Range;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use crate::muffui::*;
+
+/// top of every row and the bottom of the last one
+#[allow(dead_code)]
+pub fn rowOffsets(heights: &[i32]) -> Vec<i32> {
+    let mut res = Vec::with_capacity(heights.len() + 1);
+    res.push(0);
+    for height in heights {
+        res.push(res[res.len() - 1] + height);
+    }
+
+    res
+}
+
+/// rows of `rowOffsets` a viewport of `height` pixels shows when scrolled to `top`
+#[allow(dead_code)]
+pub fn visibleRows(offsets: &[i32], top: i32, height: i32) -> Range<usize> {
+    let count = offsets.len().saturating_sub(1);
+    if count == 0 {
+        return 0..0;
+    }
+    let start = offsets[1..].partition_point(|bottom|*bottom <= top);
+    let end = offsets[..count].partition_point(|rowTop|*rowTop < top + height);
+
+    start..end.max(start)
+}
+
+/// most rows a viewport of `height` pixels can show at once, the controls of the list are recycled between them
+#[allow(dead_code)]
+pub fn slotCount(heights: &[i32], height: i32) -> usize {
+    let smallest = heights.iter().copied().filter(|h|*h > 0).min().unwrap_or(1);
+    let slots = (height.max(0) + smallest - 1) / smallest + 1;
+
+    (slots as usize).min(heights.len()).max(1)
+}
+
+/// heights of the rows of the virtual lists by row index, measured when the rows were last shown
+pub struct RowHeights;
+
+impl RowHeights {
+    fn registry() -> &'static RefCell<HashMap<Handle, Vec<Option<i32>>>> {
+        thread_local! {
+            static CONF: &'static RefCell<HashMap<Handle, Vec<Option<i32>>>> = Box::leak(Box::new(RefCell::new(HashMap::new())));
+        }
+
+        CONF.with(|conf|*conf)
+    }
+
+    pub fn release(hwnd: Handle) {
+        Self::registry().borrow_mut().remove(&hwnd);
+    }
+}
+
+/// renders the item at an index into a row
+pub type RowFn<A, T> = Rc<dyn Fn(&A, i32) -> T>;
+
+/// list that creates controls only for the rows in its viewport and scrolls over the rest,
+/// rows are RowHeight high or measured from the control they render
+#[allow(dead_code)]
+pub struct VirtualList<A, T: Renderable> {
+    pub items: Vec<A>,
+    pub row: RowFn<A, T>,
+    pub props: Vec<SharedProps>,
+}
+
+impl<A, T: Renderable> Renderable for VirtualList<A, T> {
+    fn childs(&self) -> Option<Rc<dyn Renderable>> {
+        None
+    }
+
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
+        let mut context = context.render(parent, index, self, msg);
+        let hwnd = match context.items.get(index) {
+            Some(ci) => ci.hwnd,
+            _ => return context,
+        };
+
+        let backend = context.backend.clone();
+        let dpi = context.dpi(index);
+        let props = scaleProps(&self.props, dpi);
+        let viewport = context.clientSize(index);
+        let top = ScrollState::find(hwnd).map(|s|s.offset.y)
+            .or_else(||declaredOffset(&props).map(|offset|offset.y))
+            .unwrap_or_default();
+        let heights = self.rowHeights(&*backend, hwnd, &props, dpi, top, viewport.cy);
+        let offsets = rowOffsets(&heights);
+        // the offset has already moved when a scroll message is handled
+        let state = match msg {
+            Some(_) => ScrollState::find(hwnd).unwrap_or_default(),
+            _ => {
+                let line = match props.prop(&SP::RowHeight(0)) {
+                    Some(SharedProps::RowHeight(height)) => *height,
+                    _ => scale(SCROLL_LINE, dpi),
+                };
+                let content = Size { cx: viewport.cx, cy: offsets[offsets.len() - 1] };
+                ScrollState::update(&*backend, hwnd, content, viewport, line, declaredOffset(&props))
+            },
+        };
+
+        // a row takes the control of the slot it lands in, whatever row had it before
+        let slots = slotCount(&heights, viewport.cy);
+        let childIndex = format!("{}:1", index);
+        let rows = visibleRows(&offsets, state.offset.y, viewport.cy)
+            .map(|idx|(format!("{}_1[{}]", childIndex, idx % slots), idx))
+            .collect::<Vec<_>>();
+        if msg.is_none() {
+            let top = |idx: usize| offsets[idx] - state.offset.y;
+            context.place(&childIndex, rows.iter().map(|(path, idx)|(path.clone(), Rect {
+                left: 0, top: top(*idx), right: viewport.cx, bottom: top(*idx + 1),
+            })));
+        }
+
+        rows.into_iter().fold(context, |context, (path, idx)|{
+            (self.row)(&self.items[idx], idx as i32).render(context, index, &path, msg)
+        })
+    }
+
+    fn toViewState(&self) -> Vec<SharedProps> {
+        self.props.clone()
+    }
+}
+
+#[allow(dead_code)]
+impl<A, T: Renderable> VirtualList<A, T> {
+    pub fn new<P: Into<Vec<SharedProps>>>(props: P, items: Vec<A>, row: impl Fn(&A, i32) -> T + 'static) -> Self {
+        let defaultProps = vec![
+            SP::ClassName("panel#0x8002"),
+            SP::Renderer("virtual-list"),
+        ];
+        Self {
+            items,
+            row: Rc::new(row),
+            props: defaultProps.merge(props.into()),
+        }
+    }
+
+    /// heights of the rows in pixels, building a row to measure it doesn't create its controls.
+    /// only the rows a viewport at `top` shows are measured, the others keep the height they were shown with
+    /// or take the average of the measured ones
+    fn rowHeights(&self, backend: &dyn Backend, hwnd: Handle, props: &Vec<SharedProps>, dpi: u32, top: i32, height: i32) -> Vec<i32> {
+        if let Some(SharedProps::RowHeight(height)) = props.prop(&SP::RowHeight(0)) {
+            return vec![(*height).max(0); self.items.len()];
+        }
+
+        let measureRow = |idx: usize| {
+            let props = (self.row)(&self.items[idx], idx as i32).layoutItems("").into_iter().next()
+                .map(|(_, props)|measure(backend, &props, dpi))
+                .unwrap_or_default();
+            match props.prop(&SP::Height(0)) {
+                Some(SharedProps::Height(height)) if *height != CW_USEDEFAULT => (*height).max(0),
+                _ => 0,
+            }
+        };
+        let mut known = RowHeights::registry().borrow_mut().remove(&hwnd).unwrap_or_default();
+        known.resize(self.items.len(), None);
+        // rows shown now are measured again, their items could have changed since
+        let mut fresh = HashSet::new();
+        if !known.is_empty() && known.iter().all(|h|h.is_none()) {
+            known[0] = Some(measureRow(0));
+            fresh.insert(0);
+        }
+
+        let heights = loop {
+            let measured = known.iter().flatten().collect::<Vec<_>>();
+            let estimate = measured.iter().copied().sum::<i32>() / (measured.len().max(1) as i32);
+            let heights = known.iter().map(|h|h.unwrap_or(estimate)).collect::<Vec<_>>();
+            let pending = visibleRows(&rowOffsets(&heights), top, height).filter(|idx|!fresh.contains(idx)).collect::<Vec<_>>();
+            if pending.is_empty() {
+                break heights;
+            }
+            for idx in pending {
+                known[idx] = Some(measureRow(idx));
+                fresh.insert(idx);
+            }
+        };
+        RowHeights::registry().borrow_mut().insert(hwnd, known);
+
+        heights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_rows_in_viewport() {
+        let offsets = rowOffsets(&[20, 20, 40, 20, 20]);
+        assert_eq!(offsets, vec![0, 20, 40, 80, 100, 120]);
+
+        assert_eq!(visibleRows(&offsets, 0, 30), 0..2);
+        assert_eq!(visibleRows(&offsets, 20, 20), 1..2);
+        assert_eq!(visibleRows(&offsets, 50, 40), 2..4);
+        assert_eq!(visibleRows(&offsets, 110, 100), 4..5);
+        assert_eq!(visibleRows(&rowOffsets(&[]), 0, 100), 0..0);
+
+        assert_eq!(slotCount(&[20; 1000], 100), 6);
+        assert_eq!(slotCount(&[20, 40], 100), 2);
+        assert_eq!(slotCount(&[0, 0], 100), 2);
+    }
+
+    #[test]
+    fn it_realizes_only_visible_rows() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = VirtualList::new([SP::Width(200), SP::Height(100), SP::RowHeight(20)], (0..10_000).collect(), |i: &i32, _|
+            Label::new([SP::ControlId(*i), SP::Title(&format!("row {}", i))])
+        );
+        let titles = |context: &UIContext| {
+            let mut rows = context.items.iter().filter(|(path, _)|path.as_str() != "0")
+                .map(|(path, ci)|{
+                    let node = backend.node(ci.hwnd).unwrap();
+                    (node.rect.top, node.title, path.clone())
+                })
+                .collect::<Vec<_>>();
+            rows.sort();
+            rows
+        };
+
+        let context = UIContext::create(backend.clone()).update(&view);
+        let hwnd = context.items["0"].hwnd;
+        let rows = titles(&context);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0], (0, String::from("row 0"), String::from("0:1_1[0]")));
+        assert_eq!(backend.node(hwnd).unwrap().scroll.0, Size { cx: 200, cy: 200_000 });
+
+        // scrolled a row and a half down, the control of row 0 shows row 6 below the others
+        let row0 = context.items["0:1_1[0]"].hwnd;
+        backend.postMessage(Message { hwnd, message: WM_VSCROLL, wParam: 30 << 16 | SB_THUMBTRACK as usize, ..Default::default() });
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+        let context = context.update(&view);
+        let rows = titles(&context);
+        assert_eq!(rows.iter().map(|(top, title, _)|(*top, title.as_str())).collect::<Vec<_>>(), vec![
+            (-10, "row 1"), (10, "row 2"), (30, "row 3"), (50, "row 4"), (70, "row 5"), (90, "row 6"),
+        ]);
+        assert_eq!(backend.node(row0).unwrap().title, "row 6");
+        assert_eq!(backend.nodes().len(), 1 + 6);
+
+        // the far end needs no more controls
+        backend.postMessage(Message { hwnd, message: WM_VSCROLL, wParam: SB_BOTTOM as usize, ..Default::default() });
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+        let context = context.update(&view);
+        assert_eq!(titles(&context).last().map(|(top, title, _)|(*top, title.clone())), Some((80, String::from("row 9999"))));
+        assert_eq!(backend.nodes().len(), 1 + 5);
+    }
+
+    #[test]
+    fn it_measures_rows_without_row_height() {
+        let backend = Rc::new(HeadlessBackend::new());
+        let view = VirtualList::new([SP::Width(200), SP::Height(100)], vec!["a", "b", "c"], |title: &&str, i|
+            Button::new([SP::Title(title), SP::Height(if i == 1 { 50 } else { CW_USEDEFAULT })])
+        );
+
+        let context = UIContext::create(backend.clone()).update(&view);
+        let rect = |path: &str| backend.node(context.items[path].hwnd).unwrap().rect;
+        assert_eq!((rect("0:1_1[0]").top, rect("0:1_1[0]").bottom), (0, 24));
+        assert_eq!((rect("0:1_1[1]").top, rect("0:1_1[1]").bottom), (24, 74));
+        assert_eq!(rect("0:1_1[2]").top, 74);
+        assert_eq!(ScrollState::find(context.items["0"].hwnd).unwrap().content.cy, 98);
+    }
+
+    #[test]
+    fn it_builds_only_shown_rows_without_row_height() {
+        use std::cell::Cell;
+
+        let backend = Rc::new(HeadlessBackend::new());
+        let calls = Rc::new(Cell::new(0));
+        let view = VirtualList::new([SP::Width(200), SP::Height(100)], (0..10_000).collect(), {
+            let calls = calls.clone();
+            move |i: &i32, _| {
+                calls.set(calls.get() + 1);
+                Label::new([SP::Title(&format!("row {}", i)), SP::Height(20)])
+            }
+        });
+
+        // a row is built once to measure it and once to render it
+        let context = UIContext::create(backend.clone()).update(&view);
+        let hwnd = context.items["0"].hwnd;
+        assert!(calls.get() <= 2 * 6, "built {} rows", calls.get());
+        assert_eq!(ScrollState::find(hwnd).unwrap().content.cy, 200_000);
+
+        calls.set(0);
+        backend.postMessage(Message { hwnd, message: WM_VSCROLL, wParam: 50_000 << 16 | SB_THUMBTRACK as usize, ..Default::default() });
+        backend.dispatchMessage(&backend.peekMessage().unwrap());
+        let context = context.update(&view);
+        assert!(calls.get() <= 2 * 6, "built {} rows", calls.get());
+        let row = context.items.iter().find(|(path, _)|path.as_str() != "0")
+            .map(|(_, ci)|backend.node(ci.hwnd).unwrap())
+            .unwrap();
+        assert_eq!(row.rect.bottom - row.rect.top, 20);
+    }
+}
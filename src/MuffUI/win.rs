@@ -13,9 +13,25 @@ pub use windows::{
     Win32::Graphics::Gdi::ValidateRect,
     Win32::Graphics::Gdi::EndPaint,
     Win32::Graphics::Gdi::BeginPaint,
+    Win32::Graphics::Gdi::GetDCEx,
+    Win32::Graphics::Gdi::DCX_CACHE,
+    Win32::Graphics::Gdi::CreateSolidBrush,
+    Win32::Graphics::Gdi::FrameRect,
+    Win32::Graphics::Gdi::DeleteObject,
+    Win32::Graphics::Gdi::ReleaseDC,
     Win32::System::LibraryLoader::GetModuleHandleA,
     Win32::UI::WindowsAndMessaging::*,
     Win32::UI::Controls::*,
+    Win32::UI::Input::KeyboardAndMouse::SetCapture,
+    Win32::UI::Input::KeyboardAndMouse::ReleaseCapture,
+    Win32::UI::Input::KeyboardAndMouse::GetKeyState,
+    Win32::UI::Input::KeyboardAndMouse::TrackMouseEvent,
+    Win32::UI::Input::KeyboardAndMouse::TRACKMOUSEEVENT,
+    Win32::UI::Input::KeyboardAndMouse::TME_LEAVE,
+    Win32::UI::Input::KeyboardAndMouse::VK_SHIFT,
+    Win32::UI::Input::KeyboardAndMouse::VK_CONTROL,
+    Win32::UI::Input::KeyboardAndMouse::VK_MENU,
+    Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY,
     Win32::System::Diagnostics::Debug::FormatMessageA,
     Win32::System::Diagnostics::Debug::FORMAT_MESSAGE_IGNORE_INSERTS,
     Win32::System::Diagnostics::Debug::FORMAT_MESSAGE_FROM_SYSTEM,
@@ -24,12 +40,19 @@ pub use windows::{
 use crate::AnchorMap;
 use crate::Notifier;
 use crate::NotifierExt;
+use crate::ScrollState;
+use crate::isRoutedByLoop;
+use crate::SplitState;
+use crate::Win32Backend;
 
+// named after the win32 types they stand for
+#[allow(clippy::upper_case_acronyms)]
 pub type MSG = windows::Win32::UI::WindowsAndMessaging::MSG;
 #[allow(dead_code)]
 pub static ES_AUTOHSCROLL: i32 = windows::Win32::UI::WindowsAndMessaging::ES_AUTOHSCROLL;
 pub static WS_VSCROLL: WINDOW_STYLE = windows::Win32::UI::WindowsAndMessaging::WS_VSCROLL;
 
+#[allow(clippy::upper_case_acronyms)]
 pub type ATOM = u16;
 
 #[allow(dead_code)]
@@ -37,6 +60,7 @@ pub type ATOM = u16;
 pub fn LOWORD(l: usize) -> usize {
     l & 0xffff
 }
+#[allow(dead_code)]
 #[inline]
 pub fn HIWORD(l: usize) -> usize {
     (l >> 16) & 0xffff
@@ -80,7 +104,6 @@ pub fn RegisterClass(className: &str) -> ATOM {
         hIcon: HICON(0),
         hbrBackground: HBRUSH(5),
         lpszMenuName: PSTR(std::ptr::null_mut()),
-        ..Default::default()
     };
 
     unsafe {
@@ -99,6 +122,23 @@ extern "system" fn wndproc(hwnd: HWND, message: u32, wParam: WPARAM, lParam: LPA
     unsafe {
         match message as u32 {
             WM_GETMINMAXINFO => {
+                let info: &mut MINMAXINFO = {
+                    let LPARAM(lParam) = lParam;
+                    &mut *(lParam as *mut MINMAXINFO)
+                };
+                let limits = AnchorMap::windowLimits(&Win32Backend, hwnd.into());
+                if limits.minWidth > 0 {
+                    info.ptMinTrackSize.x = limits.minWidth;
+                }
+                if limits.minHeight > 0 {
+                    info.ptMinTrackSize.y = limits.minHeight;
+                }
+                if limits.maxWidth < i32::MAX {
+                    info.ptMaxTrackSize.x = limits.maxWidth;
+                }
+                if limits.maxHeight < i32::MAX {
+                    info.ptMaxTrackSize.y = limits.maxHeight;
+                }
             },
             WM_NCCREATE => {
                 let param: &CREATESTRUCTA = unsafe {
@@ -133,14 +173,60 @@ extern "system" fn wndproc(hwnd: HWND, message: u32, wParam: WPARAM, lParam: LPA
                 EndPaint(hwnd, &ps);
             },
             WM_SIZE | WM_SIZING => {
-                AnchorMap::shared().try_lock().as_mut().ok().and_then(|am|am.handleAnchors(None));
+                AnchorMap::layout(&Win32Backend, hwnd.into());
+            },
+            WM_HSCROLL | WM_VSCROLL | WM_MOUSEWHEEL => {
+                let msg = MSG { hwnd, message, wParam, lParam, ..Default::default() }.into();
+                if ScrollState::scroll(&Win32Backend, &msg).is_some() {
+                    // the wheel stops at the first scroll view instead of going on to its parent
+                    if !isRoutedByLoop(&msg) {
+                        if let Some(e) = Arc::get_mut(Notifier::shared()) {
+                            e.notify(Some(msg));
+                        }
+                    }
+                    return LRESULT(0);
+                }
+            },
+            WM_LBUTTONDOWN | WM_MOUSEMOVE | WM_LBUTTONUP => {
+                // the panes cover a split but its divider, the mouse stays with the divider while it is dragged
+                let msg = MSG { hwnd, message, wParam, lParam, ..Default::default() }.into();
+                match SplitState::drag(&Win32Backend, &msg).map(|state|state.grab.is_some()) {
+                    Some(true) if message == WM_LBUTTONDOWN => { SetCapture(hwnd); },
+                    Some(false) if message == WM_LBUTTONUP => { ReleaseCapture(); },
+                    _ => {},
+                }
+            },
+            WM_SETCURSOR if LOWORD(lParam.0 as usize) as u32 == HTCLIENT => {
+                if let Some(state) = SplitState::find(hwnd.into()) {
+                    let cursor = match state.style.direction {
+                        crate::muffui::Direction::Row => IDC_SIZEWE,
+                        _ => IDC_SIZENS,
+                    };
+                    SetCursor(LoadCursorW(None, cursor));
+                    return LRESULT(1);
+                }
+            },
+            WM_DPICHANGED => {
+                // maps first, so the WM_SIZE of the suggested rect lays the children out at the new dpi
+                let dpi = HIWORD(wParam.0) as u32;
+                AnchorMap::changeDpi(&Win32Backend, hwnd.into(), dpi);
+                let rect: &RECT = {
+                    let LPARAM(lParam) = lParam;
+                    &*(lParam as *const RECT)
+                };
+                SetWindowPos(hwnd, HWND(0), rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top,
+                    SWP_NOZORDER | SWP_NOACTIVATE);
             },
             _ => {},
         }
 
-        Arc::get_mut(Notifier::shared()).map(|e|e.notify(
-            Some(MSG { hwnd, message, wParam, lParam, time: 0, pt: POINT { x:0, y:0 } })
-        ));
+        // the app loop notifies the messages it dispatches by itself, the sent ones come only here
+        let msg = MSG { hwnd, message, wParam, lParam, time: 0, pt: POINT { x:0, y:0 } }.into();
+        if !isRoutedByLoop(&msg) {
+            if let Some(e) = Arc::get_mut(Notifier::shared()) {
+                e.notify(Some(msg));
+            }
+        }
 
         DefWindowProcA(hwnd, message, wParam, lParam)
     }
@@ -149,9 +235,12 @@ extern "system" fn wndproc(hwnd: HWND, message: u32, wParam: WPARAM, lParam: LPA
 pub fn SetDefaultWindowProc(hwnd: HWND) -> bool {
     extern "system" fn customWinProc(hwnd: HWND, message: u32, wParam: WPARAM, lParam: LPARAM, _uidsubclass: usize, _dwrefdata: usize) -> LRESULT {
         unsafe {
-            Arc::get_mut(Notifier::shared()).map(|e|e.notify(
-                Some(MSG { hwnd, message, wParam, lParam, time: 0, pt: POINT { x:0, y:0 } })
-            ));
+            let msg = MSG { hwnd, message, wParam, lParam, time: 0, pt: POINT { x:0, y:0 } }.into();
+            if !isRoutedByLoop(&msg) {
+                if let Some(e) = Arc::get_mut(Notifier::shared()) {
+                    e.notify(Some(msg));
+                }
+            }
 
             windows::Win32::UI::Shell::DefSubclassProc(hwnd, message, wParam, lParam)
         }
@@ -202,6 +291,22 @@ pub fn GetMessage() -> Option<MSG> {
     }
 }
 
+/// waits until the queue of the thread gets a message or `timeout` has passed, false on timeout
+pub fn WaitMessage(timeout: Option<std::time::Duration>) -> bool {
+    let milliseconds = timeout.map(|t|t.as_millis().min(u32::MAX as u128 - 1) as u32).unwrap_or(u32::MAX);
+    unsafe {
+        MsgWaitForMultipleObjects(0, std::ptr::null(), false, milliseconds, QS_ALLINPUT) != WAIT_TIMEOUT
+    }
+}
+
+pub fn GetCurrentThreadId() -> u32 {
+    unsafe { windows::Win32::System::Threading::GetCurrentThreadId() }
+}
+
+pub fn PostThreadMessage(threadId: u32, message: u32, wParam: usize, lParam: isize) -> bool {
+    unsafe { PostThreadMessageA(threadId, message, WPARAM(wParam), LPARAM(lParam)).into() }
+}
+
 pub fn PeekMessage() -> Option<MSG> {
     let mut message = MSG::default();
 
@@ -296,8 +401,8 @@ static mut GLOBALFONT: Option<windows::Win32::Graphics::Gdi::HFONT> = None;
 
 pub fn GetSystemFont() -> Option<windows::Win32::Graphics::Gdi::HFONT> {
     unsafe {
-        if let Some(_) = GLOBALFONT {
-            return GLOBALFONT;
+        if let Some(font) = GLOBALFONT {
+            return Some(font);
         }
         let mut ncm = windows::Win32::UI::WindowsAndMessaging::NONCLIENTMETRICSA::default();
         ncm.cbSize = std::mem::size_of::<NONCLIENTMETRICSA>() as _;
@@ -358,7 +463,13 @@ pub fn GetWindowFontFace(hwnd: HWND) -> Result<String, String> {
     }
 }
 
-pub fn SetWindowFontFace(hwnd: HWND, fontFace: &str) -> Option<HFONT> {
+/// height of the system font in pixels at the system dpi
+pub fn GetSystemFontSize() -> i32 {
+    GetSystemFont().and_then(GetFont).map(|f|f.lfHeight.abs()).unwrap_or(16)
+}
+
+/// system font with another face and a character height of `fontSize` pixels
+fn GetFontWithFace(fontFace: &str, fontSize: i32) -> Option<windows::Win32::Graphics::Gdi::LOGFONTA> {
     let hFont = GetSystemFont()?;
     let mut fontInfo = GetFont(hFont)?;
 
@@ -366,6 +477,40 @@ pub fn SetWindowFontFace(hwnd: HWND, fontFace: &str) -> Option<HFONT> {
     let mut b = [CHAR(0); 32];
     b[..a.len()].copy_from_slice(&a);
     fontInfo.lfFaceName = b;
+    if fontSize > 0 {
+        fontInfo.lfHeight = -fontSize;
+        fontInfo.lfWidth = 0;
+    }
+
+    Some(fontInfo)
+}
+
+/// lets windows on every monitor get their own dpi instead of being stretched by the system
+pub fn SetPerMonitorDpiAware() -> bool {
+    unsafe {
+        windows::Win32::UI::HiDpi::SetProcessDpiAwarenessContext(
+            windows::Win32::UI::HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2
+        ) == true
+    }
+}
+
+pub fn GetDpiForSystem() -> u32 {
+    unsafe {
+        windows::Win32::UI::HiDpi::GetDpiForSystem()
+    }
+}
+
+pub fn GetDpiForWindow(hwnd: HWND) -> u32 {
+    unsafe {
+        match windows::Win32::UI::HiDpi::GetDpiForWindow(hwnd) {
+            0 => GetDpiForSystem(),
+            dpi => dpi,
+        }
+    }
+}
+
+pub fn SetWindowFontFace(hwnd: HWND, fontFace: &str, fontSize: i32) -> Option<HFONT> {
+    let fontInfo = GetFontWithFace(fontFace, fontSize)?;
 
     unsafe {
         let windowHFont = windows::Win32::Graphics::Gdi::CreateFontIndirectA(&fontInfo);
@@ -376,6 +521,84 @@ pub fn SetWindowFontFace(hwnd: HWND, fontFace: &str) -> Option<HFONT> {
     }
 }
 
+/// extent of `text` drawn on the screen with the system font in `fontFace` of `fontSize` pixels
+pub fn MeasureText(text: &str, fontFace: &str, fontSize: i32) -> Option<SIZE> {
+    let fontInfo = GetFontWithFace(fontFace, fontSize)?;
+    // an empty line still has the height of the font
+    let line = format!("{}\0", if text.is_empty() { " " } else { text });
+    let mut size = SIZE::default();
+
+    unsafe {
+        let hFont = windows::Win32::Graphics::Gdi::CreateFontIndirectA(&fontInfo);
+        let hdc = windows::Win32::Graphics::Gdi::GetDC(HWND(0));
+        let prevFont = windows::Win32::Graphics::Gdi::SelectObject(hdc, hFont);
+        let res = windows::Win32::Graphics::Gdi::GetTextExtentPoint32A(
+            hdc, PSTR(line.as_ptr() as _), line.len() as i32 - 1, &mut size,
+        );
+        windows::Win32::Graphics::Gdi::SelectObject(hdc, prevFont);
+        windows::Win32::Graphics::Gdi::ReleaseDC(HWND(0), hdc);
+        DeleteFont(hFont);
+
+        if res != true {
+            return None;
+        }
+    }
+
+    if text.is_empty() {
+        size.cx = 0;
+    }
+
+    Some(size)
+}
+
+/// range of a scroll bar from 0 to `content`, `page` of it visible from `pos`, hidden when everything is visible
+pub fn SetScrollBar(hwnd: HWND, bar: SCROLLBAR_CONSTANTS, content: i32, page: i32, pos: i32) -> i32 {
+    let info = SCROLLINFO {
+        cbSize: std::mem::size_of::<SCROLLINFO>() as _,
+        fMask: SIF_RANGE | SIF_PAGE | SIF_POS,
+        nMin: 0,
+        nMax: (content - 1).max(0),
+        nPage: page.max(0) as u32,
+        nPos: pos,
+        nTrackPos: 0,
+    };
+    unsafe {
+        SetScrollInfo(hwnd, bar, &info, true)
+    }
+}
+
+/// red frames over the children of `hwnd`, the device context doesn't clip them away
+pub fn FrameRects(hwnd: HWND, rects: &[RECT]) {
+    unsafe {
+        let hdc = GetDCEx(hwnd, None, DCX_CACHE);
+        let brush = CreateSolidBrush(0x000000FF);
+        for rect in rects {
+            FrameRect(hdc, rect, brush);
+        }
+        DeleteObject(brush);
+        ReleaseDC(hwnd, hdc);
+    }
+}
+
+/// posts WM_MOUSELEAVE to `hwnd` once the mouse leaves it
+pub fn TrackMouseLeave(hwnd: HWND) -> bool {
+    let mut track = TRACKMOUSEEVENT {
+        cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as _,
+        dwFlags: TME_LEAVE,
+        hwndTrack: hwnd,
+        dwHoverTime: 0,
+    };
+    unsafe {
+        TrackMouseEvent(&mut track) == true
+    }
+}
+
+/// Shift, Ctrl and Alt of the message being handled
+pub fn GetModifiers() -> crate::muffui::Modifiers {
+    let isDown = |key: VIRTUAL_KEY| unsafe { GetKeyState(key as i32) } < 0;
+    crate::muffui::Modifiers { shift: isDown(VK_SHIFT), control: isDown(VK_CONTROL), alt: isDown(VK_MENU) }
+}
+
 pub fn SetWindowFont(hwnd: HWND, hFont: HFONT) -> LRESULT {
     let HFONT(hFont) = hFont;
     unsafe {
@@ -475,7 +698,7 @@ pub fn SendMessage(hwnd: HWND, msg: u32, wParam: WPARAM, lParam: LPARAM) -> LRES
 }
 
 pub fn ScreenToClient(hwnd: HWND, point: &POINT) -> Option<POINT> {
-    let mut pt = point.clone();
+    let mut pt = *point;
     unsafe {
         if windows::Win32::Graphics::Gdi::ScreenToClient(hwnd, &mut pt) == true {
             Some(pt)
@@ -535,7 +758,7 @@ pub fn DeferWindowPos(posInfo: isize, hwnd: HWND, insertAfter: HWND, posX: i32,
         )
     }
 }
-
+#[allow(dead_code)]
 pub fn GetDlgItem(parent: HWND, controlId: i32) -> Option<HWND> {
     unsafe {
         let res = windows::Win32::UI::WindowsAndMessaging::GetDlgItem(parent, controlId);
@@ -624,7 +847,7 @@ pub fn SelectGetItemsCount(hwnd: HWND) -> isize {
     count
 }
 
-pub fn SelectSetItems(hwnd: HWND, items: &Vec<String>) {
+pub fn SelectSetItems(hwnd: HWND, items: &[String]) {
     SendMessage(hwnd, CB_RESETCONTENT, WPARAM(0), LPARAM(0));
     for item in items {
         let mut text = format!("{}\0", item);
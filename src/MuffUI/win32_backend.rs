@@ -0,0 +1,364 @@
+#![allow(non_snake_case)]
+
+use std::time::Duration;
+use std::time::Instant;
+use crate::Win;
+use crate::muffui::*;
+
+impl From<Win::HWND> for Handle {
+    fn from(Win::HWND(hwnd): Win::HWND) -> Self {
+        Handle(hwnd)
+    }
+}
+
+impl From<Handle> for Win::HWND {
+    fn from(Handle(hwnd): Handle) -> Self {
+        Win::HWND(hwnd)
+    }
+}
+
+impl From<Win::RECT> for Rect {
+    fn from(rc: Win::RECT) -> Self {
+        Rect { left: rc.left, top: rc.top, right: rc.right, bottom: rc.bottom }
+    }
+}
+
+impl From<Rect> for Win::RECT {
+    fn from(rc: Rect) -> Self {
+        Win::RECT { left: rc.left, top: rc.top, right: rc.right, bottom: rc.bottom }
+    }
+}
+
+impl From<Win::MSG> for Message {
+    fn from(msg: Win::MSG) -> Self {
+        let Win::WPARAM(wParam) = msg.wParam;
+        let Win::LPARAM(lParam) = msg.lParam;
+        Message { hwnd: msg.hwnd.into(), message: msg.message, wParam, lParam }
+    }
+}
+
+impl From<Message> for Win::MSG {
+    fn from(msg: Message) -> Self {
+        Win::MSG {
+            hwnd: msg.hwnd.into(),
+            message: msg.message,
+            wParam: Win::WPARAM(msg.wParam),
+            lParam: Win::LPARAM(msg.lParam),
+            ..Default::default()
+        }
+    }
+}
+
+fn ScreenToClient(hwnd: Win::HWND, rect: &Win::RECT) -> Option<Win::RECT> {
+    let mut pt1 = Win::POINT { ..Default::default() };
+    let mut pt2 = Win::POINT { ..Default::default() };
+    pt1.x = rect.left;
+    pt1.y = rect.top;
+    pt2.x = rect.right;
+    pt2.y = rect.bottom;
+
+    let pt1 = Win::ScreenToClient(hwnd, &pt1)?;
+    let pt2 = Win::ScreenToClient(hwnd, &pt2)?;
+
+    Some(Win::RECT {
+        left: pt1.x,
+        top: pt1.y,
+        right: pt2.x,
+        bottom: pt2.y,
+    })
+}
+
+pub struct Win32Backend;
+
+impl Backend for Win32Backend {
+    fn initialize(&self) -> Result<(), String> {
+        Win::SetPerMonitorDpiAware();
+        Win::CoInitializeEx().map_err(|e|e.message().to_string_lossy())
+    }
+
+    fn systemFontFace(&self) -> String {
+        Win::GetSystemFontFace()
+    }
+
+    fn systemFontSize(&self) -> i32 {
+        Win::GetSystemFontSize()
+    }
+
+    fn systemDpi(&self) -> u32 {
+        Win::GetDpiForSystem()
+    }
+
+    fn getDpi(&self, hwnd: Handle) -> u32 {
+        Win::GetDpiForWindow(hwnd.into())
+    }
+
+    fn createControl(&self, parent: Option<Handle>, desc: &ControlDesc) -> Handle {
+        let style = match desc.renderer {
+            "window" => Win::WS_OVERLAPPEDWINDOW | Win::WS_VISIBLE,
+            "split" => Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_CLIPSIBLINGS | Win::WS_CLIPCHILDREN,
+            "panel" | "flex" | "grid" | "dock-panel" => Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_CLIPSIBLINGS | Win::WS_BORDER,
+            "scroll-view" | "virtual-list" => Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_CLIPSIBLINGS | Win::WS_CLIPCHILDREN | Win::WS_BORDER
+                | Win::WS_VSCROLL | Win::WS_HSCROLL,
+            "check-box" => (Win::BS_CHECKBOX as Win::WINDOW_STYLE) | Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE,
+            "group-box" => (Win::BS_GROUPBOX as Win::WINDOW_STYLE) | Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE,
+            "text-box" => Win::WS_CHILD | Win::WS_VISIBLE,
+            "label" => Win::WS_CHILD | Win::WS_VISIBLE,
+            "button" => Win::WS_TABSTOP | Win::WS_BORDER | Win::WS_CHILD | Win::WS_VISIBLE,
+            "radio-box" => (Win::BS_RADIOBUTTON as Win::WINDOW_STYLE) | Win::WS_TABSTOP | Win::WS_CHILD | Win::WS_VISIBLE,
+            "select0" => (Win::CBS_HASSTRINGS as Win::WINDOW_STYLE) | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_VSCROLL,
+            "select" => ((Win::CBS_DROPDOWN | Win::CBS_HASSTRINGS | Win::CBS_AUTOHSCROLL) as Win::WINDOW_STYLE) | Win::WS_CHILD | Win::WS_VISIBLE | Win::WS_VSCROLL,
+            "select2" => ((Win::CBS_DROPDOWNLIST | Win::BS_DEFSPLITBUTTON | Win::CBS_DROPDOWN | Win::CBS_HASSTRINGS) as Win::WINDOW_STYLE) | Win::WS_VISIBLE | Win::WS_CHILD | Win::WS_VSCROLL,  // Styles WS_VSCROLL | BS_DEFSPLITBUTTON WS_DISABLED |
+            "size-grip" => Win::WS_CHILD | Win::WS_VISIBLE | (Win::SBS_SIZEGRIP as Win::WINDOW_STYLE),
+            _ => Win::WS_BORDER,
+        };
+
+        let exStyle: Win::WINDOW_EX_STYLE = match desc.renderer {
+            "group-box" => Win::WS_EX_CONTROLPARENT,
+            //"panel" => Win::WS_EX_CLIENTEDGE,
+            "text-box" => Win::WS_EX_CLIENTEDGE,
+            _ => Default::default(),
+        };
+
+        let className = format!("{}\0", desc.className);
+        let res = Win::GetClassInfoEx(className.as_str());
+        if res.is_none() {
+            let atom = Win::RegisterClass(className.as_str());
+            debug_assert!(atom != 0);
+        }
+
+        let parent = parent.map(Win::HWND::from);
+        let hwnd = Win::CreateWindowEx(
+            exStyle, style, className.as_str(), parent, desc.controlId, desc.title,
+            desc.posX, desc.posY, desc.width, desc.height,
+            &(desc.anchor, desc.controlId, parent)
+        );
+        match desc.renderer {
+            "group-box" => {
+                Win::SetDefaultWindowProc(hwnd);
+            },
+            "size-grip" => unsafe {
+                Win::SetWindowPos(
+                    hwnd, Win::HWND_TOP,
+                    0,0,0,0,
+                    Win::SWP_NOMOVE | Win::SWP_NOACTIVATE | Win::SWP_NOSIZE
+                );
+            },
+            _ => (),
+        }
+
+        hwnd.into()
+    }
+
+    fn destroyControl(&self, hwnd: Handle) -> bool {
+        Win::DestroyWindow(hwnd.into())
+    }
+
+    fn getParent(&self, hwnd: Handle) -> Handle {
+        Win::GetParent(hwnd.into()).into()
+    }
+
+    fn getChildren(&self, hwnd: Handle) -> Vec<Handle> {
+        let mut children = vec![];
+        unsafe {
+            let mut child = Win::GetWindow(Win::HWND::from(hwnd), Win::GW_CHILD);
+            while child != Win::HWND(0) {
+                children.push(child.into());
+                child = Win::GetWindow(child, Win::GW_HWNDNEXT);
+            }
+        }
+
+        children
+    }
+
+    fn getControlId(&self, hwnd: Handle) -> i32 {
+        unsafe {
+            Win::GetDlgCtrlID(Win::HWND::from(hwnd))
+        }
+    }
+
+    fn getClassName(&self, hwnd: Handle) -> Option<String> {
+        Win::GetClassName(hwnd.into())
+    }
+
+    fn getTitle(&self, hwnd: Handle) -> String {
+        Win::GetWindowText(hwnd.into())
+    }
+
+    fn setTitle(&self, hwnd: Handle, title: &str) {
+        Win::SetWindowText(hwnd.into(), title);
+    }
+
+    fn updateFont(&self, hwnd: Handle, hFont: Option<Handle>, fontFace: &str, fontSize: i32) -> Option<Handle> {
+        let toString = |a:[Win::CHAR; 32]| String::from_iter(a.iter().filter(|Win::CHAR(a)|*a != 0).map(|Win::CHAR(a)|*a as char));
+        let hFont = hFont.map(|Handle(h)|Win::HFONT(h));
+        let hFont = hFont.and_then(Win::GetFont)
+            .filter(|f|toString(f.lfFaceName) == fontFace && f.lfHeight.abs() == fontSize).and(hFont)
+            .or_else(||{
+                Win::DeleteFont(hFont?);
+                None
+            });
+
+        hFont.or_else(||Win::SetWindowFontFace(hwnd.into(), fontFace, fontSize))
+            .map(|Win::HFONT(h)|Handle(h))
+    }
+
+    fn measureText(&self, text: &str, fontFace: &str, fontSize: i32) -> Size {
+        Win::MeasureText(text, fontFace, fontSize)
+            .map(|Win::SIZE { cx, cy }|Size { cx, cy })
+            .unwrap_or_default()
+    }
+
+    fn isSelected(&self, hwnd: Handle) -> bool {
+        Win::IsSelected(hwnd.into())
+    }
+
+    fn setSelected(&self, hwnd: Handle, selected: bool) {
+        Win::MarkSelected(hwnd.into(), selected);
+    }
+
+    fn getSelectItems(&self, hwnd: Handle) -> Vec<String> {
+        Win::SelectGetItems(hwnd.into())
+    }
+
+    fn setSelectItems(&self, hwnd: Handle, items: &[String]) {
+        Win::SelectSetItems(hwnd.into(), items)
+    }
+
+    fn getSelectedIndex(&self, hwnd: Handle) -> Option<usize> {
+        Win::SelectGetCurrentIndex(hwnd.into())
+    }
+
+    fn setSelectedIndex(&self, hwnd: Handle, index: usize) {
+        Win::SelectSetCurrentIndex(hwnd.into(), index);
+    }
+
+    fn getSelectItemText(&self, hwnd: Handle, index: usize) -> Option<String> {
+        Win::SelectGetItemText(hwnd.into(), index)
+    }
+
+    fn getWindowRect(&self, hwnd: Handle) -> Option<Rect> {
+        Win::GetWindowRect(hwnd.into()).map(Rect::from)
+    }
+
+    fn getClientRect(&self, hwnd: Handle) -> Option<Rect> {
+        Win::GetClientRect(hwnd.into()).map(Rect::from)
+    }
+
+    fn getControlRect(&self, hwnd: Handle) -> Option<Rect> {
+        let hwnd = Win::HWND::from(hwnd);
+        Win::GetWindowRect(hwnd)
+            .and_then(|rect|ScreenToClient(Win::GetParent(hwnd), &rect))
+            .map(Rect::from)
+    }
+
+    fn adjustWindowRect(&self, hwnd: Handle, rect: Rect) -> Rect {
+        let hwnd = Win::HWND::from(hwnd);
+        let mut rc = Win::RECT::from(rect);
+        unsafe {
+            let style = Win::GetWindowLong(hwnd, Win::GWL_STYLE);
+            let menu = Win::GetMenu(hwnd);
+            Win::AdjustWindowRect(&mut rc, style as Win::WINDOW_STYLE, menu != Win::HMENU(0));
+        }
+
+        rc.into()
+    }
+
+    fn moveControl(&self, hwnd: Handle, rect: &Rect) -> bool {
+        let posInfo = Win::BeginDeferWindowPos(1);
+        Win::DeferWindowPos(
+            posInfo, hwnd.into(), Win::HWND(0),
+            rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top,
+            Win::SWP_NOZORDER | Win::SWP_NOOWNERZORDER | Win::SWP_SHOWWINDOW
+        );
+
+        Win::EndDeferWindowPos(posInfo)
+    }
+
+    fn isMaximized(&self, hwnd: Handle) -> bool {
+        Win::GetWindowPlacement(hwnd.into())
+            .map(|wp|wp.showCmd == Win::SW_MAXIMIZE)
+            .unwrap_or(false)
+    }
+
+    fn isVisible(&self, hwnd: Handle) -> bool {
+        Win::IsWindowVisible(hwnd.into())
+    }
+
+    fn setVisible(&self, hwnd: Handle, visible: bool) {
+        Win::ShowWindow(hwnd.into(), if visible { Win::SW_SHOW } else { Win::SW_HIDE });
+    }
+
+    fn sizeGripSize(&self) -> Size {
+        unsafe {
+            Size {
+                cx: Win::GetSystemMetrics(Win::SM_CXVSCROLL),
+                cy: Win::GetSystemMetrics(Win::SM_CYHSCROLL),
+            }
+        }
+    }
+
+    fn setScrollInfo(&self, hwnd: Handle, content: Size, viewport: Size, offset: Point) {
+        Win::SetScrollBar(hwnd.into(), Win::SB_VERT, content.cy, viewport.cy, offset.y);
+        Win::SetScrollBar(hwnd.into(), Win::SB_HORZ, content.cx, viewport.cx, offset.x);
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        Win::GetModifiers()
+    }
+
+    fn drawOutlines(&self, hwnd: Handle, rects: &[Rect]) {
+        Win::FrameRects(hwnd.into(), &rects.iter().map(|rc|Win::RECT::from(*rc)).collect::<Vec<_>>());
+    }
+
+    fn postMessage(&self, msg: Message) {
+        if msg.message == WM_QUIT {
+            Win::PostQuitMessage(msg.wParam as i32);
+        } else {
+            unsafe {
+                Win::PostMessageA(Win::HWND::from(msg.hwnd), msg.message, Win::WPARAM(msg.wParam), Win::LPARAM(msg.lParam));
+            }
+        }
+    }
+
+    fn peekMessage(&self) -> Option<Message> {
+        Win::PeekMessage().map(Message::from)
+    }
+
+    fn waitMessage(&self, timeout: Option<Duration>) -> Option<Message> {
+        // messages sent by other threads are handled inside PeekMessage and don't come out of it
+        let deadline = timeout.map(|timeout|Instant::now() + timeout);
+        loop {
+            if let Some(msg) = self.peekMessage() {
+                return Some(msg);
+            }
+            let left = deadline.map(|deadline|deadline.saturating_duration_since(Instant::now()));
+            if left.map(|left|left.is_zero()).unwrap_or(false) || !Win::WaitMessage(left) {
+                return None;
+            }
+        }
+    }
+
+    fn dispatchMessage(&self, msg: &Message) {
+        let msg = Win::MSG::from(*msg);
+        // WM_MOUSELEAVE comes only while it is asked for
+        if msg.message == WM_MOUSEMOVE {
+            Win::TrackMouseLeave(msg.hwnd);
+        }
+
+        Win::TranslateMessage(&msg);
+        Win::DispatchMessage(&msg);
+    }
+
+    fn wakeHandle(&self) -> WakeHandle {
+        let threadId = Win::GetCurrentThreadId();
+        WakeHandle::new(move|msg|{
+            if msg.hwnd == Handle(0) {
+                Win::PostThreadMessage(threadId, msg.message, msg.wParam, msg.lParam);
+            } else {
+                unsafe {
+                    Win::PostMessageA(Win::HWND::from(msg.hwnd), msg.message, Win::WPARAM(msg.wParam), Win::LPARAM(msg.lParam));
+                }
+            }
+        })
+    }
+}
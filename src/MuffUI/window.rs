@@ -1,26 +1,15 @@
 #![allow(non_snake_case)]
 
-use std::sync::Arc;
 use std::rc::Rc;
-use std::sync::Mutex;
 use crate::muffui::*;
 use SharedProps::*;
-use crate::Win;
 
+#[derive(Default)]
 pub struct Window {
     pub children: Option<Rc<dyn Renderable>>,
     pub props: Vec<SharedProps>,
 }
 
-impl Default for Window {
-    fn default() -> Self {
-        Self {
-            children: Default::default(),
-            props: vec![],
-        }
-    }
-}
-
 impl Window {
     pub fn new<T: Into<Vec<SharedProps>>>(props: T) -> Self {
         let defaultProps = vec![
@@ -65,26 +54,21 @@ impl Window {
     #[allow(dead_code)]
     pub fn didResize<C: Into<Command<Vec<SharedProps>>>>(self, handler: C) -> Self {
         Self {
-            props: self.props.merge(SharedProps::DidResize(Arc::new(Mutex::new(handler.into())))),
+            props: self.props.merge(SP::DidResize(handler)),
+            ..self
+        }
+    }
+    #[allow(dead_code)]
+    pub fn onResize<C: Into<Command<ResizeEvent>>>(self, handler: C) -> Self {
+        Self {
+            props: self.props.merge(SP::OnResize(handler)),
             ..self
         }
     }
     #[allow(dead_code)]
-    pub fn content<A: 'static, B: 'static, C: 'static, D: 'static, E: 'static, F: 'static, G: 'static, T: 'static, FF>(self, mut children: FF) -> Self
-        where
-            A: Renderable,
-            B: Renderable,
-            C: Renderable,
-            D: Renderable,
-            E: Renderable,
-            F: Renderable,
-            G: Renderable,
-            T: Into<ContentArgs<A, B, C, D, E, F, G>>,
-            FF: FnMut() -> T
-    {
-        let args: ContentArgs<A, B, C, D, E, F, G> = children().into();
+    pub fn content<T: IntoChildren, FF: FnMut() -> T>(self, mut children: FF) -> Self {
         Self {
-            children: Some(Rc::new(args)),
+            children: Some(Rc::new(children().intoChildren())),
             ..self
         }
     }
@@ -95,7 +79,7 @@ impl Renderable for Window {
         Some(self.children.as_ref()?.clone())
     }
 
-    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Win::MSG>) -> Box<UIContext> {
+    fn render(&self, context: Box<UIContext>, parent: &str, index: &str, msg: Option<Message>) -> Box<UIContext> {
         let context = context.render(parent, index, self, msg);
         match &self.children {
             Some(children) => children.render(context, index, &format!("{}:1", index), msg),
@@ -104,12 +88,12 @@ impl Renderable for Window {
     }
 
     fn toViewState(&self) -> Vec<SharedProps> {
-        self.props.clone().merge([SP::DidResize({
-            let didResize = self.props.prop(&SP::DidResize(|_|{})).map(|d|d.clone());
-            move|props: Vec<SharedProps>|{
+        self.props.clone().merge([SP::OnResize({
+            let didResize = self.props.prop(&SP::DidResize(|_|{})).cloned();
+            move|event: ResizeEvent|{
                 let didResize = didResize.clone();
                 if let Some(SharedProps::DidResize(didResize)) = didResize {
-                    didResize.lock().unwrap().exec(props.clone());
+                    didResize.lock().unwrap().exec(event);
                 }
             }
         })])
@@ -1,24 +1,23 @@
 #![allow(non_snake_case)]
 #![warn(unused_variables)]
 
-use windows::{
-    core::*,
-};
-
 mod main_vm;
+#[path = "MuffUI/mod.rs"]
 mod muffui;
 
-use crate::muffui::win as Win;
 use std::rc::Rc;
-use std::sync::Arc;
-use std::cell::RefCell;
 use crate::muffui::*;
-use SharedProps::*;
 use crate::main_vm::MainViewModel;
 
 
 pub struct MyView {
-    vm: Arc<MainViewModel>,
+    vm: Rc<MainViewModel>,
+}
+
+impl Default for MyView {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MyView {
@@ -35,93 +34,76 @@ impl Renderable for MyView {
         let newTitle: &str = &self.vm.newTitle.borrow();
         let allChecked = self.vm.items.borrow().len() == self.vm.getCompleted();
 
-        Some(Rc::from(Window::new([
-            SP::ClassName("window#1"), ControlId(0), Anchor(ANF_DOCK_ALL), SP::Title("TODO: Example"), SP::FontFace("Monaco"),
-            Width(500), Height(310), SP::DidDestroy(|_|Win::PostQuitMessage(0))
-        ]).content(||(
-            Panel::new([SP::Title("create new todo"), ControlId(103), Anchor(ANF_DOCK_TOP)]).posX(0).posY(0).width(484).height(35).content(||(
-                CheckBox::new([SP::Title("Mark All"), ControlId(102), Anchor(ANF_TOP|ANF_LEFT), Selected(allChecked)]).posX(5).posY(5).width(70).height(25).content(Command::new({
-                    let vm = self.vm.clone();
-                    move|event: Vec<SharedProps>| {
-                        if let Some(checked) = event.prop(&SharedProps::Selected(true)) {
-                            vm.completeAll();
-                        }
-                    }
-                }))
-                ,
-                Label::new([SP::Title("New todo title:")]).posX(76).posY(9).width(120).height(25)
-                ,
-                TextBox::new([ControlId(201), Anchor(ANF_TOP| ANF_LEFTRIGHT)]).title(newTitle).posX(170).posY(6).width(260).height(21).content({
-                    let newTitle = Rc::clone(&self.vm.newTitle);
-                    move|event: Vec<SharedProps>|{
-                        let mut newTitle = newTitle.borrow_mut();
-                        if let Some(Title(v)) = event.prop(&SP::Title("")) {
-                            *newTitle = v.to_string();
+        Some(Rc::from(view! {
+            Window(ClassName: "window#1", ControlId: 0, Anchor: ANF_DOCK_ALL, Title: "TODO: Example", FontFace: "Monaco",
+                Width: 500, Height: 310, DidDestroy: |_|PostQuitMessage(0)) {
+                Panel(Title: "create new todo", ControlId: 103, Anchor: ANF_DOCK_TOP, PosX: 0, PosY: 0, Width: 484, Height: 35) {
+                    CheckBox(Title: "Mark All", ControlId: 102, Anchor: ANF_TOP|ANF_LEFT, Selected: allChecked, PosX: 5, PosY: 5,
+                        OnClick: {
+                            let vm = self.vm.clone();
+                            move|_|vm.completeAll()
+                        }),
+                    Label(Title: "New todo title:", PosX: 76, PosY: 9),
+                    TextBox(ControlId: 201, Anchor: ANF_TOP|ANF_LEFTRIGHT, Title: newTitle, PosX: 170, PosY: 6, Width: 260, Height: 21,
+                        OnChange: {
+                            let newTitle = Rc::clone(&self.vm.newTitle);
+                            move|event: ChangeEvent|*newTitle.borrow_mut() = event.text
+                        }),
+                    Button(Title: "Save", ControlId: 202, Anchor: ANF_TOP|ANF_RIGHT, PosX: 436, PosY: 4, Width: 40, Height: 24,
+                        OnClick: {
+                            let vm = self.vm.clone();
+                            move|_|vm.createToDo()
+                        }),
+                },
+                ScrollView(ControlId: 203, Anchor: ANF_TOPBOTTOM|ANF_LEFTRIGHT, PosX: 4, PosY: 40, Width: 475, Height: 200,
+                    Padding: 2, Spacing: 2, AlignItems: Align::Stretch) {
+                    for ((id, name, isFinished) in self.vm.getItems(), key = |(id, _, _)|*id) {
+                        Panel(ControlId: 1000 + 4 * id as i32, Height: 27) {
+                            CheckBox(Selected: isFinished, Title: "Done", ControlId: 1001 + 4 * id as i32, Anchor: ANF_TOP|ANF_LEFT, PosX: 4, Width: 50, PosY: 1, Height: 24,
+                                OnClick: {
+                                    let vm = self.vm.clone();
+                                    let name = name.clone();
+                                    move|_|vm.updateToDo((id, name.clone(), !isFinished))
+                                }),
+                            TextBox(Title: name.as_str(), ControlId: 1002 + 4 * id as i32, Anchor: ANF_TOP|ANF_LEFTRIGHT, PosX: 55, PosY: 1, Width: 350, Height: 24,
+                                OnChange: {
+                                    let vm = self.vm.clone();
+                                    move|event: ChangeEvent|vm.updateToDo((id, event.text, isFinished))
+                                }),
+                            Button(Title: "Remove", ControlId: 1003 + 4 * id as i32, Anchor: ANF_TOP|ANF_RIGHT, PosX: 406, PosY: 1, Width: 60, Height: 22,
+                                OnClick: {
+                                    let vm = self.vm.clone();
+                                    move|_|vm.removeToDo(id)
+                                }),
                         }
                     }
-                })
-                ,
-                Button::new([SP::Title("Save"), ControlId(202), Anchor(ANF_TOP|ANF_RIGHT)]).posX(436).posY(4).width(40).height(24).content({
-                    let vm = self.vm.clone();
-                    move|_|vm.createToDo()
-                })
-                ,
-            ))
-            ,
-            Panel::new([ControlId(203), Anchor(ANF_TOPBOTTOM|ANF_LEFTRIGHT)]).posX(4).posY(40).width(475).height(200).content(||
-                ForEach::new(self.vm.getItems().iter().map(|i|i.clone()).collect::<Vec<_>>(), |(id, name, isFinished), index|(
-                    Panel::new([ControlId(300 + index), Anchor(ANF_LEFT|ANF_TOP|ANF_DOCK_RIGHT_EX)]).posX(4).posY(2 + index * 29).height(27).width(400).content(||(
-                        CheckBox::new([Selected(isFinished), SP::Title("Done"), ControlId(400 + index), Anchor(ANF_TOP|ANF_LEFT)]).posX(4).width(50).posY(1).height(24).content({
+                },
+                Panel(Title: "testing title", ControlId: 104, Anchor: ANF_DOCK_BOTTOM, PosX: 0, PosY: 243, Width: 484, Height: 28) {
+                    Label(Title: format!("{} item left", self.vm.getCompleted()).as_str(), ControlId: 105, Anchor: ANF_TOP|ANF_LEFT, FontFace: "Monaco",
+                        PosX: 5, PosY: 5, Width: 125, Height: 25),
+                    RadioBox(Title: "All", ControlId: 110, Anchor: ANF_TOP|ANF_LEFT, Selected: *showAll == 0, PosX: 130, PosY: 2, Width: 45, Height: 25,
+                        OnClick: {
+                            let vm = self.vm.clone();
+                            move|_|vm.setShowAll(0)
+                        }),
+                    RadioBox(Title: "Active", ControlId: 111, Anchor: ANF_TOP|ANF_LEFT, Selected: *showAll == 1, PosX: 175, PosY: 2, Width: 65, Height: 25,
+                        OnClick: {
                             let vm = self.vm.clone();
-                            let name = name.clone();
-                            move|args: Vec<SharedProps>|{
-                                vm.updateToDo((id, name.clone(), !isFinished));
-                        }})
-                        ,
-                        TextBox::new([SP::Title(name.as_str()), ControlId(400 + 2 * index), Anchor(ANF_TOP|ANF_LEFTRIGHT)]).posX(55).posY(1).width(350).height(24).content({
+                            move|_|vm.setShowAll(1)
+                        }),
+                    RadioBox(Title: "Completed", ControlId: 111, Anchor: ANF_TOP|ANF_LEFT, Selected: *showAll == 2, PosX: 240, PosY: 2, Width: 85, Height: 25,
+                        OnClick: {
                             let vm = self.vm.clone();
-                            move|args: Vec<SharedProps>|{
-                                if let Some(Title(title)) = args.prop(&SP::Title("")) {
-                                    vm.updateToDo((id, title.to_string(), isFinished));
-                                }
-                            }
-                        })
-                        ,
-                        Button::new([SP::Title("Remove"), ControlId(400 + 3 * index), Anchor(ANF_TOP|ANF_RIGHT)]).posX(406).posY(1).width(60).height(22).content({
+                            move|_|vm.setShowAll(2)
+                        }),
+                    Button(Title: "Clear Completed", ControlId: 112, Anchor: ANF_TOP|ANF_RIGHT, PosX: 360, PosY: 2, Width: 118, Height: 23,
+                        OnClick: {
                             let vm = self.vm.clone();
-                            move|_|vm.removeToDo(id)
-                        })
-                        ,
-                    ))
-                ))
-            )
-            ,
-            Panel::new([SP::Title("testing title"), ControlId(104), Anchor(ANF_DOCK_BOTTOM)]).posX(0).posY(243).width(484).height(28).content(||(
-                Label::new([SP::Title(format!("{} item left", self.vm.getCompleted()).as_str()), ControlId(105), Anchor(ANF_TOP|ANF_LEFT), SP::FontFace("Monaco")]).posX(5).posY(5).width(125).height(25)
-                ,
-                RadioBox::new([SP::Title("All"), ControlId(110), Anchor(ANF_TOP|ANF_LEFT), Selected(*showAll == 0)]).posX(130).posY(2).width(45).height(25).content({
-                    let vm = self.vm.clone();
-                    move|_|vm.setShowAll(0)
-                })
-                ,
-                RadioBox::new([SP::Title("Active"), ControlId(111), Anchor(ANF_TOP|ANF_LEFT), Selected(*showAll == 1)]).posX(175).posY(2).width(65).height(25).content({
-                    let vm = self.vm.clone();
-                    move|_|vm.setShowAll(1)
-                })
-                ,
-                RadioBox::new([SP::Title("Completed"), ControlId(111), Anchor(ANF_TOP|ANF_LEFT), Selected(*showAll == 2)]).posX(240).posY(2).width(85).height(25).content({
-                    let vm = self.vm.clone();
-                    move|_|vm.setShowAll(2)
-                })
-                ,
-                Button::new([SP::Title("Clear Completed"), ControlId(112), Anchor(ANF_TOP|ANF_RIGHT)]).posX(360).posY(2).width(118).height(23).content({
-                    let vm = self.vm.clone();
-                    move|_|vm.clearCompleted()
-                })
-                ,
-            ))
-            ,
-        ))))
+                            move|_|vm.clearCompleted()
+                        }),
+                },
+            }
+        }))
     }
 }
 
@@ -132,11 +114,235 @@ impl MainApp for App {
     }
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<(), String> {
 
     let app = App::shared();
 
-    app.lock()
-        .and_then(|a|Ok(a)).as_mut().map(|a|a.run()).map(|_|())
-        .or_else(|_|Err(Error::new(Win::HRESULT(1), "locking app mutex failed".into())))
+    app.lock().as_mut().map(|a|a.run()).map(|_|()).map_err(|_|String::from("locking app mutex failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn mount() -> (App, Rc<HeadlessBackend>) {
+        let backend = Rc::new(HeadlessBackend::new());
+        let mut app = App::withBackend(backend.clone());
+        assert_eq!(app.mount(), Ok(true));
+
+        (app, backend)
+    }
+
+    fn find(backend: &HeadlessBackend, controlId: i32) -> HeadlessNode {
+        backend.nodes().into_iter().find(|n|n.controlId == controlId).unwrap()
+    }
+
+    #[test]
+    fn it_renders_todo_view() {
+        let (_app, backend) = mount();
+
+        let window = backend.node(backend.roots()[0]).unwrap();
+        assert_eq!(window.title, "TODO: Example");
+        assert_eq!(window.fontFace, "Monaco");
+        assert_eq!(find(&backend, 105).title, "0 item left");
+        assert_eq!(find(&backend, 202).rect, Rect { left: 436, top: 4, right: 476, bottom: 28 });
+    }
+
+    #[test]
+    fn it_queries_todo_view_with_driver() {
+        let driver = TestDriver::mount().unwrap();
+
+        let window = driver.get(By::Path("0"));
+        assert_eq!(window.title, "TODO: Example");
+        assert_eq!(window.fontFace, "Monaco");
+        assert_eq!(driver.get(By::ControlId(105)).title, "0 item left");
+        assert_eq!(driver.get(By::Title("Save")).rect, Rect { left: 436, top: 4, right: 476, bottom: 28 });
+        assert_eq!(driver.findAll(By::Renderer("radio-box")).len(), 3);
+    }
+
+    #[test]
+    fn it_matches_todo_view_snapshot() {
+        let mut driver = TestDriver::mount().unwrap();
+        driver.typeText(By::ControlId(201), "milk");
+        driver.click(By::Title("Save"));
+        driver.click(By::Title("Done"));
+
+        assertSnapshot("todo_view", &driver.app.snapshot());
+    }
+
+    #[test]
+    fn it_adds_todo_on_save() {
+        let (mut app, backend) = mount();
+
+        let input = find(&backend, 201);
+        backend.setTitle(input.hwnd, "milk");
+        backend.postMessage(Message { hwnd: input.hwnd, message: WM_CHAR, ..Default::default() });
+        backend.postMessage(Message { hwnd: find(&backend, 202).hwnd, message: BM_CLICK, ..Default::default() });
+        assert!(app.pumpMessages());
+
+        assert_eq!(find(&backend, 201).title, "");
+        assert!(backend.nodes().iter().any(|n|n.renderer == "text-box" && n.title == "milk"));
+    }
+
+    #[test]
+    fn it_adds_todo_by_typing_and_clicking() {
+        let mut driver = TestDriver::mount().unwrap();
+
+        assert!(driver.typeText(By::ControlId(201), "milk"));
+        assert!(driver.click(By::Title("Save")));
+
+        assert_eq!(driver.get(By::ControlId(201)).title, "");
+        let row = driver.get(By::Title("milk"));
+        assert_eq!(row.renderer, "text-box");
+        assert!(driver.path(row.parent).unwrap().starts_with("0:1_2:"));
+        assert!(driver.find(By::Title("Remove")).is_some());
+    }
+
+    #[test]
+    fn it_removes_todo() {
+        let mut driver = TestDriver::mount().unwrap();
+        driver.typeText(By::ControlId(201), "milk");
+        driver.click(By::Title("Save"));
+
+        assert!(driver.click(By::Title("Remove")));
+
+        assert!(driver.find(By::Title("milk")).is_none());
+        assert!(driver.find(By::Title("Remove")).is_none());
+    }
+
+    #[test]
+    fn it_keeps_row_controls_when_earlier_row_is_removed() {
+        let mut driver = TestDriver::mount().unwrap();
+        for title in ["milk", "bread"] {
+            driver.typeText(By::ControlId(201), title);
+            driver.click(By::Title("Save"));
+        }
+        let bread = driver.get(By::Title("bread"));
+        let breadRow = driver.backend.node(bread.parent).unwrap();
+        assert_eq!(breadRow.rect.top, 31);
+        assert_eq!((breadRow.controlId, bread.controlId), (1008, 1010));
+
+        driver.click(By::Title("Remove"));
+
+        assert!(driver.find(By::Title("milk")).is_none());
+        assert_eq!(driver.get(By::Title("bread")).hwnd, bread.hwnd);
+        assert_eq!(driver.backend.node(breadRow.hwnd).unwrap().rect.top, 2);
+        // the ids come from the todo, not from the row it is shown in
+        assert_eq!(driver.backend.node(breadRow.hwnd).unwrap().controlId, 1008);
+        assert_eq!(driver.get(By::Title("bread")).controlId, 1010);
+        assert_eq!(driver.get(By::Title("Remove")).controlId, 1011);
+        assert_eq!(driver.findAll(By::Title("Remove")).len(), 1);
+    }
+
+    #[test]
+    fn it_scrolls_rows_that_dont_fit() {
+        let mut driver = TestDriver::mount().unwrap();
+        for index in 0..9 {
+            driver.typeText(By::ControlId(201), &format!("todo {}", index));
+            driver.click(By::Title("Save"));
+        }
+        let row = |title: &str| driver.backend.node(driver.get(By::Title(title)).parent).unwrap().rect;
+        assert_eq!(row("todo 8").bottom, 261);
+
+        assert!(driver.scroll(By::Title("todo 4"), 1));
+
+        let row = |title: &str| driver.backend.node(driver.get(By::Title(title)).parent).unwrap().rect;
+        assert_eq!(row("todo 0").top, -58);
+        assert_eq!(row("todo 8").bottom, 201);
+    }
+
+    #[test]
+    fn it_replays_recorded_session() {
+        let state = || {
+            let vm = MainViewModel::shared();
+            let items = vm.items.borrow().clone();
+            let showAll = *vm.showAll.borrow();
+            (items, showAll)
+        };
+        let mut driver = TestDriver::mount().unwrap();
+        EventRecorder::enable();
+        for title in ["milk", "bread"] {
+            driver.typeText(By::ControlId(201), title);
+            driver.click(By::Title("Save"));
+        }
+        driver.click(By::Title("Done"));
+        driver.click(By::Title("Active"));
+        let json = EventRecorder::toJson();
+        EventRecorder::disable();
+        let events = EventRecorder::parse(&json).unwrap();
+        assert_eq!(events.iter().map(|e|e.kind.as_str()).collect::<Vec<_>>(),
+            vec!["DidChange", "DidClick", "DidChange", "DidClick", "DidClick", "DidClick"]);
+        assert_eq!(events[2].payload, EventPayload::Change { text: String::from("bread"), selectedIndex: None });
+
+        // a fresh thread has a fresh view model and counts the ids of the todos from the start again
+        let replayed = std::thread::spawn(move||{
+            let mut driver = TestDriver::mount().unwrap();
+            assert_eq!(driver.replay(&events), Ok(true));
+            state()
+        }).join().unwrap();
+
+        assert_eq!(replayed, state());
+        assert_eq!(replayed.1, 1);
+    }
+
+    #[test]
+    fn it_quits_when_window_is_closed() {
+        let (mut app, backend) = mount();
+
+        backend.close(backend.roots()[0]);
+
+        assert!(!app.pumpMessages());
+        assert!(backend.nodes().is_empty());
+    }
+
+    #[test]
+    fn it_settles_when_driven_window_is_closed() {
+        let mut driver = TestDriver::mount().unwrap();
+
+        driver.backend.close(driver.get(By::Path("0")).hwnd);
+
+        assert!(!driver.settle());
+        assert!(driver.backend.nodes().is_empty());
+    }
+
+    #[test]
+    fn it_sleeps_until_timer_or_wake_handle() {
+        use std::sync::mpsc::channel;
+
+        let backend = Rc::new(HeadlessBackend::new());
+        assert_eq!(backend.waitMessage(Some(std::time::Duration::ZERO)), None);
+
+        // the worker wakes the loop only when it is told to, so the wait returns because of the wake
+        let (wake, woken) = channel::<()>();
+        let handle = backend.wakeHandle();
+        let worker = std::thread::spawn(move||{
+            woken.recv().unwrap();
+            handle.wake();
+        });
+        wake.send(()).unwrap();
+        assert_eq!(backend.waitMessage(None).map(|msg|msg.message), Some(WM_WAKE));
+        worker.join().unwrap();
+
+        // the timer tells the worker to quit, so the loop ends after the timer fired
+        let mut app = App::withBackend(backend.clone());
+        let fired = Rc::new(Cell::new(0));
+        let (quit, quitting) = channel::<()>();
+        let counter = fired.clone();
+        app.setTimeout(std::time::Duration::ZERO, move||{
+            counter.set(counter.get() + 1);
+            quit.send(()).unwrap();
+        });
+        let handle = app.wakeHandle();
+        let worker = std::thread::spawn(move||{
+            quitting.recv().unwrap();
+            handle.wake();
+            handle.quit(0);
+        });
+
+        assert_eq!(app.run(), Ok(()));
+        worker.join().unwrap();
+        assert_eq!(fired.get(), 1);
+        assert_eq!(backend.peekMessage(), None);
+    }
 }
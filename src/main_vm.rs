@@ -1,14 +1,12 @@
-use std::sync::Arc;
 use std::rc::Rc;
-use std::cell::RefCell;
-use std::sync::Once;
-use std::mem::MaybeUninit;
-use crate::muffui::utils;
+use std::cell::{Cell, RefCell};
 
 pub struct MainViewModel {
     pub newTitle: Rc<RefCell<String>>,
     pub items: Rc<RefCell<Vec<(usize, String, bool)>>>,
     pub showAll: Rc<RefCell<usize>>,
+    /// id of the last todo created, ids count from 1 in every view model
+    lastId: Cell<usize>,
 }
 
 impl MainViewModel {
@@ -17,18 +15,16 @@ impl MainViewModel {
             newTitle: Rc::from(RefCell::new(String::from(""))),
             items: Rc::from(RefCell::new(vec![])),
             showAll: Rc::new(RefCell::new(0)),
+            lastId: Cell::new(0),
         }
     }
 
-    pub fn shared() -> Arc<MainViewModel> {
-        static mut CONF: MaybeUninit<Arc<MainViewModel>> = MaybeUninit::uninit();
-        static ONCE: Once = Once::new();
-    
-        ONCE.call_once(|| unsafe {
-            CONF.as_mut_ptr().write(Arc::new(MainViewModel::new()));
-        });
+    pub fn shared() -> Rc<MainViewModel> {
+        thread_local! {
+            static CONF: Rc<MainViewModel> = Rc::new(MainViewModel::new());
+        }
 
-        unsafe { (*CONF.as_ptr()).clone() }
+        CONF.with(|conf|conf.clone())
     }
 
     pub fn setShowAll(&self, val: usize) {
@@ -61,14 +57,15 @@ impl MainViewModel {
 
     pub fn createToDo(&self) {
         let mut items = self.items.borrow_mut();
-        let id = utils::uniqId();
+        let id = self.lastId.get() + 1;
+        self.lastId.set(id);
         items.push((id, String::from(&*self.newTitle.borrow()), false));
         *self.newTitle.borrow_mut() = String::from("");
     }
 
     pub fn updateToDo(&self, props: (usize, String, bool)) {
         let (id, name, isFinished) = props;
-        if let Some(mut item) = self.items.borrow_mut().iter_mut().find(|i|i.0 == id) {
+        if let Some(item) = self.items.borrow_mut().iter_mut().find(|i|i.0 == id) {
             item.1 = name;
             item.2 = isFinished;
         }
@@ -94,7 +91,7 @@ impl MainViewModel {
     }
 
     pub fn completeAll(&self) {
-        let incomplete = self.items.borrow().iter().filter(|(_, _, done)|!*done).map(|i|i.clone()).collect::<Vec<_>>();
+        let incomplete = self.items.borrow().iter().filter(|(_, _, done)|!*done).cloned().collect::<Vec<_>>();
         for (id, name, _) in incomplete {
             self.updateToDo((id, name, true));
         }